@@ -0,0 +1,157 @@
+use std::io;
+use std::path::Path;
+
+use futures_util::future::join_all;
+
+use crate::download::{DownloadRequest, DownloaderService, JobStatus};
+use crate::error::DownloadError;
+
+/// Requests read from `*.txt` manifests, one URL per line.
+#[derive(Debug, Clone)]
+pub struct BatchJob {
+    requests: Vec<DownloadRequest>,
+}
+
+#[derive(Debug)]
+pub struct BatchJobResult {
+    pub total: usize,
+    pub succeeded: usize,
+    /// URLs that could not be queued or whose download did not succeed.
+    pub failed: Vec<(String, DownloadError)>,
+}
+
+impl BatchJob {
+    /// Read every `*.txt` file in `dir` (not recursively), in file name order.
+    /// Blank lines and lines starting with `#` are ignored; each other line
+    /// becomes a copy of `base_request` with that URL and a fresh job ID,
+    /// named after its title rather than `output_filename`.
+    pub fn from_dir(dir: &Path, base_request: DownloadRequest) -> Result<BatchJob, io::Error> {
+        let mut manifests = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "txt") {
+                manifests.push(path);
+            }
+        }
+        manifests.sort();
+
+        let mut requests = Vec::new();
+        for manifest in manifests {
+            let content = std::fs::read_to_string(&manifest)?;
+            for line in content.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let mut request = base_request.clone();
+                request.url = line.to_string();
+                request.id = None;
+                request.output_filename = None;
+                requests.push(request);
+            }
+        }
+        Ok(BatchJob { requests })
+    }
+
+    pub fn requests(&self) -> &[DownloadRequest] {
+        &self.requests
+    }
+
+    /// Queue every request and wait for all of them to finish. Downloads run
+    /// within the service's concurrency limit.
+    pub async fn run(&self, service: &DownloaderService) -> BatchJobResult {
+        let mut failed = Vec::new();
+        let mut handles = Vec::new();
+        for request in &self.requests {
+            match service.queue(request.clone()).await {
+                Ok(handle) => handles.push(handle),
+                Err(error) => failed.push((request.url.clone(), error)),
+            }
+        }
+
+        let mut succeeded = 0;
+        for summary in join_all(handles.into_iter().map(|handle| handle.wait())).await {
+            match summary.status {
+                JobStatus::Succeeded => succeeded += 1,
+                JobStatus::Canceled => failed.push((summary.url, DownloadError::Canceled)),
+                _ => {
+                    let message = summary
+                        .error_message
+                        .unwrap_or_else(|| "download failed".to_string());
+                    failed.push((summary.url, DownloadError::JobFailed(message)));
+                }
+            }
+        }
+
+        BatchJobResult {
+            total: self.requests.len(),
+            succeeded,
+            failed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::config::AudioFormat;
+
+    fn base_request() -> DownloadRequest {
+        DownloadRequest::new(String::new(), PathBuf::new(), AudioFormat::Opus)
+    }
+
+    #[test]
+    fn from_dir_reads_txt_manifests_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("b.txt"),
+            "https://x.com/i/spaces/2\n\n# skipped\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("a.txt"), "  https://x.com/i/spaces/1  \n").unwrap();
+        std::fs::write(dir.path().join("notes.md"), "https://x.com/i/spaces/3\n").unwrap();
+
+        let batch = BatchJob::from_dir(dir.path(), base_request()).unwrap();
+        let urls: Vec<_> = batch.requests().iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            ["https://x.com/i/spaces/1", "https://x.com/i/spaces/2"]
+        );
+        assert!(batch
+            .requests()
+            .iter()
+            .all(|request| request.format == AudioFormat::Opus));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_counts_successes_and_failures() {
+        use crate::config::Config;
+        use crate::testing::MockYtDlp;
+
+        let dir = tempfile::tempdir().unwrap();
+        let yt_dlp =
+            MockYtDlp::immediate_success(dir.path().join("out").join("Space.m4a")).unwrap();
+        let manifests = dir.path().join("manifests");
+        std::fs::create_dir(&manifests).unwrap();
+        std::fs::write(
+            manifests.join("list.txt"),
+            "https://x.com/i/spaces/1\nnot a url\n",
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().join("out");
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        let service = DownloaderService::new_without_history(config);
+
+        let batch = BatchJob::from_dir(&manifests, base_request()).unwrap();
+        let result = batch.run(&service).await;
+        assert_eq!(result.total, 2);
+        assert_eq!(result.succeeded, 1);
+        assert_eq!(result.failed.len(), 1);
+        assert_eq!(result.failed[0].0, "not a url");
+        assert!(matches!(result.failed[0].1, DownloadError::InvalidUrl(_)));
+    }
+}
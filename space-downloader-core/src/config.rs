@@ -1,11 +1,21 @@
+use std::ffi::OsStr;
 use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime};
 
 use directories::ProjectDirs;
 use once_cell::sync::Lazy;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 use crate::error::ConfigError;
+use crate::sponsorblock::{self, SponsorBlockCategory};
+
+/// ISO 3166-1 alpha-2 country codes as accepted by `--geo-bypass-country`.
+static COUNTRY_CODE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Z]{2}$").expect("valid regex"));
 
 static DEFAULT_PROJECT_DIRS: Lazy<Option<ProjectDirs>> = Lazy::new(|| {
     #[cfg(target_os = "macos")]
@@ -27,6 +37,30 @@ static DEFAULT_PROJECT_DIRS: Lazy<Option<ProjectDirs>> = Lazy::new(|| {
 
 pub const CONFIG_RELATIVE_PATH: &str = "space_downloader.toml";
 
+/// Upper bound on simultaneous downloads, regardless of the configured value.
+pub const MAX_CONCURRENCY: usize = 3;
+
+/// Default [`LogSettings::file_name_prefix`].
+pub const DEFAULT_LOG_FILE_PREFIX: &str = "space_downloader";
+
+/// Default [`LogSettings::max_files`].
+pub const DEFAULT_MAX_LOG_FILES: u8 = 7;
+
+/// yt-dlp flags that [`AdvancedSettings::force_overwrites`] overrides.
+const OVERWRITE_FLAGS: &[&str] = &["-w", "--no-overwrites", "--no-force-overwrites"];
+
+/// Browser targets accepted by yt-dlp's `--impersonate`.
+pub const IMPERSONATE_TARGETS: &[&str] = &["chrome", "firefox", "safari", "chrome-android"];
+
+/// yt-dlp's own `--fragment-retries` default.
+pub const DEFAULT_FRAGMENT_RETRIES: u32 = 10;
+
+/// Upper bound accepted for [`AdvancedSettings::fragment_retries`].
+pub const MAX_FRAGMENT_RETRIES: u32 = 100;
+
+/// Characters rejected in values that are passed through to yt-dlp verbatim.
+const SHELL_METACHARACTERS: &[char] = &[';', '|', '&', '$', '`', '<', '>', '\n'];
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     #[serde(default)]
@@ -40,7 +74,23 @@ pub struct Config {
 }
 
 impl Config {
+    /// Load the config at `path` (or [`Config::default_path`]), writing the
+    /// defaults there if it does not exist. Invalid values are reset to their
+    /// defaults and logged; use [`Config::load_with_warnings`] to report them.
     pub fn load_or_default(path: Option<&Path>) -> Result<(Self, PathBuf), ConfigError> {
+        let (config, path, warnings) = Self::load_with_warnings(path)?;
+        for warning in warnings {
+            warn!("{warning}; using the default");
+        }
+        Ok((config, path))
+    }
+
+    /// Like [`Config::load_or_default`], but also returns the validation
+    /// errors of the values that were reset to their defaults, so the caller
+    /// can show them. Only unreadable or unparsable files fail the load.
+    pub fn load_with_warnings(
+        path: Option<&Path>,
+    ) -> Result<(Self, PathBuf, Vec<ConfigError>), ConfigError> {
         let resolved_path = path
             .map(|p| p.to_path_buf())
             .unwrap_or_else(default_config_path);
@@ -50,12 +100,14 @@ impl Config {
                 path: resolved_path.clone(),
                 source,
             })?;
-            let config =
+            let mut config =
                 toml::from_str::<Config>(&content).map_err(|source| ConfigError::Parse {
                     path: resolved_path.clone(),
                     source,
                 })?;
-            Ok((config, resolved_path))
+            config.merge_with_defaults();
+            let warnings = config.reset_invalid_fields();
+            Ok((config, resolved_path, warnings))
         } else {
             if let Some(parent) = resolved_path.parent() {
                 fs::create_dir_all(parent).map_err(|source| ConfigError::Io {
@@ -65,12 +117,52 @@ impl Config {
             }
             let config = Config::default();
             config.save(&resolved_path)?;
-            Ok((config, resolved_path))
+            Ok((config, resolved_path, Vec::new()))
         }
     }
 
+    /// Where [`Config::load_or_default`] looks when no path is given.
+    pub fn default_path() -> PathBuf {
+        default_config_path()
+    }
+
+    /// Open the config file at `path` in a text editor and wait until the
+    /// user is done with it.
+    ///
+    /// `$VISUAL` or `$EDITOR` is used when set (except on Windows); otherwise
+    /// the platform's default editor opens the file. Most editors are waited
+    /// for until they exit, but `xdg-open` returns right away, so on Linux
+    /// without an editor variable this waits for the file to be saved, for at
+    /// most [`EDITOR_WATCH_TIMEOUT`]. Call [`Config::load_or_default`]
+    /// afterwards to pick up the edits.
+    pub fn open_in_editor(path: &Path) -> io::Result<()> {
+        let editor = std::env::var_os("VISUAL")
+            .filter(|visual| !visual.is_empty())
+            .or_else(|| std::env::var_os("EDITOR"));
+        let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        let (mut command, waits) = editor_command(std::env::consts::OS, editor.as_deref(), path);
+        let status = command.status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("editor exited with {status}")));
+        }
+        if !waits {
+            wait_for_modification(path, modified, EDITOR_WATCH_TIMEOUT);
+        }
+        Ok(())
+    }
+
+    /// Write the config to `path`; see [`Config::save_atomic`].
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ConfigError> {
-        let path = path.as_ref();
+        self.save_atomic(path.as_ref())
+    }
+
+    /// Write the config next to `path` as `<name>.toml.tmp`, sync it to disk
+    /// and rename it over `path`, so a crash mid-write leaves either the old
+    /// or the new file but never a truncated one.
+    ///
+    /// `std::fs::rename` replaces an existing destination on Windows as well
+    /// (`MoveFileExW` with `MOVEFILE_REPLACE_EXISTING`).
+    pub fn save_atomic(&self, path: &Path) -> Result<(), ConfigError> {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).map_err(|source| ConfigError::Io {
                 path: parent.to_path_buf(),
@@ -80,12 +172,261 @@ impl Config {
 
         let serialized =
             toml::to_string_pretty(self).map_err(|source| ConfigError::Serialize { source })?;
-        fs::write(path, serialized).map_err(|source| ConfigError::Io {
-            path: path.to_path_buf(),
-            source,
+        let tmp_path = path.with_extension("toml.tmp");
+        let written = fs::File::create(&tmp_path).and_then(|mut file| {
+            file.write_all(serialized.as_bytes())?;
+            file.flush()?;
+            file.sync_all()
+        });
+        if let Err(source) = written {
+            fs::remove_file(&tmp_path).ok();
+            return Err(ConfigError::Io {
+                path: tmp_path,
+                source,
+            });
+        }
+        fs::rename(&tmp_path, path).map_err(|source| {
+            fs::remove_file(&tmp_path).ok();
+            ConfigError::Io {
+                path: path.to_path_buf(),
+                source,
+            }
         })
     }
 
+    /// Replace empty or zero values that have no meaning with their defaults,
+    /// leaving every value the user actually set untouched.
+    ///
+    /// Fields missing from the file already get their defaults during
+    /// deserialization; this additionally repairs values such as an empty
+    /// output directory or a concurrency of zero left by older versions.
+    pub fn merge_with_defaults(&mut self) {
+        let general = GeneralSettings::default();
+        if self.general.output_dir.as_os_str().is_empty() {
+            self.general.output_dir = general.output_dir;
+        }
+        if self.general.language.trim().is_empty() {
+            self.general.language = general.language;
+        }
+
+        if self.download.concurrency == 0 {
+            self.download.concurrency = DownloadSettings::default().concurrency;
+        }
+
+        if self.advanced.yt_dlp_path.as_os_str().is_empty() {
+            self.advanced.yt_dlp_path = AdvancedSettings::default().yt_dlp_path;
+        }
+    }
+
+    /// Reset each value [`Config::validate`] rejects to its default, returning
+    /// the errors in the order they were fixed.
+    fn reset_invalid_fields(&mut self) -> Vec<ConfigError> {
+        let mut errors = Vec::new();
+        while let Err(error) = self.validate() {
+            let ConfigError::Invalid { field, .. } = &error else {
+                unreachable!("validate only returns ConfigError::Invalid");
+            };
+            let (download, advanced, logging) = (
+                DownloadSettings::default(),
+                AdvancedSettings::default(),
+                LogSettings::default(),
+            );
+            match *field {
+                "download.audio_bitrate_kbps" => {
+                    self.download.audio_bitrate_kbps = download.audio_bitrate_kbps
+                }
+                "download.age_limit" => self.download.age_limit = download.age_limit,
+                "download.sponsorblock_mark" => {
+                    self.download.sponsorblock_mark = download.sponsorblock_mark
+                }
+                "download.sponsorblock_remove" => {
+                    self.download.sponsorblock_remove = download.sponsorblock_remove
+                }
+                "download.geo_bypass" => self.download.geo_bypass = download.geo_bypass,
+                "download.format_sort" => self.download.format_sort = download.format_sort,
+                "advanced.socket_timeout_sec" => {
+                    self.advanced.socket_timeout_sec = advanced.socket_timeout_sec
+                }
+                "advanced.wait_for_video" => self.advanced.wait_for_video = advanced.wait_for_video,
+                "advanced.fragment_retries" => {
+                    self.advanced.fragment_retries = advanced.fragment_retries
+                }
+                "advanced.impersonate_browser" => {
+                    self.advanced.impersonate_browser = advanced.impersonate_browser
+                }
+                "logging.file_name_prefix" => {
+                    self.logging.file_name_prefix = logging.file_name_prefix
+                }
+                "logging.max_files" => self.logging.max_files = logging.max_files,
+                _ => {
+                    // A field without its own reset; give up on the whole file.
+                    *self = Config::default();
+                }
+            }
+            errors.push(error);
+        }
+        errors
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(kbps) = self.download.audio_bitrate_kbps {
+            if !(32..=320).contains(&kbps) {
+                return Err(ConfigError::Invalid {
+                    field: "download.audio_bitrate_kbps",
+                    message: format!("{kbps} is outside 32..=320"),
+                });
+            }
+        }
+
+        if let Some(age) = self.download.age_limit {
+            if !(1..=99).contains(&age) {
+                return Err(ConfigError::Invalid {
+                    field: "download.age_limit",
+                    message: format!("{age} is outside 1..=99"),
+                });
+            }
+        }
+
+        let socket_timeout = self.advanced.socket_timeout_sec;
+        let timeout = self.download.timeout_sec;
+        if socket_timeout > 0 && timeout > 0 && socket_timeout >= timeout {
+            return Err(ConfigError::Invalid {
+                field: "advanced.socket_timeout_sec",
+                message: format!("{socket_timeout} must be less than timeout_sec ({timeout})"),
+            });
+        }
+
+        if let Some((min, max)) = self.advanced.wait_for_video {
+            if min == 0 || min > max {
+                return Err(ConfigError::Invalid {
+                    field: "advanced.wait_for_video",
+                    message: format!("{min}-{max} must be a non-zero, ascending range"),
+                });
+            }
+        }
+
+        let fragment_retries = self.advanced.fragment_retries;
+        if fragment_retries > MAX_FRAGMENT_RETRIES {
+            return Err(ConfigError::Invalid {
+                field: "advanced.fragment_retries",
+                message: format!("{fragment_retries} is above {MAX_FRAGMENT_RETRIES}"),
+            });
+        }
+
+        for (field, categories) in [
+            (
+                "download.sponsorblock_mark",
+                &self.download.sponsorblock_mark,
+            ),
+            (
+                "download.sponsorblock_remove",
+                &self.download.sponsorblock_remove,
+            ),
+        ] {
+            if categories.as_ref().is_some_and(Vec::is_empty) {
+                return Err(ConfigError::Invalid {
+                    field,
+                    message: "list at least one category or unset it".to_string(),
+                });
+            }
+        }
+        if let (Some(marked), Some(removed)) = (
+            &self.download.sponsorblock_mark,
+            &self.download.sponsorblock_remove,
+        ) {
+            let both: Vec<_> = marked
+                .iter()
+                .copied()
+                .filter(|category| removed.contains(category))
+                .collect();
+            if !both.is_empty() {
+                return Err(ConfigError::Invalid {
+                    field: "download.sponsorblock_mark",
+                    message: format!(
+                        "{} cannot be both marked and removed",
+                        sponsorblock::category_list(&both)
+                    ),
+                });
+            }
+        }
+
+        if let GeoBypassMode::ForceCountry(country) = &self.download.geo_bypass {
+            if !COUNTRY_CODE_RE.is_match(country) {
+                return Err(ConfigError::Invalid {
+                    field: "download.geo_bypass",
+                    message: format!("{country:?} is not a two-letter uppercase country code"),
+                });
+            }
+        }
+
+        if let Some(sort) = &self.download.format_sort {
+            if sort.trim().is_empty() || sort.contains(SHELL_METACHARACTERS) {
+                return Err(ConfigError::Invalid {
+                    field: "download.format_sort",
+                    message: format!("{sort:?} is not a valid format sort"),
+                });
+            }
+        }
+
+        if let Some(target) = &self.advanced.impersonate_browser {
+            if !IMPERSONATE_TARGETS.contains(&target.as_str()) {
+                return Err(ConfigError::Invalid {
+                    field: "advanced.impersonate_browser",
+                    message: format!("unsupported impersonation target {target:?}"),
+                });
+            }
+        }
+
+        if let Some(args_file) = &self.advanced.yt_dlp_args_file {
+            if !args_file.exists() {
+                warn!(
+                    "yt-dlp args file {} does not exist; it will be ignored",
+                    args_file.display()
+                );
+            }
+        }
+
+        if self.advanced.quiet && self.advanced.save_logs {
+            warn!(
+                "advanced.quiet is set; saved yt-dlp logs will be sparse and may be hard to debug"
+            );
+        }
+
+        if self.advanced.force_overwrites {
+            if let Some(flag) = self
+                .advanced
+                .extra_args
+                .iter()
+                .find(|arg| OVERWRITE_FLAGS.contains(&arg.as_str()))
+            {
+                warn!(
+                    "advanced.force_overwrites is set; {flag} in advanced.extra_args is overridden"
+                );
+            }
+        }
+
+        if self.advanced.print_traffic && !self.advanced.save_logs {
+            warn!("advanced.print_traffic is set but advanced.save_logs is off; the traffic dump will not be kept");
+        }
+
+        let prefix = &self.logging.file_name_prefix;
+        if prefix.is_empty() || prefix.contains(['/', '\\']) {
+            return Err(ConfigError::Invalid {
+                field: "logging.file_name_prefix",
+                message: format!("{prefix:?} must be a non-empty file name"),
+            });
+        }
+
+        if self.logging.max_files == 0 {
+            return Err(ConfigError::Invalid {
+                field: "logging.max_files",
+                message: "must keep at least one log file".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
     pub fn merge_download(&mut self, download: DownloadSettings) {
         self.download = download;
     }
@@ -103,6 +444,51 @@ impl Config {
     }
 }
 
+/// How long [`Config::open_in_editor`] waits for the file to be saved when
+/// the editor it launched does not block.
+pub const EDITOR_WATCH_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// The command that opens `path` for editing, and whether it only exits once
+/// the user is done editing.
+fn editor_command(os: &str, editor: Option<&OsStr>, path: &Path) -> (Command, bool) {
+    if os != "windows" {
+        // `$EDITOR` may carry its own flags, e.g. `code --wait`.
+        if let Some(mut parts) = editor.and_then(OsStr::to_str).map(str::split_whitespace) {
+            if let Some(program) = parts.next() {
+                let mut command = Command::new(program);
+                command.args(parts).arg(path);
+                return (command, true);
+            }
+        }
+    }
+
+    let (mut command, waits) = match os {
+        "windows" => (Command::new("notepad.exe"), true),
+        "macos" => {
+            // `-W` waits for the editor to quit.
+            let mut command = Command::new("open");
+            command.args(["-W", "-t"]);
+            (command, true)
+        }
+        _ => (Command::new("xdg-open"), false),
+    };
+    command.arg(path);
+    (command, waits)
+}
+
+/// Poll `path` until its modification time differs from `since`, or until
+/// `timeout` has passed.
+fn wait_for_modification(path: &Path, since: Option<SystemTime>, timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        if modified.is_some() && modified != since {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+}
+
 fn default_config_path() -> PathBuf {
     if let Some(project_dirs) = DEFAULT_PROJECT_DIRS.as_ref() {
         project_dirs.config_dir().join("space_downloader.toml")
@@ -121,6 +507,7 @@ pub enum ThemePreference {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GeneralSettings {
     pub output_dir: PathBuf,
     pub language: String,
@@ -191,6 +578,20 @@ pub enum AudioFormat {
     M4a,
     Mp3,
     Opus,
+    /// Ogg Vorbis, written with the `.ogg` extension.
+    Vorbis,
+}
+
+impl AudioFormat {
+    /// File extension yt-dlp gives files converted to this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            AudioFormat::Vorbis => "ogg",
+            AudioFormat::M4a => "m4a",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Opus => "opus",
+        }
+    }
 }
 
 impl std::fmt::Display for AudioFormat {
@@ -199,22 +600,88 @@ impl std::fmt::Display for AudioFormat {
             AudioFormat::M4a => "m4a",
             AudioFormat::Mp3 => "mp3",
             AudioFormat::Opus => "opus",
+            AudioFormat::Vorbis => "vorbis",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// Container the video is merged into when it is kept alongside the audio.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoContainer {
+    #[default]
+    Mp4,
+    Mkv,
+    Webm,
+}
+
+impl std::fmt::Display for VideoContainer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            VideoContainer::Mp4 => "mp4",
+            VideoContainer::Mkv => "mkv",
+            VideoContainer::Webm => "webm",
         };
         write!(f, "{}", text)
     }
 }
 
+/// How yt-dlp should work around geographic restrictions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GeoBypassMode {
+    /// Pass no geo bypass flag.
+    #[default]
+    None,
+    /// `--geo-bypass`: fake a suitable `X-Forwarded-For` header.
+    Auto,
+    /// `--geo-bypass-country`: appear to be in the given country, e.g. `"US"`.
+    ForceCountry(String),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DownloadSettings {
     pub format: AudioFormat,
     pub max_retries: u8,
     pub timeout_sec: u64,
     pub concurrency: usize,
+    pub audio_bitrate_kbps: Option<u32>,
+    /// Embed chapter markers into the output file. Requires ffmpeg.
+    pub embed_chapters: bool,
+    /// Experimental: also report bytes received on the network interfaces
+    /// while a download runs (Linux only).
+    pub use_native_progress: bool,
+    /// SponsorBlock segments to mark as chapters in the output file, via
+    /// `--sponsorblock-mark`. Must not share categories with
+    /// `sponsorblock_remove`.
+    pub sponsorblock_mark: Option<Vec<SponsorBlockCategory>>,
+    /// SponsorBlock segments to cut out of the output file. Requires ffmpeg.
+    pub sponsorblock_remove: Option<Vec<SponsorBlockCategory>>,
+    /// Stream preference passed to `--format-sort`, e.g. `"ext:m4a:m4a"`.
+    pub format_sort: Option<String>,
+    /// Save the comment section in the info JSON via `--write-comments`.
+    /// This can make the info JSON file many times larger.
+    pub write_comments: bool,
+    /// Leave the `.info.json` file next to the download once its metadata
+    /// has been read.
+    pub keep_info_json: bool,
+    pub geo_bypass: GeoBypassMode,
+    /// Prefer free codecs (Opus, Vorbis) over AAC/MP3 when a site offers the
+    /// same quality in both, via `--prefer-free-formats`. This only affects
+    /// which stream is downloaded; the file is still converted to `format`,
+    /// so e.g. `Mp3` output stays MP3.
+    pub prefer_free_formats: bool,
+    /// Highest content age rating to download, via `--age-limit`; must be
+    /// 1..=99. Platforms that enforce age verification also need an
+    /// authenticated [`AdvancedSettings::cookie_file`].
+    pub age_limit: Option<u8>,
 }
 
 impl DownloadSettings {
     pub fn effective_concurrency(&self) -> usize {
-        self.concurrency.clamp(1, 3)
+        self.concurrency.clamp(1, MAX_CONCURRENCY)
     }
 }
 
@@ -225,16 +692,82 @@ impl Default for DownloadSettings {
             max_retries: 3,
             timeout_sec: 0,
             concurrency: 1,
+            audio_bitrate_kbps: None,
+            embed_chapters: false,
+            use_native_progress: false,
+            sponsorblock_mark: None,
+            sponsorblock_remove: None,
+            format_sort: None,
+            write_comments: false,
+            keep_info_json: true,
+            geo_bypass: GeoBypassMode::None,
+            prefer_free_formats: false,
+            age_limit: None,
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AdvancedSettings {
     pub yt_dlp_path: PathBuf,
     pub cookie_file: Option<PathBuf>,
     pub extra_args: Vec<String>,
     pub save_logs: bool,
+    pub socket_timeout_sec: u64,
+    pub impersonate_browser: Option<String>,
+    /// User-maintained yt-dlp config passed via `--config-location`. Options in
+    /// it may override the flags space-downloader relies on (output template,
+    /// progress output), so downloads can break if it changes them.
+    pub ytdlp_config_file: Option<PathBuf>,
+    /// Text file with one yt-dlp argument per line, added before `extra_args`.
+    /// Lines starting with `#` are comments; shell quoting is honoured.
+    pub yt_dlp_args_file: Option<PathBuf>,
+    /// Seconds to wait before retrying a job the server rate limited.
+    pub rate_limit_cooldown_sec: u64,
+    /// Split downloads with chapters into one file per chapter via
+    /// `--split-chapters`. Requires ffmpeg.
+    pub extract_chapters_as_tracks: bool,
+    /// Start yt-dlp without a console window on Windows. Turn off to see its
+    /// output when the GUI is launched from a terminal.
+    pub windows_no_console: bool,
+    /// How often yt-dlp retries a single failed DASH/HLS fragment, passed via
+    /// `--fragment-retries` when it differs from yt-dlp's default. These
+    /// retries happen inside one yt-dlp run; `DownloadSettings::max_retries`
+    /// restarts the whole run once yt-dlp gives up.
+    pub fragment_retries: u32,
+    /// Pass `--quiet --no-warnings` so yt-dlp only prints progress and
+    /// errors, keeping the log panel short.
+    pub quiet: bool,
+    /// Pass `--print-traffic` so yt-dlp logs every HTTP request and response
+    /// header, e.g. to debug authentication. Very verbose.
+    pub print_traffic: bool,
+    /// Pass `--force-overwrites`, which makes yt-dlp replace existing files,
+    /// partial downloads and info JSON files instead of reusing them. It is
+    /// added after `yt_dlp_args_file` and `extra_args`, so it wins over
+    /// `--no-overwrites` or `--no-force-overwrites` given there.
+    pub force_overwrites: bool,
+    /// Pass `--live-from-start` so a live stream is recorded from its
+    /// beginning rather than from the moment the download starts. Live
+    /// downloads run until the broadcast ends, so
+    /// [`DownloadSettings::timeout_sec`] is not applied while this or
+    /// `wait_for_video` is set.
+    pub live_from_start: bool,
+    /// `(min_secs, max_secs)` for `--wait-for-video`: when a scheduled stream
+    /// has not started yet, yt-dlp retries every `min..=max` seconds until it
+    /// does. Like `live_from_start`, this disables the download timeout.
+    pub wait_for_video: Option<(u64, u64)>,
+    /// yt-dlp `--download-archive` file for downloads started from the GUI;
+    /// URLs already listed in it are skipped unless force-retried.
+    pub download_archive: Option<PathBuf>,
+}
+
+impl AdvancedSettings {
+    /// Whether downloads may wait for or follow a live stream, and so must
+    /// not be cut off by [`DownloadSettings::timeout_sec`].
+    pub fn is_live(&self) -> bool {
+        self.live_from_start || self.wait_for_video.is_some()
+    }
 }
 
 impl Default for AdvancedSettings {
@@ -244,6 +777,20 @@ impl Default for AdvancedSettings {
             cookie_file: None,
             extra_args: Vec::new(),
             save_logs: true,
+            socket_timeout_sec: 0,
+            impersonate_browser: None,
+            ytdlp_config_file: None,
+            yt_dlp_args_file: None,
+            rate_limit_cooldown_sec: 60,
+            extract_chapters_as_tracks: false,
+            windows_no_console: true,
+            fragment_retries: DEFAULT_FRAGMENT_RETRIES,
+            quiet: false,
+            print_traffic: false,
+            force_overwrites: false,
+            live_from_start: false,
+            wait_for_video: None,
+            download_archive: None,
         }
     }
 }
@@ -259,9 +806,14 @@ pub enum LogLevel {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LogSettings {
     pub enabled: bool,
     pub level: LogLevel,
+    /// Log files are named `<prefix>.log.<date>`.
+    pub file_name_prefix: String,
+    /// How many daily log files to keep; older ones are deleted.
+    pub max_files: u8,
 }
 
 impl Default for LogSettings {
@@ -269,6 +821,8 @@ impl Default for LogSettings {
         Self {
             enabled: true,
             level: LogLevel::Info,
+            file_name_prefix: DEFAULT_LOG_FILE_PREFIX.to_string(),
+            max_files: DEFAULT_MAX_LOG_FILES,
         }
     }
 }
@@ -285,6 +839,336 @@ mod tests {
         assert_eq!(restored.download.concurrency, 1);
         assert_eq!(restored.advanced.extra_args.len(), 0);
     }
+
+    #[test]
+    fn vorbis_format_round_trips() {
+        let mut config = Config::default();
+        config.download.format = AudioFormat::Vorbis;
+        let toml = toml::to_string(&config).unwrap();
+        assert!(toml.contains("format = \"vorbis\""));
+        let restored: Config = toml::from_str(&toml).unwrap();
+        assert_eq!(restored.download.format, AudioFormat::Vorbis);
+
+        assert_eq!(
+            "vorbis".parse::<AudioFormat>().unwrap(),
+            AudioFormat::Vorbis
+        );
+        assert!("ogv".parse::<AudioFormat>().is_err());
+        assert_eq!(AudioFormat::Vorbis.extension(), "ogg");
+    }
+
+    #[test]
+    fn partial_config_gets_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("space_downloader.toml");
+        fs::write(
+            &path,
+            "[download]\nformat = \"mp3\"\nconcurrency = 0\n\n[advanced]\nyt_dlp_path = \"\"\n",
+        )
+        .unwrap();
+
+        let (config, _) = Config::load_or_default(Some(&path)).unwrap();
+        let defaults = Config::default();
+        assert_eq!(config.download.format, AudioFormat::Mp3);
+        assert_eq!(config.download.max_retries, defaults.download.max_retries);
+        assert_eq!(config.download.concurrency, defaults.download.concurrency);
+        assert_eq!(config.download.audio_bitrate_kbps, None);
+        assert_eq!(config.advanced.yt_dlp_path, defaults.advanced.yt_dlp_path);
+        assert_eq!(config.advanced.socket_timeout_sec, 0);
+        assert!(config.advanced.windows_no_console);
+        assert!(config.logging.enabled);
+    }
+
+    #[test]
+    fn invalid_values_are_reset_to_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("space_downloader.toml");
+        fs::write(
+            &path,
+            "[download]\nformat = \"mp3\"\naudio_bitrate_kbps = 16\n\n[logging]\nmax_files = 0\n",
+        )
+        .unwrap();
+
+        let (config, _, warnings) = Config::load_with_warnings(Some(&path)).unwrap();
+        assert_eq!(config.download.format, AudioFormat::Mp3);
+        assert_eq!(config.download.audio_bitrate_kbps, None);
+        assert_eq!(config.logging.max_files, LogSettings::default().max_files);
+        let fields: Vec<_> = warnings
+            .iter()
+            .map(|warning| match warning {
+                ConfigError::Invalid { field, .. } => *field,
+                other => panic!("unexpected warning {other}"),
+            })
+            .collect();
+        assert_eq!(fields, ["download.audio_bitrate_kbps", "logging.max_files"]);
+
+        assert!(Config::load_or_default(Some(&path)).is_ok());
+    }
+
+    #[test]
+    fn save_atomic_survives_an_interrupted_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut config = Config::default();
+        config.download.format = AudioFormat::Mp3;
+        config.save_atomic(&path).unwrap();
+
+        // A crash while writing the next version leaves a truncated temp file
+        // behind; the real config must be untouched by it.
+        let tmp_path = dir.path().join("config.toml.tmp");
+        let serialized = fs::read_to_string(&path).unwrap();
+        fs::write(&tmp_path, &serialized[..serialized.len() / 2]).unwrap();
+        let (loaded, _) = Config::load_or_default(Some(&path)).unwrap();
+        assert_eq!(loaded.download.format, AudioFormat::Mp3);
+
+        config.download.format = AudioFormat::Opus;
+        config.save(&path).unwrap();
+        assert!(!tmp_path.exists());
+        let (loaded, _) = Config::load_or_default(Some(&path)).unwrap();
+        assert_eq!(loaded.download.format, AudioFormat::Opus);
+    }
+
+    fn command_line(command: &Command) -> Vec<&OsStr> {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .collect()
+    }
+
+    #[test]
+    fn editor_command_prefers_editor_variable() {
+        let path = Path::new("space_downloader.toml");
+        let (command, waits) = editor_command("linux", Some(OsStr::new("code --wait")), path);
+        assert_eq!(
+            command_line(&command),
+            ["code", "--wait", "space_downloader.toml"]
+        );
+        assert!(waits);
+        let (command, _) = editor_command("macos", Some(OsStr::new("vim")), path);
+        assert_eq!(command_line(&command), ["vim", "space_downloader.toml"]);
+    }
+
+    #[test]
+    fn editor_command_falls_back_to_platform_handler() {
+        let path = Path::new("space_downloader.toml");
+        let (command, waits) = editor_command("linux", None, path);
+        assert_eq!(
+            command_line(&command),
+            ["xdg-open", "space_downloader.toml"]
+        );
+        assert!(!waits);
+        let (command, _) = editor_command("linux", Some(OsStr::new("  ")), path);
+        assert_eq!(
+            command_line(&command),
+            ["xdg-open", "space_downloader.toml"]
+        );
+        let (command, waits) = editor_command("macos", None, path);
+        assert_eq!(
+            command_line(&command),
+            ["open", "-W", "-t", "space_downloader.toml"]
+        );
+        assert!(waits);
+        let (command, waits) = editor_command("windows", Some(OsStr::new("vim")), path);
+        assert_eq!(
+            command_line(&command),
+            ["notepad.exe", "space_downloader.toml"]
+        );
+        assert!(waits);
+    }
+
+    #[test]
+    fn wait_for_modification_returns_once_the_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let start = Instant::now();
+        // A missing file never counts as modified.
+        wait_for_modification(&path, None, Duration::from_millis(100));
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        fs::write(&path, "").unwrap();
+        let start = Instant::now();
+        wait_for_modification(&path, None, Duration::from_secs(5));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_age_limit() {
+        let mut config = Config::default();
+        for age in [1, 18, 99] {
+            config.download.age_limit = Some(age);
+            assert!(config.validate().is_ok());
+        }
+        for age in [0, 100] {
+            config.download.age_limit = Some(age);
+            assert!(matches!(
+                config.validate(),
+                Err(ConfigError::Invalid {
+                    field: "download.age_limit",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_bitrate() {
+        let mut config = Config::default();
+        config.download.audio_bitrate_kbps = Some(320);
+        assert!(config.validate().is_ok());
+        config.download.audio_bitrate_kbps = Some(16);
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_rejects_overlapping_sponsorblock_categories() {
+        let mut config = Config::default();
+        config.download.sponsorblock_mark = Some(vec![
+            SponsorBlockCategory::Intro,
+            SponsorBlockCategory::Sponsor,
+        ]);
+        config.download.sponsorblock_remove = Some(vec![SponsorBlockCategory::Outro]);
+        assert!(config.validate().is_ok());
+
+        config.download.sponsorblock_remove = Some(vec![SponsorBlockCategory::Sponsor]);
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid { field: "download.sponsorblock_mark", message })
+                if message == "sponsor cannot be both marked and removed"
+        ));
+
+        config.download.sponsorblock_mark = Some(Vec::new());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_log_prefix_with_path_separator() {
+        let mut config = Config::default();
+        config.logging.file_name_prefix = "instance-1".to_string();
+        assert!(config.validate().is_ok());
+        config.logging.file_name_prefix = "../elsewhere".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid {
+                field: "logging.file_name_prefix",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn validate_rejects_zero_max_log_files() {
+        let mut config = Config::default();
+        assert_eq!(config.logging.max_files, DEFAULT_MAX_LOG_FILES);
+        config.logging.max_files = 0;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid {
+                field: "logging.max_files",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn validate_rejects_unsafe_format_sort() {
+        let mut config = Config::default();
+        config.download.format_sort = Some("codec:aac,br:192".to_string());
+        assert!(config.validate().is_ok());
+        for sort in ["", "br:192; rm -rf ~", "ext:m4a | cat"] {
+            config.download.format_sort = Some(sort.to_string());
+            assert!(matches!(
+                config.validate(),
+                Err(ConfigError::Invalid {
+                    field: "download.format_sort",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn validate_checks_geo_bypass_country() {
+        let mut config = Config::default();
+        config.download.geo_bypass = GeoBypassMode::ForceCountry("US".to_string());
+        assert!(config.validate().is_ok());
+        for country in ["us", "USA", "U", ""] {
+            config.download.geo_bypass = GeoBypassMode::ForceCountry(country.to_string());
+            assert!(matches!(
+                config.validate(),
+                Err(ConfigError::Invalid {
+                    field: "download.geo_bypass",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn geo_bypass_round_trips_through_toml() {
+        for mode in [
+            GeoBypassMode::None,
+            GeoBypassMode::Auto,
+            GeoBypassMode::ForceCountry("JP".to_string()),
+        ] {
+            let mut config = Config::default();
+            config.download.geo_bypass = mode.clone();
+            let text = toml::to_string(&config).unwrap();
+            let parsed: Config = toml::from_str(&text).unwrap();
+            assert_eq!(parsed.download.geo_bypass, mode);
+        }
+    }
+
+    #[test]
+    fn validate_caps_fragment_retries() {
+        let mut config = Config::default();
+        config.advanced.fragment_retries = MAX_FRAGMENT_RETRIES;
+        assert!(config.validate().is_ok());
+        config.advanced.fragment_retries = MAX_FRAGMENT_RETRIES + 1;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid {
+                field: "advanced.fragment_retries",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn validate_requires_socket_timeout_below_total_timeout() {
+        let mut config = Config::default();
+        config.advanced.socket_timeout_sec = 30;
+        assert!(config.validate().is_ok());
+        config.download.timeout_sec = 600;
+        assert!(config.validate().is_ok());
+        config.download.timeout_sec = 30;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_requires_ascending_wait_for_video_range() {
+        let mut config = Config::default();
+        config.advanced.wait_for_video = Some((30, 300));
+        assert!(config.validate().is_ok());
+        let text = toml::to_string(&config).unwrap();
+        let parsed: Config = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.advanced.wait_for_video, Some((30, 300)));
+
+        for range in [(300, 30), (0, 30)] {
+            config.advanced.wait_for_video = Some(range);
+            assert!(matches!(
+                config.validate(),
+                Err(ConfigError::Invalid {
+                    field: "advanced.wait_for_video",
+                    ..
+                })
+            ));
+        }
+    }
 }
 #[derive(Debug, Clone)]
 pub struct ParseAudioFormatError(pub String);
@@ -297,6 +1181,7 @@ impl std::str::FromStr for AudioFormat {
             "m4a" => Ok(AudioFormat::M4a),
             "mp3" => Ok(AudioFormat::Mp3),
             "opus" => Ok(AudioFormat::Opus),
+            "vorbis" => Ok(AudioFormat::Vorbis),
             other => Err(ParseAudioFormatError(other.to_string())),
         }
     }
@@ -1,12 +1,20 @@
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 use tokio::process::Command;
 use tokio::time::timeout;
 
-use crate::config::AdvancedSettings;
+use crate::config::{AdvancedSettings, DownloadSettings};
 use crate::error::DependencyError;
 
+/// How long a dependency check result is reused before re-spawning the binaries.
+const DEPENDENCY_CACHE_TTL: Duration = Duration::from_secs(60);
+
+static DEPENDENCY_CACHE: Lazy<Mutex<Option<DependencyStatusCache>>> =
+    Lazy::new(|| Mutex::new(None));
+
 #[derive(Debug, Clone)]
 pub struct DependencyCheck {
     pub binary: String,
@@ -14,6 +22,8 @@ pub struct DependencyCheck {
     pub version: Option<String>,
     pub path: Option<PathBuf>,
     pub error: Option<String>,
+    /// Whether the binary accepts `--impersonate` (yt-dlp 2023.11.16 and later).
+    pub supports_impersonate: bool,
 }
 
 impl DependencyCheck {
@@ -24,6 +34,7 @@ impl DependencyCheck {
             version: None,
             path: None,
             error,
+            supports_impersonate: false,
         }
     }
 }
@@ -38,20 +49,95 @@ impl DependencyStatus {
     pub fn all_available(&self) -> bool {
         self.yt_dlp.available && self.ffmpeg.available
     }
+
+    /// Human-readable notes about enabled settings that the detected
+    /// dependencies cannot honour.
+    pub fn setting_notes(&self, download: &DownloadSettings) -> Vec<String> {
+        let mut notes = Vec::new();
+        if download.embed_chapters && !self.ffmpeg.available {
+            notes.push("embedding chapters requires ffmpeg, which was not found".to_string());
+        }
+        notes
+    }
 }
 
+#[derive(Debug, Clone)]
+struct DependencyStatusCache {
+    status: DependencyStatus,
+    checked_at: Instant,
+    yt_dlp_setting: PathBuf,
+    yt_dlp_modified: Option<SystemTime>,
+    ffmpeg_modified: Option<SystemTime>,
+}
+
+impl DependencyStatusCache {
+    fn new(status: DependencyStatus, yt_dlp_setting: PathBuf) -> Self {
+        Self {
+            yt_dlp_modified: binary_modified(&status.yt_dlp),
+            ffmpeg_modified: binary_modified(&status.ffmpeg),
+            status,
+            checked_at: Instant::now(),
+            yt_dlp_setting,
+        }
+    }
+
+    fn is_valid_for(&self, yt_dlp_setting: &Path, now: Instant) -> bool {
+        self.yt_dlp_setting == yt_dlp_setting
+            && now.saturating_duration_since(self.checked_at) < DEPENDENCY_CACHE_TTL
+            && binary_modified(&self.status.yt_dlp) == self.yt_dlp_modified
+            && binary_modified(&self.status.ffmpeg) == self.ffmpeg_modified
+    }
+}
+
+/// Check yt-dlp and ffmpeg availability.
+///
+/// Results are cached for 60 seconds unless `force` is set or one of the
+/// binaries has been modified since the last check.
 pub async fn check_dependencies(
     settings: &AdvancedSettings,
+    force: bool,
 ) -> Result<DependencyStatus, DependencyError> {
-    let yt_dlp = check_binary(
+    if !force {
+        let cache = DEPENDENCY_CACHE.lock();
+        if let Some(cached) = cache.as_ref() {
+            if cached.is_valid_for(&settings.yt_dlp_path, Instant::now()) {
+                return Ok(cached.status.clone());
+            }
+        }
+    }
+
+    let mut yt_dlp = check_binary(
         settings.yt_dlp_path.to_str().unwrap_or("yt-dlp"),
         &["--version"],
     )
     .await?;
+    if let Some(path) = yt_dlp.path.clone().filter(|_| yt_dlp.available) {
+        yt_dlp.supports_impersonate = supports_impersonate(&path).await;
+    }
 
     let ffmpeg = check_binary("ffmpeg", &["-version"]).await?;
 
-    Ok(DependencyStatus { yt_dlp, ffmpeg })
+    let status = DependencyStatus { yt_dlp, ffmpeg };
+    *DEPENDENCY_CACHE.lock() = Some(DependencyStatusCache::new(
+        status.clone(),
+        settings.yt_dlp_path.clone(),
+    ));
+
+    Ok(status)
+}
+
+async fn supports_impersonate(yt_dlp: &Path) -> bool {
+    let mut command = Command::new(yt_dlp);
+    command.arg("--list-impersonate-targets").kill_on_drop(true);
+    matches!(
+        timeout(Duration::from_secs(5), command.output()).await,
+        Ok(Ok(output)) if output.status.success()
+    )
+}
+
+fn binary_modified(check: &DependencyCheck) -> Option<SystemTime> {
+    let path = check.path.as_ref()?;
+    std::fs::metadata(path).ok()?.modified().ok()
 }
 
 async fn check_binary(binary: &str, args: &[&str]) -> Result<DependencyCheck, DependencyError> {
@@ -95,6 +181,7 @@ async fn check_binary(binary: &str, args: &[&str]) -> Result<DependencyCheck, De
                     version: None,
                     path: Some(command_path.clone()),
                     error: Some(String::from_utf8_lossy(&output.stderr).to_string()),
+                    supports_impersonate: false,
                 });
             }
 
@@ -112,6 +199,7 @@ async fn check_binary(binary: &str, args: &[&str]) -> Result<DependencyCheck, De
                 version: parse_version(version_text).map(|s| s.to_string()),
                 path: Some(command_path),
                 error: None,
+                supports_impersonate: false,
             })
         }
     }
@@ -131,11 +219,10 @@ pub fn resolve_binary(candidate: &Path) -> Option<PathBuf> {
         }
     }
 
-    let binary_name = candidate.file_name()?;
-
     // Priority 1: Check common Homebrew installation paths (for macOS GUI apps)
     #[cfg(target_os = "macos")]
     {
+        let binary_name = candidate.file_name()?;
         let homebrew_paths = [
             "/opt/homebrew/bin",  // Apple Silicon
             "/usr/local/bin",     // Intel
@@ -162,3 +249,66 @@ fn parse_version(text: &str) -> Option<&str> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached_at() -> Option<Instant> {
+        DEPENDENCY_CACHE
+            .lock()
+            .as_ref()
+            .map(|cache| cache.checked_at)
+    }
+
+    #[test]
+    fn embed_chapters_without_ffmpeg_is_noted() {
+        let status = DependencyStatus {
+            yt_dlp: DependencyCheck::missing("yt-dlp", None),
+            ffmpeg: DependencyCheck::missing("ffmpeg", None),
+        };
+        let mut download = DownloadSettings::default();
+        assert!(status.setting_notes(&download).is_empty());
+
+        download.embed_chapters = true;
+        assert_eq!(status.setting_notes(&download).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn second_check_within_ttl_uses_cache() {
+        let settings = AdvancedSettings {
+            yt_dlp_path: PathBuf::from("space-downloader-missing-yt-dlp"),
+            ..AdvancedSettings::default()
+        };
+
+        let first = check_dependencies(&settings, true).await.unwrap();
+        let first_checked_at = cached_at().expect("cache populated");
+
+        let second = check_dependencies(&settings, false).await.unwrap();
+        assert_eq!(cached_at(), Some(first_checked_at));
+        assert_eq!(first.yt_dlp.available, second.yt_dlp.available);
+
+        check_dependencies(&settings, true).await.unwrap();
+        assert_ne!(cached_at(), Some(first_checked_at));
+    }
+
+    #[test]
+    fn cache_expires_after_ttl() {
+        let setting = PathBuf::from("space-downloader-missing-yt-dlp");
+        let cache = DependencyStatusCache::new(
+            DependencyStatus {
+                yt_dlp: DependencyCheck::missing("yt-dlp", None),
+                ffmpeg: DependencyCheck::missing("ffmpeg", None),
+            },
+            setting.clone(),
+        );
+        let checked_at = cache.checked_at;
+        assert!(cache.is_valid_for(&setting, checked_at));
+        assert!(cache.is_valid_for(
+            &setting,
+            checked_at + DEPENDENCY_CACHE_TTL - Duration::from_millis(1)
+        ));
+        assert!(!cache.is_valid_for(&setting, checked_at + DEPENDENCY_CACHE_TTL));
+        assert!(!cache.is_valid_for(Path::new("yt-dlp-other"), checked_at));
+    }
+}
@@ -1,25 +1,32 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::OsString;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
 use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
-use parking_lot::Mutex as ParkingMutex;
+use parking_lot::{Mutex as ParkingMutex, RwLock as ParkingRwLock};
 use regex::Regex;
 use tokio::fs;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::{mpsc, watch, RwLock, Semaphore};
+use tokio::sync::{mpsc, oneshot, watch, OwnedSemaphorePermit, RwLock, Semaphore};
 use tokio::time;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, info_span, instrument, warn, Instrument};
 use uuid::Uuid;
 
-use crate::config::{AdvancedSettings, AudioFormat, Config, DownloadSettings};
+use crate::config::{
+    AdvancedSettings, AudioFormat, Config, DownloadSettings, GeoBypassMode, VideoContainer,
+    DEFAULT_FRAGMENT_RETRIES,
+};
 use crate::dependency::resolve_binary;
-use crate::error::{DownloadError, HistoryError};
-use crate::history::HistoryRepository;
+use crate::error::{ConfigError, DownloadError, ErrorHelp, HistoryError, SpaceDownloaderError};
+use crate::history::{AsyncHistoryRepository, HistoryQuery, HistoryRepository};
+use crate::sponsorblock;
 
 static PROGRESS_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(
@@ -28,16 +35,84 @@ static PROGRESS_RE: Lazy<Regex> = Lazy::new(|| {
     .expect("valid regex" )
 });
 
+/// Fragmented (DASH/HLS) downloads report fragment counts instead of byte percentages.
+static FRAGMENT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\[download\]\s+fragment\s+(?P<frag>\d+)\s+of\s+(?P<total_frags>\d+)")
+        .expect("valid regex")
+});
+
+/// How long finished jobs remain visible through [`DownloaderService::status`].
+const DEFAULT_COMPLETED_JOB_TTL: Duration = Duration::from_secs(60);
+
+/// Events buffered per job before yt-dlp's output reader waits for the
+/// receiver.
+const EVENT_CHANNEL_CAPACITY: usize = 128;
+/// [`EVENT_CHANNEL_CAPACITY`] with [`AdvancedSettings::print_traffic`], which
+/// turns every HTTP header into a log line.
+const TRAFFIC_EVENT_CHANNEL_CAPACITY: usize = 4096;
+
 static DESTINATION_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"Destination:\s+(?P<path>.+)").expect("valid regex"));
 
-#[derive(Debug, Clone)]
+/// Query parameters YouTube adds for tracking that do not affect the video.
+const YOUTUBE_TRACKING_PARAMS: &[&str] = &["si", "feature", "pp"];
+
+/// Upper bound (exclusive) on [`DownloadRequest::output_filename`] in bytes;
+/// most file systems allow 255 per file name.
+const MAX_OUTPUT_FILENAME_LEN: usize = 255;
+
+/// History error codes [`DownloaderService::requeue_failed`] leaves alone
+/// because retrying would fail the same way.
+const NOT_REQUEUED_ERROR_CODES: &[&str] = &["UnsupportedSite", "GeoBlocked"];
+
+/// Metadata fields that may be overridden through `--parse-metadata`.
+pub const METADATA_FIELD_ALLOWLIST: &[&str] = &[
+    "title", "artist", "album", "genre", "date", "comment", "track",
+];
+
+/// What a download produces.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadMode {
+    /// Extract the audio track only.
+    #[default]
+    Audio,
+    /// Keep the video merged into `container` and also extract the audio
+    /// track in the request's format. Requires ffmpeg.
+    AudioVideo { container: VideoContainer },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct DownloadRequest {
     pub url: String,
     pub output_dir: PathBuf,
     pub format: AudioFormat,
+    #[serde(default)]
     pub extra_args: Vec<String>,
+    #[serde(default)]
     pub cookie_file: Option<PathBuf>,
+    /// Metadata overrides keyed by field name. Values may be literal text or
+    /// yt-dlp output templates such as `%(channel)s`.
+    #[serde(default)]
+    pub metadata_fields: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub mode: DownloadMode,
+    /// yt-dlp `--download-archive` file; URLs already listed in it are skipped.
+    #[serde(default)]
+    pub archive_file: Option<PathBuf>,
+    /// Download even if the URL is listed in `archive_file`.
+    #[serde(default)]
+    pub force_redownload: bool,
+    /// Job ID to use instead of a random one, for tests and integrations that
+    /// need to know it in advance. Must not belong to a job that was queued
+    /// before, in this session or in the history.
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    /// File name to save as instead of the video title, without extension;
+    /// yt-dlp still adds the extension of the converted file. Taken literally,
+    /// so `%` is not expanded as a template field.
+    #[serde(default)]
+    pub output_filename: Option<String>,
 }
 
 impl DownloadRequest {
@@ -48,13 +123,112 @@ impl DownloadRequest {
             format,
             extra_args: Vec::new(),
             cookie_file: None,
+            metadata_fields: None,
+            mode: DownloadMode::Audio,
+            archive_file: None,
+            force_redownload: false,
+            id: None,
+            output_filename: None,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(fields) = &self.metadata_fields {
+            if let Some(key) = fields
+                .keys()
+                .find(|key| !METADATA_FIELD_ALLOWLIST.contains(&key.as_str()))
+            {
+                return Err(ConfigError::Invalid {
+                    field: "metadata_fields",
+                    message: format!("unknown metadata field {key:?}"),
+                });
+            }
+        }
+        if let Some(name) = &self.output_filename {
+            if name.is_empty() || name.contains(['/', '\\', '\0']) {
+                return Err(ConfigError::Invalid {
+                    field: "output_filename",
+                    message: format!("{name:?} must be a file name without path separators"),
+                });
+            }
+            if name.len() >= MAX_OUTPUT_FILENAME_LEN {
+                return Err(ConfigError::Invalid {
+                    field: "output_filename",
+                    message: format!(
+                        "must be shorter than {MAX_OUTPUT_FILENAME_LEN} bytes, got {}",
+                        name.len()
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Fetch the thumbnail image (usually JPEG or WebP) without downloading
+    /// the media, so the user can check it is the right content first.
+    pub async fn preview_thumbnail(
+        &self,
+        advanced: &AdvancedSettings,
+    ) -> Result<Vec<u8>, DownloadError> {
+        let mut command = yt_dlp_command(advanced);
+        command.args(["--skip-download", "--print", "thumbnail"]);
+        if let Some(cookie_file) = &self.cookie_file {
+            command.arg("--cookies").arg(cookie_file);
         }
+        command.arg(&self.url);
+        command.stdin(std::process::Stdio::null());
+        command.kill_on_drop(true);
+
+        let output = command
+            .output()
+            .await
+            .map_err(|source| spawn_error(&command, source))?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let thumbnail_url = stdout
+            .lines()
+            .next()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && *line != "NA");
+        let thumbnail_url = match thumbnail_url {
+            Some(url) if output.status.success() => url,
+            _ => {
+                let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                return Err(DownloadError::CommandFailed {
+                    status: output.status.code(),
+                    stderr: if stderr.is_empty() {
+                        "yt-dlp reported no thumbnail".to_string()
+                    } else {
+                        stderr
+                    },
+                });
+            }
+        };
+
+        let response = reqwest::get(thumbnail_url)
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|source| DownloadError::Http { source })?;
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|source| DownloadError::Http { source })?;
+        Ok(bytes.to_vec())
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub enum JobStatus {
+    /// Waiting for the start time given to [`DownloaderService::queue_scheduled`].
+    Scheduled,
     Queued,
     Running,
     Succeeded,
@@ -65,6 +239,7 @@ pub enum JobStatus {
 impl JobStatus {
     pub fn as_str(&self) -> &'static str {
         match self {
+            JobStatus::Scheduled => "Scheduled",
             JobStatus::Queued => "Queued",
             JobStatus::Running => "Running",
             JobStatus::Succeeded => "Succeeded",
@@ -75,6 +250,7 @@ impl JobStatus {
 
     pub fn parse_status(value: &str) -> JobStatus {
         match value {
+            "Scheduled" => JobStatus::Scheduled,
             "Queued" => JobStatus::Queued,
             "Running" => JobStatus::Running,
             "Succeeded" => JobStatus::Succeeded,
@@ -84,34 +260,220 @@ impl JobStatus {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ProgressSnapshot {
     pub percent: Option<f32>,
     pub downloaded_bytes: Option<u64>,
     pub total_bytes: Option<u64>,
     pub speed_bytes_per_sec: Option<u64>,
+    #[serde(default, with = "option_duration_secs")]
     pub eta: Option<Duration>,
+    /// `(current, total)` fragment counts reported for fragmented streams.
+    #[serde(default)]
+    pub fragments: Option<(u32, u32)>,
 }
 
-#[derive(Debug, Clone)]
+impl ProgressSnapshot {
+    /// Percentage derived from the fragment counts, for downloads that do not
+    /// report a byte percentage.
+    pub fn synthetic_percent(&self) -> Option<f32> {
+        let (frag, total_frags) = self.fragments?;
+        if total_frags == 0 {
+            return None;
+        }
+        Some(frag as f32 / total_frags as f32 * 100.0)
+    }
+
+    /// Bytes left to download, when both sizes are known.
+    pub fn bytes_remaining(&self) -> Option<u64> {
+        self.total_bytes
+            .zip(self.downloaded_bytes)
+            .and_then(|(total, downloaded)| total.checked_sub(downloaded))
+    }
+
+    /// Whether yt-dlp reported the download as finished.
+    pub fn is_complete(&self) -> bool {
+        self.percent.is_some_and(|percent| percent >= 100.0)
+    }
+
+    /// Downloaded bytes as a fraction of the total in `0.0..=1.0`, e.g. for a
+    /// progress bar. `None` when either size is unknown or the total is zero.
+    pub fn transfer_ratio(&self) -> Option<f32> {
+        let (total, downloaded) = self.total_bytes.zip(self.downloaded_bytes)?;
+        if total == 0 {
+            return None;
+        }
+        Some((downloaded as f64 / total as f64).clamp(0.0, 1.0) as f32)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct DownloadSummary {
     pub id: Uuid,
+    /// The URL as submitted.
     pub url: String,
+    /// The canonical URL yt-dlp resolved `url` to (`webpage_url`), which
+    /// differs for redirects such as shortlinks.
+    #[serde(default)]
+    pub source_url: Option<String>,
     pub status: JobStatus,
     pub title: Option<String>,
     pub uploader: Option<String>,
+    pub description: Option<String>,
     pub file_path: Option<PathBuf>,
+    /// Extracted audio file when the video was kept as well
+    /// ([`DownloadMode::AudioVideo`]); `file_path` is then the video.
+    pub audio_path: Option<PathBuf>,
+    /// Per-chapter tracks produced when `extract_chapters_as_tracks` is on.
+    #[serde(default)]
+    pub extra_files: Vec<PathBuf>,
+    #[serde(default)]
+    pub view_count: Option<u64>,
+    #[serde(default)]
+    pub like_count: Option<u64>,
+    /// Whether the info JSON contains a `comments` list.
+    #[serde(default)]
+    pub has_comments: bool,
     pub completed_at: DateTime<Utc>,
     pub error_message: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum DownloadEvent {
     Status(JobStatus),
     Progress(ProgressSnapshot),
     LogLine(String),
+    /// The server rejected a request for being too frequent; the job will be
+    /// retried after the given cooldown.
+    RateLimited(#[serde(with = "duration_secs")] Duration),
+    /// Bytes received on the network since the attempt started, sampled when
+    /// `use_native_progress` is enabled. `total_bytes` is the size yt-dlp
+    /// reported, if known.
+    NetworkProgress {
+        received_bytes: u64,
+        total_bytes: Option<u64>,
+    },
+    /// A SponsorBlock segment yt-dlp looked up for `--sponsorblock-mark` or
+    /// `--sponsorblock-remove`, in whole seconds of the original media. Sent
+    /// before [`DownloadEvent::Completed`].
+    SponsorBlockMark {
+        start_secs: u64,
+        end_secs: u64,
+        category: String,
+    },
     Completed(DownloadSummary),
-    Failed(String),
+    /// The job failed for good. `help` suggests what the user can do about it.
+    Failed {
+        message: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        help: Option<ErrorHelp>,
+    },
+}
+
+/// Serializes a [`Duration`] as whole seconds.
+mod duration_secs {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+/// Serializes an optional [`Duration`] as whole seconds.
+mod option_duration_secs {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&duration.as_secs()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
+    }
+}
+
+/// How often a running job's progress is written to its in-progress file.
+const PROGRESS_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Last known progress of a running job, saved as `<dir>/<id>.json` so a
+/// download interrupted by a crash or force-quit can be reported on the next
+/// start. The file is removed once the job finishes.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct JobProgress {
+    pub id: Uuid,
+    pub url: String,
+    pub percent: Option<f32>,
+    pub downloaded_bytes: Option<u64>,
+    #[serde(default, with = "option_duration_secs")]
+    pub eta: Option<Duration>,
+}
+
+impl JobProgress {
+    /// Read and remove the progress files left in `dir` by jobs that never
+    /// finished. Unreadable files are removed as well.
+    pub fn take_interrupted(dir: &Path) -> Vec<JobProgress> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut interrupted = Vec::new();
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<JobProgress>(&content).ok())
+            {
+                Some(progress) => interrupted.push(progress),
+                None => warn!("ignoring unreadable progress file {}", path.display()),
+            }
+            std::fs::remove_file(&path).ok();
+        }
+        interrupted
+    }
+}
+
+/// Counters describing the service's work so far, for dashboards and
+/// metrics exporters. See [`DownloaderService::metrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct DownloaderMetrics {
+    pub total_queued: u64,
+    pub total_succeeded: u64,
+    pub total_failed: u64,
+    pub total_canceled: u64,
+    /// Size of the files written by successful downloads.
+    pub total_bytes_downloaded: u64,
+    pub current_running: usize,
+    pub current_queued: usize,
+    /// Best-effort size of the queued and running downloads; see
+    /// [`DownloaderService::total_bytes_in_queue`].
+    pub total_bytes_in_queue: u64,
+}
+
+/// Cumulative counters behind [`DownloaderMetrics`], shared with every job.
+#[derive(Default)]
+struct JobCounters {
+    queued: AtomicU64,
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    canceled: AtomicU64,
+    bytes_downloaded: AtomicU64,
 }
 
 #[derive(Debug, Clone)]
@@ -129,9 +491,16 @@ pub struct JobHandle {
     progress_rx: watch::Receiver<Option<ProgressSnapshot>>,
     events_rx: ParkingMutex<Option<mpsc::Receiver<DownloadEvent>>>,
     cancel_token: CancellationToken,
+    scheduled_at: Option<DateTime<Utc>>,
 }
 
 impl JobHandle {
+    /// The start time the job was scheduled for, if it was queued with
+    /// [`DownloaderService::queue_scheduled`].
+    pub fn scheduled_at(&self) -> Option<DateTime<Utc>> {
+        self.scheduled_at
+    }
+
     pub fn status_receiver(&self) -> watch::Receiver<JobStatus> {
         self.status_rx.clone()
     }
@@ -151,8 +520,56 @@ impl JobHandle {
     pub fn cancellation_token(&self) -> CancellationToken {
         self.cancel_token.clone()
     }
+
+    /// Wait for the job to finish and return its summary. Failed and canceled
+    /// jobs yield a summary with the final status and error message. This
+    /// drains the event stream, so it should not be combined with
+    /// [`JobHandle::take_events`].
+    pub async fn wait(self) -> DownloadSummary {
+        let mut error_message = None;
+        if let Some(mut events_rx) = self.take_events() {
+            while let Some(event) = events_rx.recv().await {
+                match event {
+                    DownloadEvent::Completed(summary) => return summary,
+                    DownloadEvent::Failed { message, .. } => error_message = Some(message),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut status_rx = self.status_rx.clone();
+        let status = status_rx
+            .wait_for(|status| {
+                matches!(
+                    status,
+                    JobStatus::Succeeded | JobStatus::Failed | JobStatus::Canceled
+                )
+            })
+            .await
+            .map(|status| *status)
+            .unwrap_or(JobStatus::Failed);
+
+        DownloadSummary {
+            id: self.id,
+            url: self.url,
+            source_url: None,
+            status,
+            title: None,
+            uploader: None,
+            description: None,
+            file_path: None,
+            audio_path: None,
+            extra_files: Vec::new(),
+            view_count: None,
+            like_count: None,
+            has_comments: false,
+            completed_at: Utc::now(),
+            error_message,
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct DownloaderService {
     inner: Arc<DownloaderInner>,
 }
@@ -160,51 +577,638 @@ pub struct DownloaderService {
 struct DownloaderInner {
     config: RwLock<Config>,
     history: HistoryRepository,
-    semaphore: RwLock<Arc<Semaphore>>,
+    semaphore: Arc<Semaphore>,
+    slot_limit: ParkingMutex<SlotLimit>,
+    running_ids: Arc<ParkingRwLock<HashSet<Uuid>>>,
+    tracked_jobs: Arc<ParkingRwLock<HashMap<Uuid, TrackedJob>>>,
+    completed_job_ttl: Duration,
+    event_sink: Option<mpsc::Sender<(Uuid, DownloadEvent)>>,
+    deduplicate: bool,
+    in_progress_dir: Option<PathBuf>,
+    counters: Arc<JobCounters>,
+    /// Boosted jobs waiting to be handed the next download slot that frees up,
+    /// ahead of the semaphore's own queue.
+    boosted_waiters: ParkingMutex<VecDeque<oneshot::Sender<Slot>>>,
+}
+
+/// How many download slots the semaphore should hold.
+struct SlotLimit {
+    max: usize,
+    /// Slots still held by running jobs that must be retired when they are
+    /// released, after the limit was lowered below the number in use.
+    to_retire: usize,
+}
+
+/// Builds a [`DownloaderService`]; see [`DownloaderService::builder`].
+#[derive(Default)]
+pub struct DownloaderServiceBuilder {
+    config: Option<Config>,
+    history: Option<HistoryRepository>,
+    event_sink: Option<mpsc::Sender<(Uuid, DownloadEvent)>>,
+    completed_job_ttl: Option<Duration>,
+    deduplicate: bool,
+    in_progress_dir: Option<PathBuf>,
+}
+
+impl DownloaderServiceBuilder {
+    /// Defaults to [`Config::default`].
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Defaults to the database at [`HistoryRepository::default_path`].
+    pub fn history(mut self, history: HistoryRepository) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Also send every job's events, tagged with the job ID, to `sink`.
+    pub fn event_sink(mut self, sink: mpsc::Sender<(Uuid, DownloadEvent)>) -> Self {
+        self.event_sink = Some(sink);
+        self
+    }
+
+    /// How long finished jobs remain visible through [`DownloaderService::status`].
+    pub fn completed_job_ttl(mut self, ttl: Duration) -> Self {
+        self.completed_job_ttl = Some(ttl);
+        self
+    }
+
+    /// Reject a URL with [`DownloadError::AlreadyQueued`] while another job
+    /// for it is still queued or running.
+    pub fn enable_deduplication(mut self, enabled: bool) -> Self {
+        self.deduplicate = enabled;
+        self
+    }
+
+    /// Save running jobs' progress as [`JobProgress`] files in `dir`.
+    pub fn in_progress_dir(mut self, dir: PathBuf) -> Self {
+        self.in_progress_dir = Some(dir);
+        self
+    }
+
+    pub fn build(self) -> Result<DownloaderService, SpaceDownloaderError> {
+        let config = self.config.unwrap_or_default();
+        config.validate()?;
+        if config.advanced.print_traffic {
+            warn!("print_traffic enabled: log output will be very verbose");
+        }
+        let history = match self.history {
+            Some(history) => history,
+            None => HistoryRepository::open(None)?,
+        };
+        let mut service = DownloaderService::from_parts(config, history);
+        let inner = Arc::get_mut(&mut service.inner).expect("service was just created");
+        inner.event_sink = self.event_sink;
+        inner.deduplicate = self.deduplicate;
+        inner.in_progress_dir = self.in_progress_dir;
+        if let Some(ttl) = self.completed_job_ttl {
+            inner.completed_job_ttl = ttl;
+        }
+        Ok(service)
+    }
+}
+
+struct TrackedJob {
+    url: String,
+    status_rx: watch::Receiver<JobStatus>,
+    progress_rx: watch::Receiver<Option<ProgressSnapshot>>,
+    finished_at: Option<Instant>,
+    output_dir: PathBuf,
+    cancel_token: CancellationToken,
+    destination: Arc<ParkingMutex<Option<PathBuf>>>,
+    /// Tells a job still waiting for a download slot to jump the queue; taken
+    /// on first use.
+    boost_tx: Option<oneshot::Sender<()>>,
+    request: Arc<ParkingMutex<DownloadRequest>>,
+}
+
+fn job_total_bytes(job: &TrackedJob) -> u64 {
+    job.progress_rx
+        .borrow()
+        .as_ref()
+        .and_then(|progress| progress.total_bytes)
+        .unwrap_or(0)
 }
 
 struct JobRuntime {
     id: Uuid,
-    request: DownloadRequest,
+    /// Shared with the [`TrackedJob`] so it can be edited until the job starts.
+    request: Arc<ParkingMutex<DownloadRequest>>,
     status_tx: watch::Sender<JobStatus>,
     progress_tx: watch::Sender<Option<ProgressSnapshot>>,
     events_tx: mpsc::Sender<DownloadEvent>,
     cancel_token: CancellationToken,
     download_settings: DownloadSettings,
     advanced_settings: AdvancedSettings,
-    history: HistoryRepository,
+    history: AsyncHistoryRepository,
     history_row_id: ParkingMutex<Option<i64>>,
+    /// Where this job's [`JobProgress`] is saved while it runs.
+    progress_file: Option<PathBuf>,
+    progress_saved_at: ParkingMutex<Option<Instant>>,
+    counters: Arc<JobCounters>,
+    running_ids: Arc<ParkingRwLock<HashSet<Uuid>>>,
+    tracked_jobs: Arc<ParkingRwLock<HashMap<Uuid, TrackedJob>>>,
+    /// The file yt-dlp last reported writing to.
+    destination: Arc<ParkingMutex<Option<PathBuf>>>,
 }
 
 impl DownloaderService {
+    /// Create a service whose history is kept in memory only, for embedding
+    /// and tests that should not touch the user's history database.
+    pub fn new_without_history(config: Config) -> Self {
+        let history =
+            HistoryRepository::open_in_memory().expect("in-memory history database should open");
+        Self::from_parts(config, history)
+    }
+
+    pub fn builder() -> DownloaderServiceBuilder {
+        DownloaderServiceBuilder::default()
+    }
+
+    /// Load the config at `path` (creating it with defaults if missing), open
+    /// the history database at [`HistoryRepository::default_path`] and build a
+    /// service from both. Downloads a previous run left unfinished are marked
+    /// failed; see [`HistoryRepository::open_and_cleanup_running`]. The
+    /// history is returned too, for callers that browse it.
+    pub fn from_config_file(
+        path: &Path,
+    ) -> Result<(Self, HistoryRepository), SpaceDownloaderError> {
+        Self::from_files(path, HistoryRepository::default_path())
+    }
+
+    fn from_files(
+        config_path: &Path,
+        history_path: PathBuf,
+    ) -> Result<(Self, HistoryRepository), SpaceDownloaderError> {
+        let (config, _) = Config::load_or_default(Some(config_path))?;
+        let (history, interrupted) = HistoryRepository::open_and_cleanup_running(history_path)?;
+        if interrupted > 0 {
+            info!("Marked {} interrupted downloads as failed", interrupted);
+        }
+        let service = Self::builder()
+            .config(config)
+            .history(history.clone())
+            .build()?;
+        Ok((service, history))
+    }
+
+    #[deprecated(note = "use `DownloaderService::builder` instead")]
     pub fn new(config: Config, history: HistoryRepository) -> Self {
+        Self::from_parts(config, history)
+    }
+
+    fn from_parts(config: Config, history: HistoryRepository) -> Self {
         let concurrency = config.download.effective_concurrency();
-        let semaphore = Arc::new(Semaphore::new(concurrency));
         Self {
             inner: Arc::new(DownloaderInner {
                 config: RwLock::new(config),
                 history,
-                semaphore: RwLock::new(semaphore),
+                semaphore: Arc::new(Semaphore::new(concurrency)),
+                slot_limit: ParkingMutex::new(SlotLimit {
+                    max: concurrency,
+                    to_retire: 0,
+                }),
+                running_ids: Arc::new(ParkingRwLock::new(HashSet::new())),
+                tracked_jobs: Arc::new(ParkingRwLock::new(HashMap::new())),
+                completed_job_ttl: DEFAULT_COMPLETED_JOB_TTL,
+                event_sink: None,
+                deduplicate: false,
+                in_progress_dir: None,
+                counters: Arc::default(),
+                boosted_waiters: ParkingMutex::default(),
             }),
         }
     }
 
+    /// Change how many downloads may run at once without touching the rest of
+    /// the config. Jobs already holding a slot keep running.
+    #[instrument(skip(self))]
+    pub async fn set_max_concurrency(&self, concurrency: usize) {
+        let effective = {
+            let mut config = self.inner.config.write().await;
+            config.download.concurrency = concurrency;
+            config.download.effective_concurrency()
+        };
+        self.resize_slots(effective);
+    }
+
+    /// Replace the download and advanced settings new jobs are queued with,
+    /// leaving the download slots alone. The concurrency in `download` is
+    /// ignored; use [`set_max_concurrency`](Self::set_max_concurrency) for it.
+    #[instrument(skip_all)]
+    pub async fn update_settings(
+        &self,
+        mut download: DownloadSettings,
+        advanced: AdvancedSettings,
+    ) {
+        let mut config = self.inner.config.write().await;
+        download.concurrency = config.download.concurrency;
+        config.download = download;
+        config.advanced = advanced;
+    }
+
+    /// Grow or shrink the download semaphore to `max` slots. Queued jobs keep
+    /// waiting on the same semaphore, so added slots start them right away;
+    /// slots held by running jobs are retired as those jobs finish.
+    fn resize_slots(&self, max: usize) {
+        let mut limit = self.inner.slot_limit.lock();
+        if max > limit.max {
+            let mut added = max - limit.max;
+            let kept = added.min(limit.to_retire);
+            limit.to_retire -= kept;
+            added -= kept;
+            // Boosted jobs get the new slots first, as in `release_slot`.
+            let mut waiters = self.inner.boosted_waiters.lock();
+            while added > 0 {
+                let Some(waiter) = waiters.pop_front() else {
+                    break;
+                };
+                if waiter.send(Slot::Added).is_ok() {
+                    added -= 1;
+                }
+            }
+            self.inner.semaphore.add_permits(added);
+        } else {
+            let removed = limit.max - max;
+            let forgotten = self.inner.semaphore.forget_permits(removed);
+            limit.to_retire += removed - forgotten;
+        }
+        limit.max = max;
+    }
+
+    /// A snapshot of the config new jobs are queued with.
+    #[instrument(skip_all)]
+    pub async fn get_config(&self) -> Config {
+        self.inner.config.read().await.clone()
+    }
+
+    #[instrument(skip_all)]
     pub async fn update_config(&self, config: Config) {
         let concurrency = config.download.effective_concurrency();
         {
             let mut current = self.inner.config.write().await;
             *current = config;
         }
-        let mut semaphore = self.inner.semaphore.write().await;
-        *semaphore = Arc::new(Semaphore::new(concurrency));
+        self.resize_slots(concurrency);
+    }
+
+    /// Apply `config` and return another handle to the same service.
+    ///
+    /// Jobs that are already queued or running keep the settings they were
+    /// queued with and are not interrupted; only new jobs see the new config.
+    #[instrument(skip_all)]
+    pub async fn clone_with_config(&self, config: Config) -> DownloaderService {
+        self.update_config(config).await;
+        self.clone()
+    }
+
+    /// The semaphore limiting concurrent downloads.
+    #[cfg(feature = "tower-service")]
+    pub(crate) fn semaphore(&self) -> Arc<Semaphore> {
+        self.inner.semaphore.clone()
+    }
+
+    pub fn metrics(&self) -> DownloaderMetrics {
+        let counters = &self.inner.counters;
+        let mut metrics = DownloaderMetrics {
+            total_queued: counters.queued.load(Ordering::Relaxed),
+            total_succeeded: counters.succeeded.load(Ordering::Relaxed),
+            total_failed: counters.failed.load(Ordering::Relaxed),
+            total_canceled: counters.canceled.load(Ordering::Relaxed),
+            total_bytes_downloaded: counters.bytes_downloaded.load(Ordering::Relaxed),
+            ..DownloaderMetrics::default()
+        };
+        for job in self.inner.tracked_jobs.read().values() {
+            match *job.status_rx.borrow() {
+                JobStatus::Queued => metrics.current_queued += 1,
+                JobStatus::Running => metrics.current_running += 1,
+                _ => continue,
+            }
+            metrics.total_bytes_in_queue += job_total_bytes(job);
+        }
+        metrics
+    }
+
+    /// Sum of the total sizes reported so far by queued and running jobs.
+    ///
+    /// Jobs that have not reported progress yet count as 0, so the estimate
+    /// grows as downloads start.
+    pub fn total_bytes_in_queue(&self) -> u64 {
+        self.sum_over_unfinished_jobs(job_total_bytes)
+    }
+
+    /// Bytes still to download for queued and running jobs, from the sizes
+    /// reported so far. Like [`total_bytes_in_queue`](Self::total_bytes_in_queue),
+    /// jobs that have not reported a size yet count as 0.
+    pub fn bytes_remaining_in_queue(&self) -> u64 {
+        self.sum_over_unfinished_jobs(|job| {
+            job.progress_rx
+                .borrow()
+                .as_ref()
+                .and_then(|progress| progress.bytes_remaining().or(progress.total_bytes))
+                .unwrap_or(0)
+        })
+    }
+
+    fn sum_over_unfinished_jobs(&self, bytes: impl Fn(&TrackedJob) -> u64) -> u64 {
+        self.inner
+            .tracked_jobs
+            .read()
+            .values()
+            .filter(|job| {
+                matches!(
+                    *job.status_rx.borrow(),
+                    JobStatus::Queued | JobStatus::Running
+                )
+            })
+            .map(bytes)
+            .sum()
+    }
+
+    /// Cancel job `id`, wait for yt-dlp to exit, then delete the partial files
+    /// (`.part`, `.ytdl`, `.tmp`) it left for the file it was writing.
+    ///
+    /// Returns whether any files were deleted; `false` also means the job is
+    /// unknown or had not started writing yet.
+    #[instrument(skip_all, fields(job_id = %id))]
+    pub async fn abort_and_delete(&self, id: Uuid) -> Result<bool, DownloadError> {
+        let Some((cancel_token, mut status_rx, output_dir, destination)) =
+            self.inner.tracked_jobs.read().get(&id).map(|job| {
+                (
+                    job.cancel_token.clone(),
+                    job.status_rx.clone(),
+                    job.output_dir.clone(),
+                    job.destination.clone(),
+                )
+            })
+        else {
+            return Ok(false);
+        };
+
+        cancel_token.cancel();
+        status_rx
+            .wait_for(|status| {
+                matches!(
+                    status,
+                    JobStatus::Succeeded | JobStatus::Failed | JobStatus::Canceled
+                )
+            })
+            .await
+            .ok();
+
+        let Some(destination) = destination.lock().clone() else {
+            return Ok(false);
+        };
+        let deleted =
+            tokio::task::spawn_blocking(move || remove_partial_files(&output_dir, &destination))
+                .await
+                .map_err(|source| DownloadError::Join { source })?
+                .map_err(|source| DownloadError::Io { source })?;
+        Ok(deleted > 0)
+    }
+
+    /// IDs of jobs that have been queued and not yet finished.
+    pub fn running_job_ids(&self) -> Vec<Uuid> {
+        self.inner.running_ids.read().iter().copied().collect()
+    }
+
+    /// Snapshot of a job's state, or `None` if the job is unknown or finished
+    /// longer ago than the completed-job TTL.
+    pub fn status(&self, id: Uuid) -> Option<JobState> {
+        self.prune_finished_jobs();
+        let jobs = self.inner.tracked_jobs.read();
+        let job = jobs.get(&id)?;
+        let status = *job.status_rx.borrow();
+        let progress = job.progress_rx.borrow().clone();
+        Some(JobState {
+            id,
+            url: job.url.clone(),
+            status,
+            progress,
+        })
+    }
+
+    /// Move job `id` to the front of the queue so it gets the next download
+    /// slot that frees up, ahead of jobs queued before it.
+    ///
+    /// A scheduled job keeps its start time and is boosted once it is due.
+    /// Fails with [`DownloadError::NotQueued`] if the job is unknown, already
+    /// started or was boosted before.
+    pub fn queue_with_priority_boost(&self, id: Uuid) -> Result<(), DownloadError> {
+        let boost_tx = self
+            .inner
+            .tracked_jobs
+            .write()
+            .get_mut(&id)
+            .filter(|job| {
+                matches!(
+                    *job.status_rx.borrow(),
+                    JobStatus::Queued | JobStatus::Scheduled
+                )
+            })
+            .and_then(|job| job.boost_tx.take())
+            .ok_or(DownloadError::NotQueued(id))?;
+        boost_tx.send(()).map_err(|()| DownloadError::NotQueued(id))
+    }
+
+    /// Change the request of job `id` before it starts, e.g. to pick another
+    /// output directory or format.
+    ///
+    /// `mutation` works on a copy that replaces the request only if it is still
+    /// valid. The job's [`JobHandle::url`] is not updated. Fails with
+    /// [`DownloadError::NotQueued`] if the job is unknown and
+    /// [`DownloadError::JobAlreadyStarted`] once it has left the queue.
+    #[instrument(skip_all, fields(job_id = %id))]
+    pub async fn update_request_in_queue(
+        &self,
+        id: Uuid,
+        mutation: impl FnOnce(&mut DownloadRequest),
+    ) -> Result<(), DownloadError> {
+        let (shared, status_rx) = self
+            .inner
+            .tracked_jobs
+            .read()
+            .get(&id)
+            .map(|job| (job.request.clone(), job.status_rx.clone()))
+            .ok_or(DownloadError::NotQueued(id))?;
+        let waiting = || {
+            matches!(
+                *status_rx.borrow(),
+                JobStatus::Queued | JobStatus::Scheduled
+            )
+        };
+        if !waiting() {
+            return Err(DownloadError::JobAlreadyStarted(id));
+        }
+
+        let mut request = shared.lock().clone();
+        mutation(&mut request);
+        url::Url::parse(&request.url)
+            .map_err(|_| DownloadError::InvalidUrl(request.url.clone()))?;
+        request
+            .validate()
+            .map_err(|source| DownloadError::InvalidRequest { source })?;
+        fs::create_dir_all(&request.output_dir)
+            .await
+            .map_err(|source| DownloadError::Io { source })?;
+
+        {
+            // The job reads its request only after it is marked running, so
+            // checking again under the lock is enough to win the race.
+            let mut current = shared.lock();
+            if !waiting() {
+                return Err(DownloadError::JobAlreadyStarted(id));
+            }
+            *current = request.clone();
+        }
+        if let Some(job) = self.inner.tracked_jobs.write().get_mut(&id) {
+            job.url = request.url.clone();
+            job.output_dir = request.output_dir.clone();
+        }
+        AsyncHistoryRepository::new(self.inner.history.clone())
+            .update_request(id, &request.url, request.format)
+            .await
+            .map_err(download_error_from_history)
+    }
+
+    fn prune_finished_jobs(&self) {
+        let ttl = self.inner.completed_job_ttl;
+        self.inner
+            .tracked_jobs
+            .write()
+            .retain(|_, job| job.finished_at.is_none_or(|at| at.elapsed() < ttl));
+    }
+
+    /// Queue a download and call `on_complete` from the runtime once it
+    /// finishes, for callers that do not need to track the job themselves.
+    #[instrument(skip_all)]
+    pub async fn queue_with_callback<F>(
+        &self,
+        request: DownloadRequest,
+        on_complete: F,
+    ) -> Result<Uuid, DownloadError>
+    where
+        F: FnOnce(DownloadSummary) + Send + 'static,
+    {
+        let handle = self.queue(request).await?;
+        let id = handle.id;
+        tokio::spawn(async move {
+            on_complete(handle.wait().await);
+        });
+        Ok(id)
+    }
+
+    /// Queue one download per URL in `urls`, e.g. lines pasted from the
+    /// clipboard, using `settings` instead of the configured download settings.
+    ///
+    /// URLs are trimmed and blank entries are skipped, so an empty list (or one
+    /// with only blank lines) returns `Ok` with no jobs. Each remaining URL gets
+    /// its own result so callers can report exactly which ones failed; if none
+    /// of them is a valid URL, nothing is queued and
+    /// [`DownloadError::EmptyBatch`] is returned instead.
+    #[instrument(skip_all, fields(urls = urls.len()))]
+    pub async fn queue_url_list(
+        &self,
+        urls: &[String],
+        settings: DownloadSettings,
+    ) -> Result<Vec<Result<JobHandle, DownloadError>>, SpaceDownloaderError> {
+        let urls: Vec<String> = urls
+            .iter()
+            .map(|url| url.trim())
+            .filter(|url| !url.is_empty())
+            .map(normalize_url)
+            .collect();
+        if !urls.is_empty() && urls.iter().all(|url| url::Url::parse(url).is_err()) {
+            return Err(DownloadError::EmptyBatch.into());
+        }
+
+        let mut results = Vec::with_capacity(urls.len());
+        for url in urls {
+            let request = DownloadRequest::new(url, PathBuf::new(), settings.format);
+            results.push(
+                self.queue_with_settings(request, Some(settings.clone()), None)
+                    .await,
+            );
+        }
+        Ok(results)
+    }
+
+    #[instrument(skip(self, request), fields(job_id = tracing::field::Empty))]
+    pub async fn queue(&self, request: DownloadRequest) -> Result<JobHandle, DownloadError> {
+        self.queue_with_settings(request, None, None).await
+    }
+
+    /// Queue the most recent `limit` failed downloads in `history` again,
+    /// e.g. after a network outage.
+    ///
+    /// Failures that would happen again ([`UnsupportedSite`](DownloadError::UnsupportedSite)
+    /// and geo-blocking), URLs that have since downloaded successfully and
+    /// repeats of a URL already being requeued are skipped. Each remaining
+    /// entry gets its own result.
+    #[instrument(skip(self, history))]
+    pub async fn requeue_failed(
+        &self,
+        history: &HistoryRepository,
+        limit: usize,
+    ) -> Result<Vec<Result<JobHandle, DownloadError>>, DownloadError> {
+        let history = AsyncHistoryRepository::new(history.clone());
+        let failed = history
+            .recent_with(limit, HistoryQuery::new().status(JobStatus::Failed))
+            .await
+            .map_err(download_error_from_history)?;
+
+        let mut seen = HashSet::new();
+        let mut results = Vec::new();
+        for entry in failed {
+            if entry
+                .error_code
+                .as_deref()
+                .is_some_and(|code| NOT_REQUEUED_ERROR_CODES.contains(&code))
+                || !seen.insert(entry.url.clone())
+                || history
+                    .url_was_downloaded(&entry.url)
+                    .await
+                    .map_err(download_error_from_history)?
+            {
+                continue;
+            }
+            results.push(self.queue(entry.into_download_request()).await);
+        }
+        Ok(results)
     }
 
-    pub async fn queue(&self, mut request: DownloadRequest) -> Result<JobHandle, DownloadError> {
+    /// Queue `request` to start at `at`, e.g. during off-peak hours.
+    ///
+    /// The job stays [`JobStatus::Scheduled`] until then and only afterwards
+    /// waits for a download slot like any other job. A time in the past starts
+    /// it right away.
+    #[instrument(skip(self, request), fields(job_id = tracing::field::Empty))]
+    pub async fn queue_scheduled(
+        &self,
+        request: DownloadRequest,
+        at: DateTime<Utc>,
+    ) -> Result<JobHandle, DownloadError> {
+        self.queue_with_settings(request, None, Some(at)).await
+    }
+
+    /// Queue `request`, overriding the configured download settings when
+    /// `download_settings` is given and holding it back until `start_at`.
+    async fn queue_with_settings(
+        &self,
+        mut request: DownloadRequest,
+        download_settings: Option<DownloadSettings>,
+        start_at: Option<DateTime<Utc>>,
+    ) -> Result<JobHandle, DownloadError> {
         url::Url::parse(&request.url)
             .map_err(|_| DownloadError::InvalidUrl(request.url.clone()))?;
+        request
+            .validate()
+            .map_err(|source| DownloadError::InvalidRequest { source })?;
 
         let config = self.inner.config.read().await.clone();
-        let download_settings = config.download.clone();
+        let download_settings = download_settings.unwrap_or_else(|| config.download.clone());
         let advanced_settings = config.advanced.clone();
 
         if request.output_dir.as_os_str().is_empty() {
@@ -219,95 +1223,186 @@ impl DownloaderService {
             request.cookie_file = advanced_settings.cookie_file.clone();
         }
 
-        fs::create_dir_all(&request.output_dir)
-            .await
-            .map_err(|source| DownloadError::Io { source })?;
+        let job_id = request.id.unwrap_or_else(Uuid::new_v4);
+        // Fills in the `job_id` field of the calling public method's span.
+        tracing::Span::current().record("job_id", tracing::field::display(job_id));
+        let history = AsyncHistoryRepository::new(self.inner.history.clone());
+        // Jobs of earlier sessions are only in the history; jobs of this one
+        // are caught below, before they reach it.
+        if request.id.is_some()
+            && history
+                .entry_exists(job_id)
+                .await
+                .map_err(download_error_from_history)?
+        {
+            return Err(DownloadError::DuplicateJobId(job_id));
+        }
 
-        let job_id = Uuid::new_v4();
-        let (status_tx, status_rx) = watch::channel(JobStatus::Queued);
+        let initial_status = if start_at.is_some() {
+            JobStatus::Scheduled
+        } else {
+            JobStatus::Queued
+        };
+        let (status_tx, status_rx) = watch::channel(initial_status);
         let (progress_tx, progress_rx) = watch::channel::<Option<ProgressSnapshot>>(None);
-        let (events_tx, events_rx) = mpsc::channel(128);
+        let event_capacity = event_channel_capacity(&advanced_settings);
+        let (events_tx, events_rx) = mpsc::channel(event_capacity);
+        let events_tx = match &self.inner.event_sink {
+            Some(sink) => forward_events(job_id, events_tx, sink.clone(), event_capacity),
+            None => events_tx,
+        };
         let cancel_token = CancellationToken::new();
+        let (boost_tx, boost_rx) = oneshot::channel();
 
-        let history = self.inner.history.clone();
         let handle_url = request.url.clone();
-        let history_url = handle_url.clone();
-        let history_format = request.format;
-        let history_row = tokio::task::spawn_blocking(move || {
-            history.record_queued(job_id, &history_url, history_format)
-        })
-        .await
-        .map_err(|source| DownloadError::Join { source })?
-        .map_err(download_error_from_history)?;
+        let output_dir = request.output_dir.clone();
+        let format = request.format;
+        let archive_file = request.archive_file.clone();
+        let destination = Arc::new(ParkingMutex::new(None));
+        let request = Arc::new(ParkingMutex::new(request));
+
+        // Claim the URL and ID under one lock so concurrent calls cannot both
+        // pass the duplicate checks.
+        self.prune_finished_jobs();
+        {
+            let mut jobs = self.inner.tracked_jobs.write();
+            if self.inner.deduplicate
+                && jobs
+                    .values()
+                    .any(|job| job.finished_at.is_none() && job.url == handle_url)
+            {
+                return Err(DownloadError::AlreadyQueued(handle_url));
+            }
+            if jobs.contains_key(&job_id) {
+                return Err(DownloadError::DuplicateJobId(job_id));
+            }
+            jobs.insert(
+                job_id,
+                TrackedJob {
+                    url: handle_url.clone(),
+                    status_rx: status_rx.clone(),
+                    progress_rx: progress_rx.clone(),
+                    finished_at: None,
+                    output_dir: output_dir.clone(),
+                    cancel_token: cancel_token.clone(),
+                    destination: destination.clone(),
+                    boost_tx: Some(boost_tx),
+                    request: request.clone(),
+                },
+            );
+        }
+
+        let prepared = async {
+            fs::create_dir_all(&output_dir)
+                .await
+                .map_err(|source| DownloadError::Io { source })?;
+            let history_row = history
+                .record_queued(job_id, &handle_url, format)
+                .await
+                .map_err(download_error_from_history)?;
+            if let Some(archive) = &archive_file {
+                history
+                    .set_download_archive(job_id, archive)
+                    .await
+                    .map_err(download_error_from_history)?;
+            }
+            Ok(history_row)
+        }
+        .await;
+        let history_row = match prepared {
+            Ok(history_row) => history_row,
+            Err(err) => {
+                self.inner.tracked_jobs.write().remove(&job_id);
+                return Err(err);
+            }
+        };
 
         let job = Arc::new(JobRuntime {
             id: job_id,
-            request,
+            request: request.clone(),
             status_tx,
             progress_tx,
             events_tx,
             cancel_token: cancel_token.clone(),
             download_settings,
             advanced_settings,
-            history: self.inner.history.clone(),
+            history,
             history_row_id: ParkingMutex::new(Some(history_row)),
+            progress_file: self
+                .inner
+                .in_progress_dir
+                .as_ref()
+                .map(|dir| dir.join(format!("{job_id}.json"))),
+            progress_saved_at: ParkingMutex::new(None),
+            counters: self.inner.counters.clone(),
+            running_ids: self.inner.running_ids.clone(),
+            tracked_jobs: self.inner.tracked_jobs.clone(),
+            destination,
         });
+        self.inner.running_ids.write().insert(job_id);
+        self.inner.counters.queued.fetch_add(1, Ordering::Relaxed);
 
-        let semaphore = { self.inner.semaphore.read().await.clone() };
+        let inner = self.inner.clone();
         let job_for_task = job.clone();
 
         tokio::spawn(async move {
-            let permit = tokio::select! {
-                permit = semaphore.acquire_owned() => {
-                    match permit {
-                        Ok(permit) => permit,
-                        Err(error) => {
-                            error!("download job {} failed to start: {error}", job_for_task.id);
-                            job_for_task.status_tx.send_replace(JobStatus::Failed);
-                            let message = "failed to acquire download slot".to_string();
-                            job_for_task
-                                .events_tx
-                                .send(DownloadEvent::Failed(message.clone()))
-                                .await
-                                .ok();
-                            finalize_history(&job_for_task, JobStatus::Failed, None, Some(message)).await;
+            if let Some(at) = start_at {
+                let delay = (at - Utc::now()).to_std().unwrap_or_default();
+                tokio::select! {
+                    _ = time::sleep(delay) => {}
+                    _ = job_for_task.cancel_token.cancelled() => {
+                        finish_canceled_before_start(&job_for_task).await;
+                        return;
+                    }
+                }
+                job_for_task.status_tx.send_replace(JobStatus::Queued);
+                job_for_task
+                    .events_tx
+                    .send(DownloadEvent::Status(JobStatus::Queued))
+                    .await
+                    .ok();
+            }
+
+            let semaphore = inner.semaphore.clone();
+            let acquired = tokio::select! {
+                permit = semaphore.clone().acquire_owned() => permit.ok().map(Slot::Permit),
+                Ok(()) = boost_rx => {
+                    debug!("download job {} boosted to the front of the queue", job_for_task.id);
+                    tokio::select! {
+                        permit = wait_for_boosted_slot(&inner, semaphore) => permit,
+                        _ = job_for_task.cancel_token.cancelled() => {
+                            finish_canceled_before_start(&job_for_task).await;
                             return;
                         }
                     }
                 }
                 _ = job_for_task.cancel_token.cancelled() => {
-                    job_for_task.status_tx.send_replace(JobStatus::Canceled);
-                    job_for_task
-                        .events_tx
-                        .send(DownloadEvent::Status(JobStatus::Canceled))
-                        .await
-                        .ok();
-                    finalize_history(
-                        &job_for_task,
-                        JobStatus::Canceled,
-                        None,
-                        Some("download canceled".to_string()),
-                    )
-                    .await;
+                    finish_canceled_before_start(&job_for_task).await;
                     return;
                 }
             };
-
-            if job_for_task.cancel_token.is_cancelled() {
-                job_for_task.status_tx.send_replace(JobStatus::Canceled);
+            let Some(permit) = acquired else {
+                error!(
+                    "download job {} failed to start: no download slot",
+                    job_for_task.id
+                );
+                job_for_task.status_tx.send_replace(JobStatus::Failed);
+                let message = "failed to acquire download slot".to_string();
                 job_for_task
                     .events_tx
-                    .send(DownloadEvent::Status(JobStatus::Canceled))
+                    .send(DownloadEvent::Failed {
+                        message: message.clone(),
+                        help: None,
+                    })
                     .await
                     .ok();
-                finalize_history(
-                    &job_for_task,
-                    JobStatus::Canceled,
-                    None,
-                    Some("download canceled".to_string()),
-                )
-                .await;
-                drop(permit);
+                finalize_history(&job_for_task, JobStatus::Failed, None, Some(message), None).await;
+                return;
+            };
+
+            if job_for_task.cancel_token.is_cancelled() {
+                finish_canceled_before_start(&job_for_task).await;
+                release_slot(&inner, permit);
                 return;
             }
 
@@ -315,8 +1410,9 @@ impl DownloaderService {
                 error!("download job {} failed: {error}", job_for_task.id);
             }
 
-            drop(permit);
-        });
+            release_slot(&inner, permit);
+        }
+        .instrument(info_span!("download_job", job_id = %job_id)));
 
         Ok(JobHandle {
             id: job_id,
@@ -325,11 +1421,74 @@ impl DownloaderService {
             progress_rx,
             events_rx: ParkingMutex::new(Some(events_rx)),
             cancel_token,
+            scheduled_at: start_at,
         })
     }
 }
 
-async fn run_job(job: Arc<JobRuntime>) -> Result<(), DownloadError> {
+/// Wait for a running job to hand over its slot, or take a free one if there
+/// is no queue to jump.
+/// A download slot held by a job while it runs.
+enum Slot {
+    Permit(OwnedSemaphorePermit),
+    /// A slot added by raising the limit and handed straight to a boosted job,
+    /// without going through the semaphore.
+    Added,
+}
+
+async fn wait_for_boosted_slot(inner: &DownloaderInner, semaphore: Arc<Semaphore>) -> Option<Slot> {
+    let (permit_tx, permit_rx) = oneshot::channel();
+    inner.boosted_waiters.lock().push_back(permit_tx);
+    // Permits are only left available when nobody is waiting on the semaphore.
+    // The sender stays queued; `release_slot` skips it once this returns.
+    if let Ok(permit) = semaphore.try_acquire_owned() {
+        return Some(Slot::Permit(permit));
+    }
+    permit_rx.await.ok()
+}
+
+/// Give a finished job's slot to the oldest boosted job still waiting, or back
+/// to the semaphore if there is none. Slots over a lowered limit are retired.
+fn release_slot(inner: &DownloaderInner, mut slot: Slot) {
+    {
+        let mut limit = inner.slot_limit.lock();
+        if limit.to_retire > 0 {
+            limit.to_retire -= 1;
+            if let Slot::Permit(permit) = slot {
+                permit.forget();
+            }
+            return;
+        }
+    }
+    let mut waiters = inner.boosted_waiters.lock();
+    while let Some(waiter) = waiters.pop_front() {
+        match waiter.send(slot) {
+            Ok(()) => return,
+            Err(returned) => slot = returned,
+        }
+    }
+    if let Slot::Added = slot {
+        inner.semaphore.add_permits(1);
+    }
+}
+
+async fn finish_canceled_before_start(job: &Arc<JobRuntime>) {
+    job.status_tx.send_replace(JobStatus::Canceled);
+    job.events_tx
+        .send(DownloadEvent::Status(JobStatus::Canceled))
+        .await
+        .ok();
+    finalize_history(
+        job,
+        JobStatus::Canceled,
+        None,
+        Some("download canceled".to_string()),
+        None,
+    )
+    .await;
+}
+
+async fn run_job(job: Arc<JobRuntime>) -> Result<(), DownloadError> {
     info!("starting download job {}", job.id);
     job.status_tx.send_replace(JobStatus::Running);
     job.events_tx
@@ -337,8 +1496,16 @@ async fn run_job(job: Arc<JobRuntime>) -> Result<(), DownloadError> {
         .await
         .ok();
 
-    match execute_download(job.clone()).await {
+    match execute_with_retries(&job).await {
         Ok(summary) => {
+            if !summary.extra_files.is_empty() {
+                // History keeps a single path per job.
+                info!(
+                    "download job {} extracted {} chapter tracks",
+                    job.id,
+                    summary.extra_files.len()
+                );
+            }
             job.status_tx.send_replace(JobStatus::Succeeded);
             job.events_tx
                 .send(DownloadEvent::Completed(summary.clone()))
@@ -349,19 +1516,34 @@ async fn run_job(job: Arc<JobRuntime>) -> Result<(), DownloadError> {
                 JobStatus::Succeeded,
                 summary.file_path.as_deref(),
                 None,
+                None,
             )
             .await;
 
-            if summary.title.is_some() || summary.uploader.is_some() {
-                let history = job.history.clone();
-                let title = summary.title.clone();
-                let uploader = summary.uploader.clone();
-                let job_id = job.id;
-                tokio::task::spawn_blocking(move || {
-                    history.update_metadata(job_id, title.as_deref(), uploader.as_deref())
-                })
-                .await
-                .ok();
+            if summary.title.is_some()
+                || summary.uploader.is_some()
+                || summary.description.is_some()
+            {
+                job.history
+                    .update_metadata(
+                        job.id,
+                        summary.title.as_deref(),
+                        summary.uploader.as_deref(),
+                        summary.description.as_deref(),
+                    )
+                    .await
+                    .ok();
+            }
+
+            if summary.view_count.is_some() || summary.like_count.is_some() {
+                job.history
+                    .update_counts(job.id, summary.view_count, summary.like_count)
+                    .await
+                    .ok();
+            }
+
+            if let Some(source_url) = &summary.source_url {
+                job.history.update_source_url(job.id, source_url).await.ok();
             }
 
             info!("download job {} succeeded", job.id);
@@ -378,10 +1560,13 @@ async fn run_job(job: Arc<JobRuntime>) -> Result<(), DownloadError> {
             let event = if status == JobStatus::Canceled {
                 DownloadEvent::Status(JobStatus::Canceled)
             } else {
-                DownloadEvent::Failed(message.clone())
+                DownloadEvent::Failed {
+                    message: message.clone(),
+                    help: error.help(),
+                }
             };
             job.events_tx.send(event).await.ok();
-            finalize_history(&job, status, None, Some(message.clone())).await;
+            finalize_history(&job, status, None, Some(message.clone()), Some(&error)).await;
             if status == JobStatus::Canceled {
                 warn!("download job {} canceled", job.id);
             } else {
@@ -392,6 +1577,66 @@ async fn run_job(job: Arc<JobRuntime>) -> Result<(), DownloadError> {
     }
 }
 
+async fn execute_with_retries(job: &Arc<JobRuntime>) -> Result<DownloadSummary, DownloadError> {
+    let max_retries = job.download_settings.max_retries;
+    let mut attempt: u8 = 0;
+    loop {
+        match execute_download(job.clone()).await {
+            Err(error) if error.is_retriable() && attempt < max_retries => {
+                attempt += 1;
+                let delay = retry_wait(&error, attempt, &job.advanced_settings);
+                let message = if matches!(error, DownloadError::RateLimited) {
+                    format!(
+                        "rate limited, cooling down for {}s before attempt {}/{max_retries}",
+                        delay.as_secs(),
+                        attempt + 1
+                    )
+                } else {
+                    format!(
+                        "attempt {attempt}/{max_retries} failed, retrying in {}s: {error}",
+                        delay.as_secs()
+                    )
+                };
+                warn!("download job {}: {message}", job.id);
+                job.events_tx
+                    .send(DownloadEvent::LogLine(message))
+                    .await
+                    .ok();
+                tokio::select! {
+                    _ = job.cancel_token.cancelled() => return Err(DownloadError::Canceled),
+                    _ = time::sleep(delay) => {}
+                }
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Exponential backoff between retries, capped at one minute.
+fn retry_delay(attempt: u8) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(attempt as u32).min(60))
+}
+
+/// How long to wait before the next attempt. Rate-limited jobs wait out the
+/// configured cooldown instead of the usual backoff.
+fn retry_wait(error: &DownloadError, attempt: u8, advanced: &AdvancedSettings) -> Duration {
+    match error {
+        DownloadError::RateLimited => rate_limit_cooldown(advanced),
+        _ => retry_delay(attempt),
+    }
+}
+
+fn rate_limit_cooldown(advanced: &AdvancedSettings) -> Duration {
+    Duration::from_secs(advanced.rate_limit_cooldown_sec)
+}
+
+/// Output observed while a single yt-dlp attempt runs.
+#[derive(Debug, Default)]
+struct AttemptState {
+    destination: Option<PathBuf>,
+    rate_limited: bool,
+}
+
 async fn execute_download(job: Arc<JobRuntime>) -> Result<DownloadSummary, DownloadError> {
     let mut command = build_command(&job);
     command.stdout(std::process::Stdio::piped());
@@ -399,13 +1644,18 @@ async fn execute_download(job: Arc<JobRuntime>) -> Result<DownloadSummary, Downl
 
     let mut child = command
         .spawn()
-        .map_err(|source| DownloadError::Spawn { source })?;
+        .map_err(|source| spawn_error(&command, source))?;
     let stderr = child.stderr.take().ok_or_else(|| DownloadError::Spawn {
         source: std::io::Error::other("missing stderr"),
     })?;
     let mut stderr_lines = BufReader::new(stderr).lines();
     let mut stderr_buffer = String::new();
-    let mut destination_path: Option<PathBuf> = None;
+    // Stops the network monitor however this attempt ends.
+    let _network_monitor = job
+        .download_settings
+        .use_native_progress
+        .then(|| spawn_network_monitor(job.clone()).drop_guard());
+    let mut attempt_state = AttemptState::default();
 
     loop {
         tokio::select! {
@@ -421,7 +1671,7 @@ async fn execute_download(job: Arc<JobRuntime>) -> Result<DownloadSummary, Downl
                             stderr_buffer.push('\n');
                         }
                         stderr_buffer.push_str(&line);
-                        handle_process_line(&job, &line, &mut destination_path).await;
+                        handle_process_line(&job, &line, &mut attempt_state).await;
                     }
                     Ok(None) => break,
                     Err(source) => return Err(DownloadError::Io { source }),
@@ -430,7 +1680,12 @@ async fn execute_download(job: Arc<JobRuntime>) -> Result<DownloadSummary, Downl
         }
     }
 
-    let timeout = job.download_settings.timeout_sec;
+    // Live streams run until the broadcast ends; see `AdvancedSettings::is_live`.
+    let timeout = if job.advanced_settings.is_live() {
+        0
+    } else {
+        job.download_settings.timeout_sec
+    };
     let status = if timeout > 0 {
         match time::timeout(Duration::from_secs(timeout), child.wait()).await {
             Ok(result) => result.map_err(|source| DownloadError::Io { source })?,
@@ -446,29 +1701,73 @@ async fn execute_download(job: Arc<JobRuntime>) -> Result<DownloadSummary, Downl
             .map_err(|source| DownloadError::Io { source })?
     };
     if !status.success() {
-        return Err(DownloadError::CommandFailed {
-            status: status.code(),
-            stderr: stderr_buffer,
-        });
+        let error = match exit_code(&status) {
+            Some(code) => {
+                let url = job.request.lock().url.clone();
+                classify_process_result(code, &stderr_buffer, &url)
+            }
+            None => DownloadError::CommandFailed {
+                status: None,
+                stderr: stderr_buffer,
+            },
+        };
+        if attempt_state.rate_limited && matches!(error, DownloadError::CommandFailed { .. }) {
+            return Err(DownloadError::RateLimited);
+        }
+        return Err(error);
     }
 
+    let request = job.request.lock().clone();
     let metadata = tokio::task::spawn_blocking({
-        let output_dir = job.request.output_dir.clone();
-        move || read_latest_metadata(&output_dir)
+        let output_dir = request.output_dir.clone();
+        let keep_info_json = job.download_settings.keep_info_json;
+        move || take_latest_metadata(&output_dir, keep_info_json)
     })
     .await
     .map_err(|source| DownloadError::Join { source })?;
 
+    // yt-dlp only reports how many segments it found on the console; the
+    // timings are in the info JSON.
+    for chapter in metadata.iter().flat_map(|m| &m.sponsorblock_chapters) {
+        job.events_tx
+            .send(DownloadEvent::SponsorBlockMark {
+                start_secs: chapter.start_time as u64,
+                end_secs: chapter.end_time.ceil() as u64,
+                category: chapter.category.clone().unwrap_or_default(),
+            })
+            .await
+            .ok();
+    }
+
+    let file_path = metadata
+        .as_ref()
+        .and_then(|m| m.file_path.clone())
+        .or(attempt_state.destination.clone());
+    let audio_path = match request.mode {
+        DownloadMode::Audio => None,
+        DownloadMode::AudioVideo { .. } => file_path
+            .as_deref()
+            .and_then(|video| extracted_audio_path(video, request.format)),
+    };
+    let extra_files = match &file_path {
+        Some(path) if job.advanced_settings.extract_chapters_as_tracks => chapter_track_files(path),
+        _ => Vec::new(),
+    };
+
     let summary = DownloadSummary {
         id: job.id,
-        url: job.request.url.clone(),
+        url: request.url,
+        source_url: metadata.as_ref().and_then(|m| m.source_url.clone()),
         status: JobStatus::Succeeded,
         title: metadata.as_ref().and_then(|m| m.title.clone()),
         uploader: metadata.as_ref().and_then(|m| m.uploader.clone()),
-        file_path: metadata
-            .as_ref()
-            .and_then(|m| m.file_path.clone())
-            .or(destination_path.clone()),
+        description: metadata.as_ref().and_then(|m| m.description.clone()),
+        file_path,
+        audio_path,
+        extra_files,
+        view_count: metadata.as_ref().and_then(|m| m.view_count),
+        like_count: metadata.as_ref().and_then(|m| m.like_count),
+        has_comments: metadata.as_ref().is_some_and(|m| m.has_comments),
         completed_at: Utc::now(),
         error_message: None,
     };
@@ -476,19 +1775,105 @@ async fn execute_download(job: Arc<JobRuntime>) -> Result<DownloadSummary, Downl
     Ok(summary)
 }
 
+/// Exit code as reported by a shell, mapping signal terminations to `128 + signal`.
+fn exit_code(status: &std::process::ExitStatus) -> Option<i32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return Some(128 + signal);
+        }
+    }
+    status.code()
+}
+
+/// Exit code of a process terminated by SIGKILL, which is what the Linux OOM killer sends.
+const SIGKILL_EXIT_CODE: i32 = 137;
+
+fn classify_process_result(exit_code: i32, stderr: &str, url: &str) -> DownloadError {
+    let out_of_memory = exit_code == SIGKILL_EXIT_CODE
+        || stderr.contains("MemoryError")
+        || stderr.lines().any(|line| line.trim() == "Killed");
+    if out_of_memory {
+        return DownloadError::OutOfMemory;
+    }
+
+    if let Some(url) = stderr
+        .lines()
+        .find_map(|line| line.split_once("Unsupported URL:"))
+        .map(|(_, url)| url.trim().to_string())
+    {
+        return DownloadError::UnsupportedSite(url);
+    }
+
+    if is_quota_exceeded(stderr) {
+        return DownloadError::Quota(url.to_string());
+    }
+
+    DownloadError::CommandFailed {
+        status: Some(exit_code),
+        stderr: stderr.to_string(),
+    }
+}
+
+/// YouTube's per-IP download limit, reported either as a quota error
+/// (often an `HTTP Error 403: Forbidden` explaining the quota) or as a bot
+/// check.
+fn is_quota_exceeded(stderr: &str) -> bool {
+    // Only quota errors from the site count; "Disk quota exceeded" does not.
+    let lower = stderr.to_lowercase();
+    let from_site = ["[youtube]", "http error 403", "http error 429"]
+        .iter()
+        .any(|marker| lower.contains(marker));
+    (from_site && lower.contains("quota"))
+        || stderr.contains("Sign in to confirm you're not a bot")
+        || stderr.contains("Sign in to confirm you\u{2019}re not a bot")
+}
+
 async fn finalize_history(
     job: &JobRuntime,
     status: JobStatus,
     file_path: Option<&Path>,
     error_message: Option<String>,
+    error: Option<&DownloadError>,
 ) {
-    let history = job.history.clone();
-    let job_id = job.id;
-    let path = file_path.map(|p| p.to_path_buf());
+    job.running_ids.write().remove(&job.id);
+    let counter = match status {
+        JobStatus::Succeeded => Some(&job.counters.succeeded),
+        JobStatus::Failed => Some(&job.counters.failed),
+        JobStatus::Canceled => Some(&job.counters.canceled),
+        _ => None,
+    };
+    if let Some(counter) = counter {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+    if status == JobStatus::Succeeded {
+        if let Some(size) = match file_path {
+            Some(path) => fs::metadata(path).await.ok().map(|metadata| metadata.len()),
+            None => None,
+        } {
+            job.counters
+                .bytes_downloaded
+                .fetch_add(size, Ordering::Relaxed);
+        }
+    }
+    if let Some(path) = job.progress_file.clone() {
+        tokio::task::spawn_blocking(move || std::fs::remove_file(path))
+            .await
+            .ok();
+    }
+    if let Some(tracked) = job.tracked_jobs.write().get_mut(&job.id) {
+        tracked.finished_at = Some(Instant::now());
+    }
+
     let error_code = match status {
         JobStatus::Succeeded => None,
         JobStatus::Canceled => Some("Canceled".to_string()),
-        JobStatus::Failed => Some("Failed".to_string()),
+        JobStatus::Failed => Some(
+            error
+                .map_or("Failed", DownloadError::error_code)
+                .to_string(),
+        ),
         _ => None,
     };
 
@@ -498,28 +1883,84 @@ async fn finalize_history(
     };
 
     if row_id.is_some() {
-        tokio::task::spawn_blocking(move || {
-            let _ = history.mark_completed(
-                job_id,
+        let _ = job
+            .history
+            .mark_completed(
+                job.id,
                 status,
-                path.as_deref(),
+                file_path,
                 error_code.as_deref(),
                 error_message.as_deref(),
-            );
-        })
-        .await
-        .ok();
+            )
+            .await;
     }
 }
 
 fn build_command(job: &JobRuntime) -> Command {
+    let mut command = yt_dlp_command(&job.advanced_settings);
+    command.args(build_args(
+        &job.request.lock(),
+        &job.download_settings,
+        &job.advanced_settings,
+    ));
+    command
+}
+
+/// Delete the files yt-dlp keeps while writing `destination`: `<name>.part`,
+/// `<name>.part-Frag*`, `<name>.ytdl` and `.tmp` files starting with the
+/// destination's stem. Returns how many were deleted.
+fn remove_partial_files(output_dir: &Path, destination: &Path) -> io::Result<usize> {
+    let dir = match destination.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => output_dir,
+    };
+    let (Some(name), Some(stem)) = (
+        destination.file_name().and_then(|name| name.to_str()),
+        destination.file_stem().and_then(|stem| stem.to_str()),
+    ) else {
+        return Ok(0);
+    };
+
+    let mut deleted = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let partial = file_name.strip_prefix(name).is_some_and(|rest| {
+            rest == ".part" || rest == ".ytdl" || rest.starts_with(".part-Frag")
+        }) || (file_name.starts_with(stem) && file_name.ends_with(".tmp"));
+        if partial {
+            std::fs::remove_file(&path)?;
+            deleted += 1;
+        }
+    }
+    Ok(deleted)
+}
+
+/// Wrap a failure to start `command`, singling out a binary that is not
+/// executable.
+fn spawn_error(command: &Command, source: io::Error) -> DownloadError {
+    if source.kind() == io::ErrorKind::PermissionDenied {
+        DownloadError::SpawnPermissionDenied {
+            path: PathBuf::from(command.as_std().get_program()),
+        }
+    } else {
+        DownloadError::Spawn { source }
+    }
+}
+
+/// A yt-dlp command without arguments, set up for the current platform.
+fn yt_dlp_command(advanced: &AdvancedSettings) -> Command {
     // Resolve yt-dlp binary path with priority:
     // 1. Absolute/relative path if specified
     // 2. Bundled with executable
     // 3. System PATH
-    let yt_dlp_path = resolve_binary(&job.advanced_settings.yt_dlp_path)
-        .unwrap_or_else(|| job.advanced_settings.yt_dlp_path.clone());
+    let yt_dlp_path =
+        resolve_binary(&advanced.yt_dlp_path).unwrap_or_else(|| advanced.yt_dlp_path.clone());
 
+    // Only changed further on macOS and Windows.
+    #[allow(unused_mut)]
     let mut command = Command::new(&yt_dlp_path);
 
     // Ensure yt-dlp can find ffmpeg by adding common Homebrew paths to PATH
@@ -542,32 +1983,222 @@ fn build_command(job: &JobRuntime) -> Command {
         #[allow(unused_imports)]
         use std::os::windows::process::CommandExt;
         const CREATE_NO_WINDOW: u32 = 0x08000000;
-        command.creation_flags(CREATE_NO_WINDOW);
+        if advanced.windows_no_console {
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
     }
 
-    command.arg("--extract-audio");
     command
-        .arg("--audio-format")
-        .arg(job.request.format.to_string());
-    command.arg("--audio-quality").arg("0");
-    command.arg("--write-info-json");
-    command.arg("--no-playlist");
-    command.arg("--progress");
-    command.arg("--newline");
+}
+
+fn build_args(
+    request: &DownloadRequest,
+    download: &DownloadSettings,
+    advanced: &AdvancedSettings,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = Vec::new();
+
+    // Must come first so the flags below take precedence over the user config.
+    if let Some(config_file) = &advanced.ytdlp_config_file {
+        if config_file.exists() {
+            if !request.extra_args.is_empty() {
+                warn!("both ytdlp_config_file and extra_args are set; conflicts may occur");
+            }
+            args.push("--config-location".into());
+            args.push(config_file.into());
+        } else {
+            warn!(
+                "yt-dlp config file {} does not exist; ignoring it",
+                config_file.display()
+            );
+        }
+    }
+
+    let audio_quality = match download.audio_bitrate_kbps {
+        Some(kbps) => format!("{kbps}k"),
+        None => "0".to_string(),
+    };
+
+    args.push("--extract-audio".into());
+    args.push("--audio-format".into());
+    args.push(request.format.to_string().into());
+    args.push("--audio-quality".into());
+    args.push(audio_quality.into());
+    if let DownloadMode::AudioVideo { container } = request.mode {
+        args.push("--merge-output-format".into());
+        args.push(container.to_string().into());
+        args.push("--keep-video".into());
+    }
+    if download.embed_chapters {
+        args.push("--embed-chapters".into());
+    }
+    if advanced.extract_chapters_as_tracks {
+        args.push("--split-chapters".into());
+    }
+    if let Some(categories) = &download.sponsorblock_mark {
+        if !categories.is_empty() {
+            args.push("--sponsorblock-mark".into());
+            args.push(sponsorblock::category_list(categories).into());
+        }
+    }
+    if let Some(categories) = &download.sponsorblock_remove {
+        if !categories.is_empty() {
+            args.push("--sponsorblock-remove".into());
+            args.push(sponsorblock::category_list(categories).into());
+        }
+    }
+    if download.write_comments {
+        args.push("--write-comments".into());
+    }
+    if download.prefer_free_formats {
+        args.push("--prefer-free-formats".into());
+    }
+    if let Some(age) = download.age_limit {
+        args.push("--age-limit".into());
+        args.push(age.to_string().into());
+    }
+    if let Some(sort) = &download.format_sort {
+        args.push("--format-sort".into());
+        args.push(sort.into());
+    }
+    match &download.geo_bypass {
+        GeoBypassMode::None => {}
+        GeoBypassMode::Auto => args.push("--geo-bypass".into()),
+        GeoBypassMode::ForceCountry(country) => {
+            args.push("--geo-bypass-country".into());
+            args.push(country.into());
+        }
+    }
+    args.push("--write-info-json".into());
+    args.push("--no-playlist".into());
+    // `--progress` keeps the progress lines coming even with `--quiet`.
+    args.push("--progress".into());
+    args.push("--newline".into());
+    if advanced.quiet {
+        args.push("--quiet".into());
+        args.push("--no-warnings".into());
+    }
+    if advanced.print_traffic {
+        args.push("--print-traffic".into());
+    }
+
+    let output_stem = match &request.output_filename {
+        // `%%` is yt-dlp's escape for a literal `%`.
+        Some(name) => name.replace('%', "%%"),
+        None => "%(title)s".to_string(),
+    };
+    let output_template = request.output_dir.join(format!("{output_stem}.%(ext)s"));
+    args.push("--output".into());
+    args.push(output_template.into());
+    if advanced.extract_chapters_as_tracks {
+        // Without it yt-dlp writes the tracks to the working directory; this
+        // is the naming `chapter_track_files` looks for.
+        let chapter_template = request.output_dir.join(format!(
+            "{output_stem} - %(section_number)03d %(section_title)s.%(ext)s"
+        ));
+        let mut chapter_output = OsString::from("chapter:");
+        chapter_output.push(chapter_template);
+        args.push("--output".into());
+        args.push(chapter_output);
+    }
+
+    if let Some(cookie) = &request.cookie_file {
+        args.push("--cookies".into());
+        args.push(cookie.into());
+    }
 
-    let output_template = job.request.output_dir.join("%(title)s.%(ext)s");
-    command.arg("--output").arg(&output_template);
+    if let Some(archive) = &request.archive_file {
+        if !request.force_redownload {
+            args.push("--download-archive".into());
+            args.push(archive.into());
+        }
+    }
 
-    if let Some(cookie) = &job.request.cookie_file {
-        command.arg("--cookies").arg(cookie);
+    if let Some(fields) = &request.metadata_fields {
+        let mut fields: Vec<_> = fields.iter().collect();
+        fields.sort();
+        for (key, value) in fields {
+            args.push("--parse-metadata".into());
+            args.push(format!("{}:%({key})s", metadata_literal(value)).into());
+        }
     }
 
-    for extra in &job.request.extra_args {
-        command.arg(extra);
+    if let Some(target) = &advanced.impersonate_browser {
+        args.push("--impersonate".into());
+        args.push(target.into());
     }
 
-    command.arg(&job.request.url);
-    command
+    if advanced.socket_timeout_sec > 0 {
+        args.push("--socket-timeout".into());
+        args.push(advanced.socket_timeout_sec.to_string().into());
+    }
+
+    if advanced.live_from_start {
+        args.push("--live-from-start".into());
+    }
+
+    if let Some((min, max)) = advanced.wait_for_video {
+        args.push("--wait-for-video".into());
+        args.push(format!("{min}-{max}").into());
+    }
+
+    if advanced.fragment_retries != DEFAULT_FRAGMENT_RETRIES {
+        args.push("--fragment-retries".into());
+        args.push(advanced.fragment_retries.to_string().into());
+    }
+
+    if let Some(args_file) = &advanced.yt_dlp_args_file {
+        args.extend(read_args_file(args_file).into_iter().map(OsString::from));
+    }
+
+    for extra in &request.extra_args {
+        args.push(extra.into());
+    }
+
+    // Last, so it overrides any overwrite flag in the args file or extra args.
+    if advanced.force_overwrites {
+        args.push("--force-overwrites".into());
+    }
+
+    args.push((&request.url).into());
+    args
+}
+
+/// Write `value` as a `--parse-metadata` source that yt-dlp reads as literal
+/// text. A bare word such as `Beatles` would otherwise be taken for the name
+/// of a field, so it becomes the default of a field that never exists.
+fn metadata_literal(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphabetic() || c == '_') {
+        return format!("%(__space_downloader_literal|{value})s");
+    }
+    value.replace('%', "%%").replace(':', "\\:")
+}
+
+/// Arguments listed in a [`AdvancedSettings::yt_dlp_args_file`]. A missing
+/// file or a line with unbalanced quotes is logged and skipped.
+fn read_args_file(path: &Path) -> Vec<String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) => {
+            warn!(
+                "cannot read yt-dlp args file {}: {error}; ignoring it",
+                path.display()
+            );
+            return Vec::new();
+        }
+    };
+
+    let mut args = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match shlex::split(line) {
+            Some(parts) => args.extend(parts),
+            None => warn!("skipping malformed line in {}: {line}", path.display()),
+        }
+    }
+    args
 }
 
 async fn terminate_child(child: &mut Child) -> Result<(), DownloadError> {
@@ -587,7 +2218,7 @@ async fn terminate_child(child: &mut Child) -> Result<(), DownloadError> {
     Ok(())
 }
 
-async fn handle_process_line(job: &JobRuntime, line: &str, destination: &mut Option<PathBuf>) {
+async fn handle_process_line(job: &JobRuntime, line: &str, state: &mut AttemptState) {
     debug!("yt-dlp: {line}");
     job.events_tx
         .send(DownloadEvent::LogLine(line.to_string()))
@@ -596,11 +2227,27 @@ async fn handle_process_line(job: &JobRuntime, line: &str, destination: &mut Opt
 
     if let Some(captures) = DESTINATION_RE.captures(line) {
         if let Some(path_match) = captures.name("path") {
-            *destination = Some(PathBuf::from(path_match.as_str()));
+            state.destination = Some(PathBuf::from(path_match.as_str()));
+            *job.destination.lock() = state.destination.clone();
         }
     }
 
+    if !state.rate_limited && is_rate_limit_line(line) {
+        state.rate_limited = true;
+        let cooldown = rate_limit_cooldown(&job.advanced_settings);
+        warn!(
+            "download job {} was rate limited; cooling down for {}s",
+            job.id,
+            cooldown.as_secs()
+        );
+        job.events_tx
+            .send(DownloadEvent::RateLimited(cooldown))
+            .await
+            .ok();
+    }
+
     if let Some(progress) = parse_progress(line) {
+        save_job_progress(job, &progress).await;
         job.progress_tx.send_replace(Some(progress.clone()));
         job.events_tx
             .send(DownloadEvent::Progress(progress))
@@ -609,7 +2256,120 @@ async fn handle_process_line(job: &JobRuntime, line: &str, destination: &mut Opt
     }
 }
 
+/// Write `progress` to the job's in-progress file, at most once every
+/// [`PROGRESS_SAVE_INTERVAL`].
+async fn save_job_progress(job: &JobRuntime, progress: &ProgressSnapshot) {
+    let Some(path) = job.progress_file.clone() else {
+        return;
+    };
+    {
+        let mut saved_at = job.progress_saved_at.lock();
+        if saved_at.is_some_and(|at| at.elapsed() < PROGRESS_SAVE_INTERVAL) {
+            return;
+        }
+        *saved_at = Some(Instant::now());
+    }
+
+    let record = JobProgress {
+        id: job.id,
+        url: job.request.lock().url.clone(),
+        percent: progress.percent.or_else(|| progress.synthetic_percent()),
+        downloaded_bytes: progress.downloaded_bytes,
+        eta: progress.eta,
+    };
+    let result = tokio::task::spawn_blocking(move || -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_vec(&record).map_err(io::Error::other)?;
+        std::fs::write(&path, json)
+    })
+    .await;
+    if let Ok(Err(error)) = result {
+        warn!(
+            "failed to save progress of download job {}: {error}",
+            job.id
+        );
+    }
+}
+
+const NETWORK_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Periodically emit [`DownloadEvent::NetworkProgress`] until the returned
+/// token is cancelled. Does nothing on platforms without a byte counter.
+fn spawn_network_monitor(job: Arc<JobRuntime>) -> CancellationToken {
+    let stop = CancellationToken::new();
+    let Some(baseline) = network_received_bytes() else {
+        debug!("network byte counters unavailable; native progress disabled");
+        return stop;
+    };
+
+    let stop_monitor = stop.clone();
+    tokio::spawn(async move {
+        let mut interval = time::interval(NETWORK_SAMPLE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = stop_monitor.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+            let Some(received) = network_received_bytes() else {
+                break;
+            };
+            let total_bytes = job
+                .progress_tx
+                .borrow()
+                .as_ref()
+                .and_then(|progress| progress.total_bytes);
+            job.events_tx
+                .send(DownloadEvent::NetworkProgress {
+                    received_bytes: received.saturating_sub(baseline),
+                    total_bytes,
+                })
+                .await
+                .ok();
+        }
+    });
+    stop
+}
+
+#[cfg(target_os = "linux")]
+fn network_received_bytes() -> Option<u64> {
+    let content = std::fs::read_to_string("/proc/self/net/dev").ok()?;
+    Some(parse_net_dev_received(&content))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn network_received_bytes() -> Option<u64> {
+    None
+}
+
+/// Sum the receive byte counters of every interface except loopback.
+#[cfg(target_os = "linux")]
+fn parse_net_dev_received(content: &str) -> u64 {
+    content
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(interface, _)| interface.trim() != "lo")
+        .filter_map(|(_, counters)| counters.split_whitespace().next()?.parse::<u64>().ok())
+        .sum()
+}
+
+fn is_rate_limit_line(line: &str) -> bool {
+    let lower = line.to_ascii_lowercase();
+    lower.contains("http error 429")
+        || lower.contains("too many requests")
+        || lower.contains("rate limit")
+}
+
 fn parse_progress(line: &str) -> Option<ProgressSnapshot> {
+    if let Some(captures) = FRAGMENT_RE.captures(line) {
+        let frag = captures["frag"].parse::<u32>().ok()?;
+        let total_frags = captures["total_frags"].parse::<u32>().ok()?;
+        return Some(ProgressSnapshot {
+            fragments: Some((frag, total_frags)),
+            ..ProgressSnapshot::default()
+        });
+    }
     let captures = PROGRESS_RE.captures(line)?;
     Some(ProgressSnapshot {
         percent: captures
@@ -628,6 +2388,7 @@ fn parse_progress(line: &str) -> Option<ProgressSnapshot> {
             .name("speed")
             .and_then(|m| parse_speed(m.as_str(), captures.name("speed_unit").map(|u| u.as_str()))),
         eta: captures.name("eta").and_then(|m| parse_eta(m.as_str())),
+        fragments: None,
     })
 }
 
@@ -665,25 +2426,118 @@ fn parse_eta(value: &str) -> Option<Duration> {
     Some(Duration::from_secs(seconds))
 }
 
+/// How many events a job's channel buffers; `--print-traffic` needs room
+/// for its extra output.
+fn event_channel_capacity(advanced: &AdvancedSettings) -> usize {
+    if advanced.print_traffic {
+        TRAFFIC_EVENT_CHANNEL_CAPACITY
+    } else {
+        EVENT_CHANNEL_CAPACITY
+    }
+}
+
+/// Return a sender whose events reach both `events_tx` and `sink`.
+fn forward_events(
+    job_id: Uuid,
+    events_tx: mpsc::Sender<DownloadEvent>,
+    sink: mpsc::Sender<(Uuid, DownloadEvent)>,
+    capacity: usize,
+) -> mpsc::Sender<DownloadEvent> {
+    let (job_tx, mut job_rx) = mpsc::channel::<DownloadEvent>(capacity);
+    tokio::spawn(async move {
+        while let Some(event) = job_rx.recv().await {
+            sink.send((job_id, event.clone())).await.ok();
+            events_tx.send(event).await.ok();
+        }
+    });
+    job_tx
+}
+
 fn error_message(error: &DownloadError) -> String {
     match error {
         DownloadError::InvalidUrl(url) => format!("invalid url: {url}"),
+        DownloadError::InvalidRequest { source } => format!("invalid download request: {source}"),
         DownloadError::MissingDependency(dep) => format!("missing dependency: {dep}"),
         DownloadError::Spawn { source } => format!("failed to spawn command: {source}"),
+        DownloadError::SpawnPermissionDenied { path } => format!(
+            "permission denied running {}. Check that the yt-dlp binary at {0} is executable. \
+             On Unix: `chmod +x {0}`",
+            path.display()
+        ),
         DownloadError::CommandFailed { status, stderr } => {
             format!("command failed (status {status:?}): {stderr}")
         }
+        DownloadError::RateLimited => "rate limited by the server".to_string(),
+        DownloadError::Quota(_) => "download quota exceeded".to_string(),
+        DownloadError::OutOfMemory => {
+            "Download killed by OS (low memory). Try downloading a smaller file or freeing RAM."
+                .to_string()
+        }
         DownloadError::Canceled => "download canceled".to_string(),
         DownloadError::Timeout(seconds) => format!("download timed out after {seconds} seconds"),
+        DownloadError::EmptyBatch => "none of the URLs in the batch are valid".to_string(),
+        DownloadError::JobFailed(message) => format!("download job failed: {message}"),
+        DownloadError::AlreadyQueued(url) => format!("{url} is already queued"),
+        DownloadError::DuplicateJobId(id) => format!("job {id} already exists"),
+        DownloadError::NotQueued(id) => format!("job {id} is not waiting for a download slot"),
+        DownloadError::JobAlreadyStarted(id) => format!("job {id} has already started"),
+        DownloadError::UnsupportedSite(url) => format!("unsupported URL: {url}"),
+        DownloadError::Http { source } => format!("http request failed: {source}"),
         DownloadError::Io { source } => format!("io error: {source}"),
         DownloadError::Join { source } => format!("task join error: {source}"),
     }
 }
 
+/// Maximum number of characters kept from the info JSON `description`.
+const DESCRIPTION_MAX_CHARS: usize = 500;
+
 struct DownloadMetadata {
     title: Option<String>,
     uploader: Option<String>,
+    description: Option<String>,
+    view_count: Option<u64>,
+    like_count: Option<u64>,
+    has_comments: bool,
+    source_url: Option<String>,
+    sponsorblock_chapters: Vec<SponsorBlockChapter>,
     file_path: Option<PathBuf>,
+    info_path: PathBuf,
+}
+
+/// An entry of the info JSON's `sponsorblock_chapters`, in seconds.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+struct SponsorBlockChapter {
+    start_time: f64,
+    end_time: f64,
+    category: Option<String>,
+}
+
+/// The info JSON fields we use. Everything else, notably the potentially huge
+/// `comments` list, is skipped while parsing instead of being kept in memory.
+#[derive(serde::Deserialize)]
+struct InfoJson {
+    title: Option<String>,
+    uploader: Option<String>,
+    description: Option<String>,
+    view_count: Option<u64>,
+    like_count: Option<u64>,
+    webpage_url: Option<String>,
+    #[serde(default)]
+    sponsorblock_chapters: Vec<SponsorBlockChapter>,
+    ext: Option<String>,
+    comments: Option<serde::de::IgnoredAny>,
+}
+
+/// Read the newest info JSON in `output_dir`, deleting it afterwards unless
+/// `keep_info_json` is set. A failed deletion is only logged.
+fn take_latest_metadata(output_dir: &Path, keep_info_json: bool) -> Option<DownloadMetadata> {
+    let metadata = read_latest_metadata(output_dir)?;
+    if !keep_info_json {
+        if let Err(error) = std::fs::remove_file(&metadata.info_path) {
+            warn!("failed to remove {}: {error}", metadata.info_path.display());
+        }
+    }
+    Some(metadata)
 }
 
 fn read_latest_metadata(output_dir: &Path) -> Option<DownloadMetadata> {
@@ -707,27 +2561,21 @@ fn read_latest_metadata(output_dir: &Path) -> Option<DownloadMetadata> {
     }
 
     let (_, info_path) = newest?;
-    let info_content = std::fs::read_to_string(&info_path).ok()?;
-    let value: serde_json::Value = serde_json::from_str(&info_content).ok()?;
-
-    let title = value
-        .get("title")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-    let uploader = value
-        .get("uploader")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-    let ext = value
-        .get("ext")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+    let info_file = std::fs::File::open(&info_path).ok()?;
+    let info: InfoJson = serde_json::from_reader(io::BufReader::new(info_file)).ok()?;
+
+    let description = info
+        .description
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.chars().take(DESCRIPTION_MAX_CHARS).collect());
     let base_name = info_path
         .file_name()
         .and_then(|name| name.to_str())
         .and_then(|name| name.strip_suffix(".info.json"))?;
 
-    let ext = ext.unwrap_or_else(|| "m4a".to_string());
+    let ext = info.ext.unwrap_or_else(|| "m4a".to_string());
     let candidate = info_path
         .parent()
         .unwrap_or(output_dir)
@@ -739,22 +2587,2427 @@ fn read_latest_metadata(output_dir: &Path) -> Option<DownloadMetadata> {
     };
 
     Some(DownloadMetadata {
-        title,
-        uploader,
+        title: info.title,
+        uploader: info.uploader,
+        description,
+        view_count: info.view_count,
+        like_count: info.like_count,
+        has_comments: info.comments.is_some(),
+        source_url: info.webpage_url,
+        sponsorblock_chapters: info.sponsorblock_chapters,
         file_path,
+        info_path,
     })
 }
 
+/// The audio file yt-dlp extracts next to a kept video, if it exists.
+fn extracted_audio_path(video: &Path, format: AudioFormat) -> Option<PathBuf> {
+    let audio = video.with_extension(format.extension());
+    (audio != video && audio.exists()).then_some(audio)
+}
+
+/// Files yt-dlp's `--split-chapters` wrote next to `file_path`, which
+/// [`build_args`]' `chapter:` output template names
+/// `<stem> - <chapter number> <chapter title>`.
+fn chapter_track_files(file_path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(stem)) = (
+        file_path.parent(),
+        file_path.file_stem().and_then(|stem| stem.to_str()),
+    ) else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let prefix = format!("{stem} - ");
+    let mut tracks: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.as_path() != file_path && !is_info_json(path))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix))
+        })
+        .collect();
+    tracks.sort();
+    tracks
+}
+
 fn is_info_json(path: &Path) -> bool {
     match path.file_name().and_then(|name| name.to_str()) {
         Some(name) => name.ends_with(".info.json"),
         None => false,
     }
 }
-fn download_error_from_history(error: HistoryError) -> DownloadError {
-    DownloadError::Io {
+
+/// Strip tracking parameters from YouTube URLs; other URLs are returned as is.
+fn normalize_url(url: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    let is_youtube = parsed.host_str().is_some_and(|host| {
+        matches!(
+            host,
+            "youtube.com" | "www.youtube.com" | "m.youtube.com" | "music.youtube.com" | "youtu.be"
+        )
+    });
+    if !is_youtube || parsed.query().is_none() {
+        return url.to_string();
+    }
+
+    let kept: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| {
+            !YOUTUBE_TRACKING_PARAMS.contains(&key.as_ref()) && !key.starts_with("utm_")
+        })
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    parsed.to_string()
+}
+
+fn download_error_from_history(error: HistoryError) -> DownloadError {
+    DownloadError::Io {
         source: io::Error::other(error.to_string()),
     }
 }
 unsafe impl Send for JobRuntime {}
 unsafe impl Sync for JobRuntime {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockYtDlp;
+
+    fn arg_after<'a>(args: &'a [OsString], flag: &str) -> Option<&'a OsString> {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+    }
+
+    fn sample_request() -> DownloadRequest {
+        DownloadRequest::new(
+            "https://x.com/i/spaces/1".to_string(),
+            PathBuf::from("out"),
+            AudioFormat::Mp3,
+        )
+    }
+
+    #[test]
+    fn download_request_round_trips_through_json() {
+        let mut request = sample_request();
+        request.format = AudioFormat::Opus;
+        request.extra_args = vec!["--no-mtime".to_string()];
+        request.cookie_file = Some(PathBuf::from("cookies.txt"));
+        request.metadata_fields = Some(HashMap::from([(
+            "artist".to_string(),
+            "%(uploader)s".to_string(),
+        )]));
+
+        let json = request.to_json().unwrap();
+        assert_eq!(DownloadRequest::from_json(&json).unwrap(), request);
+    }
+
+    #[test]
+    fn output_filename_replaces_title_template() {
+        let args = build_args(
+            &sample_request(),
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert_eq!(
+            arg_after(&args, "--output"),
+            Some(&OsString::from(
+                Path::new("out").join("%(title)s.%(ext)s").as_os_str()
+            ))
+        );
+
+        let mut request = sample_request();
+        request.output_filename = Some("My Song 100%".to_string());
+        request.validate().unwrap();
+        let args = build_args(
+            &request,
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert_eq!(
+            arg_after(&args, "--output"),
+            Some(&OsString::from(
+                Path::new("out").join("My Song 100%%.%(ext)s").as_os_str()
+            ))
+        );
+        assert_eq!(
+            arg_after(&args, "--audio-format"),
+            Some(&OsString::from("mp3"))
+        );
+    }
+
+    #[test]
+    fn output_filename_is_validated() {
+        let mut request = sample_request();
+        for name in ["", "../My Song", "a\\b", "nul\0byte", &"x".repeat(255)] {
+            request.output_filename = Some(name.to_string());
+            assert!(
+                matches!(
+                    request.validate(),
+                    Err(ConfigError::Invalid {
+                        field: "output_filename",
+                        ..
+                    })
+                ),
+                "{name:?}"
+            );
+        }
+        request.output_filename = Some("x".repeat(254));
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn audio_quality_defaults_to_best() {
+        let args = build_args(
+            &sample_request(),
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert_eq!(
+            arg_after(&args, "--audio-quality"),
+            Some(&OsString::from("0"))
+        );
+    }
+
+    #[test]
+    fn embed_chapters_adds_flag() {
+        let advanced = AdvancedSettings::default();
+        let mut download = DownloadSettings::default();
+        let args = build_args(&sample_request(), &download, &advanced);
+        assert!(!args.iter().any(|arg| arg == "--embed-chapters"));
+
+        download.embed_chapters = true;
+        let args = build_args(&sample_request(), &download, &advanced);
+        assert!(args.iter().any(|arg| arg == "--embed-chapters"));
+    }
+
+    fn assert_event_round_trips(event: DownloadEvent) {
+        let json = serde_json::to_string(&event).unwrap();
+        let parsed: DownloadEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, event, "{json}");
+    }
+
+    #[test]
+    fn download_events_round_trip_through_json() {
+        assert_event_round_trips(DownloadEvent::Status(JobStatus::Running));
+        assert_event_round_trips(DownloadEvent::Progress(ProgressSnapshot {
+            percent: Some(42.5),
+            downloaded_bytes: Some(1024),
+            total_bytes: Some(4096),
+            speed_bytes_per_sec: Some(512),
+            eta: Some(Duration::from_secs(90)),
+            fragments: Some((5, 20)),
+        }));
+        assert_event_round_trips(DownloadEvent::Progress(ProgressSnapshot::default()));
+        assert_event_round_trips(DownloadEvent::LogLine("[download] 1.0%".to_string()));
+        assert_event_round_trips(DownloadEvent::RateLimited(Duration::from_secs(60)));
+        assert_event_round_trips(DownloadEvent::NetworkProgress {
+            received_bytes: 2048,
+            total_bytes: None,
+        });
+        assert_event_round_trips(DownloadEvent::Completed(DownloadSummary {
+            id: Uuid::new_v4(),
+            url: "https://x.com/i/spaces/1".to_string(),
+            source_url: Some("https://x.com/i/spaces/1AbC".to_string()),
+            status: JobStatus::Succeeded,
+            title: Some("Space".to_string()),
+            uploader: Some("host".to_string()),
+            description: None,
+            file_path: Some(PathBuf::from("out/Space.m4a")),
+            audio_path: None,
+            extra_files: vec![PathBuf::from("out/Space - 001 Intro.m4a")],
+            view_count: Some(1_000),
+            like_count: None,
+            has_comments: true,
+            completed_at: Utc::now(),
+            error_message: None,
+        }));
+        assert_event_round_trips(DownloadEvent::SponsorBlockMark {
+            start_secs: 65,
+            end_secs: 131,
+            category: "sponsor".to_string(),
+        });
+        assert_event_round_trips(DownloadEvent::Failed {
+            message: "download canceled".to_string(),
+            help: None,
+        });
+        assert_event_round_trips(DownloadEvent::Failed {
+            message: "rate limited by the server".to_string(),
+            help: Some(ErrorHelp::WaitForRateLimit),
+        });
+    }
+
+    #[test]
+    fn parses_fragment_progress() {
+        let progress = parse_progress("[download] fragment 5 of 20").unwrap();
+        assert_eq!(progress.fragments, Some((5, 20)));
+        assert_eq!(progress.percent, None);
+        assert_eq!(progress.downloaded_bytes, None);
+        assert_eq!(progress.synthetic_percent(), Some(25.0));
+
+        let empty = ProgressSnapshot {
+            fragments: Some((0, 0)),
+            ..ProgressSnapshot::default()
+        };
+        assert_eq!(empty.synthetic_percent(), None);
+    }
+
+    #[test]
+    fn progress_byte_helpers_handle_boundaries() {
+        let sized = |downloaded, total| ProgressSnapshot {
+            downloaded_bytes: Some(downloaded),
+            total_bytes: Some(total),
+            ..ProgressSnapshot::default()
+        };
+
+        assert_eq!(sized(250, 1000).bytes_remaining(), Some(750));
+        assert_eq!(sized(250, 1000).transfer_ratio(), Some(0.25));
+        assert_eq!(sized(1000, 1000).bytes_remaining(), Some(0));
+        assert_eq!(sized(1000, 1000).transfer_ratio(), Some(1.0));
+        assert_eq!(sized(0, 0).bytes_remaining(), Some(0));
+        assert_eq!(sized(0, 0).transfer_ratio(), None);
+        // yt-dlp's total is an estimate and can be overtaken.
+        assert_eq!(sized(1200, 1000).bytes_remaining(), None);
+        assert_eq!(sized(1200, 1000).transfer_ratio(), Some(1.0));
+
+        let unknown = ProgressSnapshot::default();
+        assert_eq!(unknown.bytes_remaining(), None);
+        assert_eq!(unknown.transfer_ratio(), None);
+        assert!(!unknown.is_complete());
+
+        let percent = |percent| ProgressSnapshot {
+            percent: Some(percent),
+            ..ProgressSnapshot::default()
+        };
+        assert!(!percent(99.9).is_complete());
+        assert!(percent(100.0).is_complete());
+    }
+
+    #[test]
+    fn progress_eta_serializes_as_seconds() {
+        let progress = ProgressSnapshot {
+            eta: Some(Duration::from_secs(75)),
+            ..ProgressSnapshot::default()
+        };
+        let value = serde_json::to_value(&progress).unwrap();
+        assert_eq!(value["eta"], 75);
+    }
+
+    #[test]
+    fn force_redownload_skips_download_archive() {
+        let download = DownloadSettings::default();
+        let advanced = AdvancedSettings::default();
+        let mut request = sample_request();
+        request.archive_file = Some(PathBuf::from("archive.txt"));
+        let args = build_args(&request, &download, &advanced);
+        assert_eq!(
+            arg_after(&args, "--download-archive"),
+            Some(&OsString::from("archive.txt"))
+        );
+
+        request.force_redownload = true;
+        let args = build_args(&request, &download, &advanced);
+        assert!(!args.iter().any(|arg| arg == "--download-archive"));
+    }
+
+    #[test]
+    fn extract_chapters_as_tracks_adds_flag() {
+        let download = DownloadSettings::default();
+        let mut advanced = AdvancedSettings::default();
+        let args = build_args(&sample_request(), &download, &advanced);
+        assert!(!args.iter().any(|arg| arg == "--split-chapters"));
+
+        advanced.extract_chapters_as_tracks = true;
+        let args = build_args(&sample_request(), &download, &advanced);
+        assert!(args.iter().any(|arg| arg == "--split-chapters"));
+        let chapter_output =
+            PathBuf::from("out").join("%(title)s - %(section_number)03d %(section_title)s.%(ext)s");
+        let mut expected = OsString::from("chapter:");
+        expected.push(&chapter_output);
+        assert!(args.contains(&expected));
+
+        let mut request = sample_request();
+        request.output_filename = Some("100% Live".to_string());
+        let args = build_args(&request, &download, &advanced);
+        let chapter_output = PathBuf::from("out")
+            .join("100%% Live - %(section_number)03d %(section_title)s.%(ext)s");
+        let mut expected = OsString::from("chapter:");
+        expected.push(&chapter_output);
+        assert!(args.contains(&expected));
+    }
+
+    #[test]
+    fn chapter_track_files_finds_tracks_named_by_the_chapter_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut request = sample_request();
+        request.output_dir = dir.path().to_path_buf();
+        let advanced = AdvancedSettings {
+            extract_chapters_as_tracks: true,
+            ..AdvancedSettings::default()
+        };
+        let args = build_args(&request, &DownloadSettings::default(), &advanced);
+        let template = args
+            .iter()
+            .find_map(|arg| arg.to_str()?.strip_prefix("chapter:"))
+            .unwrap()
+            .replace("%(title)s", "My Space")
+            .replace("%(ext)s", "mp3");
+        for (number, title) in [("002", "Q&A"), ("001", "Intro")] {
+            let track = template
+                .replace("%(section_number)03d", number)
+                .replace("%(section_title)s", title);
+            std::fs::write(track, b"").unwrap();
+        }
+        let main_file = dir.path().join("My Space.mp3");
+        std::fs::write(&main_file, b"").unwrap();
+
+        assert_eq!(
+            chapter_track_files(&main_file),
+            vec![
+                dir.path().join("My Space - 001 Intro.mp3"),
+                dir.path().join("My Space - 002 Q&A.mp3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn chapter_track_files_lists_split_tracks_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "Mix.m4a",
+            "Mix.info.json",
+            "Mix - 002 Second.m4a",
+            "Mix - 001 First.m4a",
+            "Other - 001 Intro.m4a",
+        ] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+
+        let tracks = chapter_track_files(&dir.path().join("Mix.m4a"));
+        assert_eq!(
+            tracks,
+            vec![
+                dir.path().join("Mix - 001 First.m4a"),
+                dir.path().join("Mix - 002 Second.m4a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn audio_video_mode_keeps_merged_video() {
+        let advanced = AdvancedSettings::default();
+        let download = DownloadSettings::default();
+        let args = build_args(&sample_request(), &download, &advanced);
+        assert!(!args.iter().any(|arg| arg == "--keep-video"));
+
+        let mut request = sample_request();
+        request.mode = DownloadMode::AudioVideo {
+            container: VideoContainer::Mkv,
+        };
+        let args = build_args(&request, &download, &advanced);
+        assert_eq!(
+            arg_after(&args, "--merge-output-format"),
+            Some(&OsString::from("mkv"))
+        );
+        assert_eq!(
+            arg_after(&args, "--audio-format"),
+            Some(&OsString::from("mp3"))
+        );
+        assert!(args.iter().any(|arg| arg == "--keep-video"));
+    }
+
+    #[test]
+    fn extracted_audio_path_requires_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let video = dir.path().join("space.mp4");
+        std::fs::write(&video, b"").unwrap();
+        assert_eq!(extracted_audio_path(&video, AudioFormat::M4a), None);
+
+        let audio = dir.path().join("space.m4a");
+        std::fs::write(&audio, b"").unwrap();
+        assert_eq!(extracted_audio_path(&video, AudioFormat::M4a), Some(audio));
+
+        let vorbis = dir.path().join("space.ogg");
+        std::fs::write(&vorbis, b"").unwrap();
+        assert_eq!(
+            extracted_audio_path(&video, AudioFormat::Vorbis),
+            Some(vorbis)
+        );
+    }
+
+    #[test]
+    fn sponsorblock_remove_joins_categories() {
+        use crate::sponsorblock::SponsorBlockCategory;
+
+        let download = DownloadSettings {
+            sponsorblock_remove: Some(vec![
+                SponsorBlockCategory::Sponsor,
+                SponsorBlockCategory::MusicOfftopic,
+            ]),
+            ..DownloadSettings::default()
+        };
+        let args = build_args(&sample_request(), &download, &AdvancedSettings::default());
+        assert_eq!(
+            arg_after(&args, "--sponsorblock-remove"),
+            Some(&OsString::from("sponsor,music_offtopic"))
+        );
+        assert_eq!(arg_after(&args, "--sponsorblock-mark"), None);
+    }
+
+    #[test]
+    fn sponsorblock_mark_joins_categories() {
+        use crate::sponsorblock::SponsorBlockCategory;
+
+        let download = DownloadSettings {
+            sponsorblock_mark: Some(vec![
+                SponsorBlockCategory::Intro,
+                SponsorBlockCategory::Outro,
+            ]),
+            ..DownloadSettings::default()
+        };
+        let args = build_args(&sample_request(), &download, &AdvancedSettings::default());
+        assert_eq!(
+            arg_after(&args, "--sponsorblock-mark"),
+            Some(&OsString::from("intro,outro"))
+        );
+    }
+
+    #[test]
+    fn args_file_comes_before_extra_args() {
+        let dir = tempfile::tempdir().unwrap();
+        let args_file = dir.path().join("yt-dlp.args");
+        std::fs::write(
+            &args_file,
+            "# network\n--limit-rate 2M\n\n--add-header \"Referer: https://x.com\"\n",
+        )
+        .unwrap();
+        let advanced = AdvancedSettings {
+            yt_dlp_args_file: Some(args_file),
+            ..AdvancedSettings::default()
+        };
+        let mut request = sample_request();
+        request.extra_args = vec!["--no-mtime".to_string()];
+        let args = build_args(&request, &DownloadSettings::default(), &advanced);
+
+        assert_eq!(
+            arg_after(&args, "--limit-rate"),
+            Some(&OsString::from("2M"))
+        );
+        assert_eq!(
+            arg_after(&args, "--add-header"),
+            Some(&OsString::from("Referer: https://x.com"))
+        );
+        let position = |flag: &str| args.iter().position(|arg| arg == flag).unwrap();
+        assert!(position("--add-header") < position("--no-mtime"));
+    }
+
+    #[test]
+    fn missing_args_file_is_ignored() {
+        let advanced = AdvancedSettings {
+            yt_dlp_args_file: Some(PathBuf::from("does-not-exist.args")),
+            ..AdvancedSettings::default()
+        };
+        let with_file = build_args(&sample_request(), &DownloadSettings::default(), &advanced);
+        let without = build_args(
+            &sample_request(),
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert_eq!(with_file, without);
+    }
+
+    #[test]
+    fn geo_bypass_modes_map_to_flags() {
+        let args_for = |geo_bypass| {
+            let download = DownloadSettings {
+                geo_bypass,
+                ..DownloadSettings::default()
+            };
+            build_args(&sample_request(), &download, &AdvancedSettings::default())
+        };
+
+        let args = args_for(GeoBypassMode::None);
+        assert!(!args
+            .iter()
+            .any(|arg| arg.to_string_lossy().starts_with("--geo-bypass")));
+
+        let args = args_for(GeoBypassMode::Auto);
+        assert!(args.iter().any(|arg| arg == "--geo-bypass"));
+        assert_eq!(arg_after(&args, "--geo-bypass-country"), None);
+
+        let args = args_for(GeoBypassMode::ForceCountry("US".to_string()));
+        assert!(!args.iter().any(|arg| arg == "--geo-bypass"));
+        assert_eq!(
+            arg_after(&args, "--geo-bypass-country"),
+            Some(&OsString::from("US"))
+        );
+    }
+
+    #[test]
+    fn format_sort_is_passed_as_separate_args() {
+        let download = DownloadSettings {
+            format_sort: Some("ext:m4a:m4a".to_string()),
+            ..DownloadSettings::default()
+        };
+        let args = build_args(&sample_request(), &download, &AdvancedSettings::default());
+        assert_eq!(
+            arg_after(&args, "--format-sort"),
+            Some(&OsString::from("ext:m4a:m4a"))
+        );
+
+        let args = build_args(
+            &sample_request(),
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert!(!args.iter().any(|arg| arg == "--format-sort"));
+    }
+
+    #[test]
+    fn audio_bitrate_replaces_quality_arg() {
+        let settings = DownloadSettings {
+            audio_bitrate_kbps: Some(192),
+            ..DownloadSettings::default()
+        };
+        let args = build_args(&sample_request(), &settings, &AdvancedSettings::default());
+        assert_eq!(
+            arg_after(&args, "--audio-quality"),
+            Some(&OsString::from("192k"))
+        );
+        assert_eq!(args.iter().filter(|a| *a == "--audio-quality").count(), 1);
+    }
+
+    #[test]
+    fn metadata_fields_become_parse_metadata_args() {
+        let mut request = sample_request();
+        request.metadata_fields = Some(HashMap::from([(
+            "artist".to_string(),
+            "My Channel".to_string(),
+        )]));
+        assert!(request.validate().is_ok());
+
+        let args = build_args(
+            &request,
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert_eq!(
+            arg_after(&args, "--parse-metadata"),
+            Some(&OsString::from("My Channel:%(artist)s"))
+        );
+    }
+
+    #[test]
+    fn impersonate_browser_adds_flag() {
+        let args = build_args(
+            &sample_request(),
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert!(!args.iter().any(|arg| arg == "--impersonate"));
+
+        let advanced = AdvancedSettings {
+            impersonate_browser: Some("chrome".to_string()),
+            ..AdvancedSettings::default()
+        };
+        let args = build_args(&sample_request(), &DownloadSettings::default(), &advanced);
+        assert_eq!(
+            arg_after(&args, "--impersonate"),
+            Some(&OsString::from("chrome"))
+        );
+    }
+
+    #[test]
+    fn single_word_metadata_is_not_read_as_a_field() {
+        let mut request = sample_request();
+        request.metadata_fields = Some(HashMap::from([
+            ("artist".to_string(), "Beatles".to_string()),
+            ("album".to_string(), "100% Live: Tokyo".to_string()),
+        ]));
+        let args = build_args(
+            &request,
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        let parsed: Vec<_> = args
+            .windows(2)
+            .filter(|pair| pair[0] == "--parse-metadata")
+            .map(|pair| pair[1].clone())
+            .collect();
+        assert_eq!(
+            parsed,
+            [
+                OsString::from("100%% Live\\: Tokyo:%(album)s"),
+                OsString::from("%(__space_downloader_literal|Beatles)s:%(artist)s"),
+            ]
+        );
+    }
+
+    #[test]
+    fn config_location_is_passed_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_file = dir.path().join("yt-dlp.conf");
+        std::fs::write(&config_file, "--no-mtime\n").unwrap();
+        let advanced = AdvancedSettings {
+            ytdlp_config_file: Some(config_file.clone()),
+            ..AdvancedSettings::default()
+        };
+
+        let args = build_args(&sample_request(), &DownloadSettings::default(), &advanced);
+        assert_eq!(args[0], "--config-location");
+        assert_eq!(args[1], config_file.as_os_str());
+        assert_eq!(args.last().unwrap(), "https://x.com/i/spaces/1");
+    }
+
+    #[test]
+    fn missing_config_file_is_ignored() {
+        let advanced = AdvancedSettings {
+            ytdlp_config_file: Some(PathBuf::from("/nonexistent/yt-dlp.conf")),
+            ..AdvancedSettings::default()
+        };
+
+        let args = build_args(&sample_request(), &DownloadSettings::default(), &advanced);
+        assert!(!args.iter().any(|arg| arg == "--config-location"));
+    }
+
+    #[test]
+    fn unknown_metadata_field_is_rejected() {
+        let mut request = sample_request();
+        request.metadata_fields = Some(HashMap::from([(
+            "uploader_id".to_string(),
+            "someone".to_string(),
+        )]));
+        assert!(matches!(
+            request.validate(),
+            Err(ConfigError::Invalid { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn metrics_count_job_transitions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        let service = DownloaderService::new_without_history(config);
+        assert_eq!(service.metrics(), DownloaderMetrics::default());
+
+        let semaphore = service.inner.semaphore.clone();
+        let slot = semaphore.clone().acquire_owned().await.unwrap();
+        let handle = service.queue(sample_request()).await.unwrap();
+        let metrics = service.metrics();
+        assert_eq!(metrics.total_queued, 1);
+        assert_eq!(metrics.current_queued, 1);
+        assert_eq!(metrics.current_running, 0);
+
+        handle.cancel();
+        let summary = handle.wait().await;
+        assert_eq!(summary.status, JobStatus::Canceled);
+        drop(slot);
+        let metrics = service.metrics();
+        assert_eq!(metrics.total_canceled, 1);
+        assert_eq!(metrics.total_succeeded + metrics.total_failed, 0);
+        assert_eq!(metrics.current_queued, 0);
+    }
+
+    #[test]
+    fn total_bytes_in_queue_sums_unfinished_jobs() {
+        let service = DownloaderService::new_without_history(Config::default());
+        let with_total = |total_bytes| {
+            Some(ProgressSnapshot {
+                total_bytes,
+                ..ProgressSnapshot::default()
+            })
+        };
+        let mut senders = Vec::new();
+        for (status, progress) in [
+            (JobStatus::Queued, with_total(Some(100))),
+            (JobStatus::Running, with_total(Some(250))),
+            (JobStatus::Running, with_total(None)),
+            (JobStatus::Queued, None),
+            (JobStatus::Succeeded, with_total(Some(1_000))),
+        ] {
+            let (status_tx, status_rx) = watch::channel(status);
+            let (progress_tx, progress_rx) = watch::channel(progress);
+            service.inner.tracked_jobs.write().insert(
+                Uuid::new_v4(),
+                TrackedJob {
+                    url: "https://x.com/i/spaces/1".to_string(),
+                    status_rx,
+                    progress_rx,
+                    finished_at: None,
+                    output_dir: PathBuf::from("out"),
+                    cancel_token: CancellationToken::new(),
+                    destination: Arc::default(),
+                    boost_tx: None,
+                    request: Arc::new(ParkingMutex::new(sample_request())),
+                },
+            );
+            senders.push((status_tx, progress_tx));
+        }
+
+        assert_eq!(service.total_bytes_in_queue(), 350);
+        assert_eq!(service.metrics().total_bytes_in_queue, 350);
+        assert_eq!(service.bytes_remaining_in_queue(), 350);
+
+        senders[1].1.send_replace(Some(ProgressSnapshot {
+            total_bytes: Some(250),
+            downloaded_bytes: Some(200),
+            ..ProgressSnapshot::default()
+        }));
+        assert_eq!(service.total_bytes_in_queue(), 350);
+        assert_eq!(service.bytes_remaining_in_queue(), 150);
+
+        senders[1].0.send_replace(JobStatus::Succeeded);
+        assert_eq!(service.total_bytes_in_queue(), 100);
+        assert_eq!(service.bytes_remaining_in_queue(), 100);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn priority_boost_jumps_the_queue() {
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("Space.m4a");
+        let yt_dlp = MockYtDlp::builder()
+            .stderr(format!("[download] Destination: {}", destination.display()))
+            .sleep(Duration::from_millis(200))
+            .create_file(&destination)
+            .build()
+            .unwrap();
+
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        config.download.concurrency = 1;
+        let service = DownloaderService::new_without_history(config);
+
+        let mut handles = Vec::new();
+        for _ in 0..5 {
+            handles.push(service.queue(sample_request()).await.unwrap());
+        }
+        let mut first_status = handles[0].status_receiver();
+        first_status
+            .wait_for(|status| *status == JobStatus::Running)
+            .await
+            .unwrap();
+
+        let boosted = handles[3].id;
+        service.queue_with_priority_boost(boosted).unwrap();
+        assert!(matches!(
+            service.queue_with_priority_boost(boosted),
+            Err(DownloadError::NotQueued(_))
+        ));
+        assert!(matches!(
+            service.queue_with_priority_boost(handles[0].id),
+            Err(DownloadError::NotQueued(_))
+        ));
+
+        let finished = Arc::new(ParkingMutex::new(Vec::new()));
+        let waits: Vec<_> = handles
+            .into_iter()
+            .map(|handle| {
+                let finished = finished.clone();
+                tokio::spawn(async move {
+                    let summary = handle.wait().await;
+                    assert_eq!(summary.status, JobStatus::Succeeded);
+                    finished.lock().push(summary.id);
+                })
+            })
+            .collect();
+        for wait in waits {
+            wait.await.unwrap();
+        }
+
+        let finished = finished.lock();
+        let position = |id| {
+            finished
+                .iter()
+                .position(|finished| *finished == id)
+                .unwrap()
+        };
+        assert_eq!(finished.len(), 5);
+        assert_eq!(position(boosted), 1);
+    }
+
+    #[tokio::test]
+    async fn running_job_ids_track_unfinished_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        let service = DownloaderService::new_without_history(config);
+
+        // Hold the only download slot so both jobs stay queued.
+        let semaphore = service.inner.semaphore.clone();
+        let _slot = semaphore.acquire_owned().await.unwrap();
+
+        let url = "https://x.com/i/spaces/1".to_string();
+        let first = service
+            .queue(DownloadRequest::new(
+                url.clone(),
+                PathBuf::new(),
+                AudioFormat::M4a,
+            ))
+            .await
+            .unwrap();
+        let second = service
+            .queue(DownloadRequest::new(url, PathBuf::new(), AudioFormat::M4a))
+            .await
+            .unwrap();
+
+        let ids = service.running_job_ids();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&first.id) && ids.contains(&second.id));
+
+        first.cancel();
+        for _ in 0..200 {
+            if service.running_job_ids().len() == 1 {
+                break;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(service.running_job_ids(), vec![second.id]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn download_reports_progress_and_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("Space.mp3");
+        let yt_dlp = MockYtDlp::progress_then_succeed(
+            &["[download]  50.0% of 2.00MiB at 1.00MiB/s ETA 00:01"],
+            &destination,
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        let service = DownloaderService::new_without_history(config);
+        let mut request = sample_request();
+        request.output_dir = dir.path().to_path_buf();
+        let handle = service.queue(request).await.unwrap();
+
+        let mut events = handle.take_events().unwrap();
+        let mut percents = Vec::new();
+        let mut summary = None;
+        while let Some(event) = events.recv().await {
+            match event {
+                DownloadEvent::Progress(progress) => percents.extend(progress.percent),
+                DownloadEvent::Completed(completed) => summary = Some(completed),
+                _ => {}
+            }
+        }
+        assert_eq!(percents, [50.0]);
+        let summary = summary.expect("download completed");
+        assert_eq!(summary.status, JobStatus::Succeeded);
+        assert_eq!(summary.file_path, Some(destination));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn queued_request_can_be_updated_before_it_starts() {
+        let dir = tempfile::tempdir().unwrap();
+        let moved = dir.path().join("moved");
+        let destination = moved.join("Space.opus");
+        let yt_dlp = MockYtDlp::builder()
+            .print_args()
+            .stderr(format!("[download] Destination: {}", destination.display()))
+            .create_file(&destination)
+            .build()
+            .unwrap();
+
+        let mut config = Config::default();
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        let service = DownloaderService::new_without_history(config);
+        let semaphore = service.inner.semaphore.clone();
+        let slot = semaphore.acquire_owned().await.unwrap();
+
+        let mut request = sample_request();
+        request.output_dir = dir.path().to_path_buf();
+        let handle = service.queue(request).await.unwrap();
+        service
+            .update_request_in_queue(handle.id, |request| {
+                request.format = AudioFormat::Opus;
+                request.output_dir = moved.clone();
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            service
+                .update_request_in_queue(handle.id, |request| request.url.clear())
+                .await,
+            Err(DownloadError::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            service
+                .update_request_in_queue(Uuid::new_v4(), |_| {})
+                .await,
+            Err(DownloadError::NotQueued(_))
+        ));
+        drop(slot);
+
+        let id = handle.id;
+        let mut events = handle.take_events().unwrap();
+        let mut args = Vec::new();
+        let mut summary = None;
+        while let Some(event) = events.recv().await {
+            match event {
+                DownloadEvent::LogLine(line) => args.push(line),
+                DownloadEvent::Completed(completed) => summary = Some(completed),
+                _ => {}
+            }
+        }
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["--audio-format", "opus"]));
+        assert_eq!(summary.unwrap().file_path, Some(destination));
+        let entry = service.inner.history.recent(1).unwrap().remove(0);
+        assert_eq!(entry.format, AudioFormat::Opus);
+
+        assert!(matches!(
+            service.update_request_in_queue(id, |_| {}).await,
+            Err(DownloadError::JobAlreadyStarted(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn quiet_download_still_reports_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        // With `--quiet` yt-dlp prints no destination line, only progress.
+        let yt_dlp = MockYtDlp::builder()
+            .stderr("[download]  75.0% of 2.00MiB at 1.00MiB/s ETA 00:01")
+            .create_file(dir.path().join("Space.mp3"))
+            .build()
+            .unwrap();
+
+        let mut config = Config::default();
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        config.advanced.quiet = true;
+        let service = DownloaderService::new_without_history(config);
+        let mut request = sample_request();
+        request.output_dir = dir.path().to_path_buf();
+        let handle = service.queue(request).await.unwrap();
+
+        let mut events = handle.take_events().unwrap();
+        let mut percents = Vec::new();
+        let mut status = None;
+        while let Some(event) = events.recv().await {
+            match event {
+                DownloadEvent::Progress(progress) => percents.extend(progress.percent),
+                DownloadEvent::Completed(completed) => status = Some(completed.status),
+                _ => {}
+            }
+        }
+        assert_eq!(percents, [75.0]);
+        assert_eq!(status, Some(JobStatus::Succeeded));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn download_failure_is_classified_from_stderr() {
+        let dir = tempfile::tempdir().unwrap();
+        let yt_dlp =
+            MockYtDlp::fail_with(1, "ERROR: Unsupported URL: https://x.com/i/spaces/1").unwrap();
+
+        let mut config = Config::default();
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        let service = DownloaderService::new_without_history(config);
+        let mut request = sample_request();
+        request.output_dir = dir.path().to_path_buf();
+        let summary = service.queue(request).await.unwrap().wait().await;
+
+        assert_eq!(summary.status, JobStatus::Failed);
+        assert_eq!(
+            summary.error_message.as_deref(),
+            Some("unsupported URL: https://x.com/i/spaces/1")
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sponsorblock_segments_are_reported_from_the_info_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("Space.m4a");
+        let info_json = serde_json::json!({
+            "title": "Space",
+            "sponsorblock_chapters": [
+                {"start_time": 0.0, "end_time": 12.4, "category": "intro", "title": "Intro", "type": "skip"},
+                {"start_time": 65.2, "end_time": 130.5, "category": "sponsor", "title": "Sponsor", "type": "skip"},
+            ],
+        });
+        let yt_dlp = MockYtDlp::builder()
+            .stdout("[SponsorBlock] Fetching SponsorBlock segments")
+            .stdout("[SponsorBlock] Found 2 segments in the SponsorBlock database")
+            .write_file(dir.path().join("Space.info.json"), info_json.to_string())
+            .stderr(format!("[download] Destination: {}", destination.display()))
+            .create_file(&destination)
+            .build()
+            .unwrap();
+
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        let service = DownloaderService::new_without_history(config);
+        let mut request = sample_request();
+        request.output_dir = dir.path().to_path_buf();
+        let handle = service.queue(request).await.unwrap();
+        let mut events = handle.take_events().unwrap();
+
+        let mut marks = Vec::new();
+        while let Some(event) = events.recv().await {
+            match event {
+                DownloadEvent::SponsorBlockMark {
+                    start_secs,
+                    end_secs,
+                    category,
+                } => marks.push((start_secs, end_secs, category)),
+                DownloadEvent::Completed(summary) => {
+                    assert_eq!(summary.status, JobStatus::Succeeded);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(
+            marks,
+            vec![
+                (0, 13, "intro".to_string()),
+                (65, 131, "sponsor".to_string()),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn queue_with_callback_reports_completion() {
+        let dir = tempfile::tempdir().unwrap();
+        let yt_dlp = MockYtDlp::immediate_success(dir.path().join("Space.m4a")).unwrap();
+
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        let service = DownloaderService::new_without_history(config);
+
+        let completed: Arc<ParkingMutex<Option<DownloadSummary>>> = Arc::default();
+        let id = service
+            .queue_with_callback(sample_request(), {
+                let completed = completed.clone();
+                move |summary| *completed.lock() = Some(summary)
+            })
+            .await
+            .unwrap();
+
+        for _ in 0..500 {
+            if completed.lock().is_some() {
+                break;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        let summary = completed.lock().take().expect("callback was called");
+        assert_eq!(summary.id, id);
+        assert_eq!(summary.status, JobStatus::Succeeded);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn preview_thumbnail_fetches_printed_url() {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            tokio::io::AsyncReadExt::read(&mut socket, &mut request)
+                .await
+                .unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\nRIFF")
+                .await
+                .unwrap();
+        });
+
+        let yt_dlp = MockYtDlp::builder()
+            .stdout(format!("http://127.0.0.1:{port}/thumb.webp"))
+            .build()
+            .unwrap();
+        let advanced = AdvancedSettings {
+            yt_dlp_path: yt_dlp.path(),
+            ..AdvancedSettings::default()
+        };
+
+        let bytes = sample_request().preview_thumbnail(&advanced).await.unwrap();
+        assert_eq!(bytes, b"RIFF");
+
+        let yt_dlp = MockYtDlp::builder().stdout("NA").build().unwrap();
+        let advanced = AdvancedSettings {
+            yt_dlp_path: yt_dlp.path(),
+            ..AdvancedSettings::default()
+        };
+        let error = sample_request()
+            .preview_thumbnail(&advanced)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, DownloadError::CommandFailed { .. }));
+    }
+
+    #[test]
+    fn partial_files_are_matched_by_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "Space.m4a.part",
+            "Space.m4a.part-Frag3",
+            "Space.m4a.ytdl",
+            "Space.temp.tmp",
+            "Space.m4a",
+            "Other.m4a.part",
+        ] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let deleted = remove_partial_files(dir.path(), &dir.path().join("Space.m4a")).unwrap();
+        assert_eq!(deleted, 4);
+        let mut left: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["Other.m4a.part", "Space.m4a"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn abort_and_delete_removes_partial_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let part = dir.path().join("Space.m4a.part");
+        let yt_dlp = MockYtDlp::builder()
+            .create_file(&part)
+            .stderr(format!(
+                "[download] Destination: {}",
+                dir.path().join("Space.m4a").display()
+            ))
+            .sleep(Duration::from_secs(10))
+            .build()
+            .unwrap();
+
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        let service = DownloaderService::new_without_history(config);
+        assert!(!service.abort_and_delete(Uuid::new_v4()).await.unwrap());
+
+        let mut request = sample_request();
+        request.output_dir = dir.path().to_path_buf();
+        let handle = service.queue(request).await.unwrap();
+        let mut events = handle.take_events().unwrap();
+        while let Some(event) = events.recv().await {
+            if matches!(&event, DownloadEvent::LogLine(line) if line.contains("Destination")) {
+                break;
+            }
+        }
+
+        assert!(service.abort_and_delete(handle.id).await.unwrap());
+        assert!(!part.exists());
+        assert_eq!(*handle.status_receiver().borrow(), JobStatus::Canceled);
+    }
+
+    #[test]
+    fn permission_denied_spawn_names_the_binary() {
+        let command = Command::new("/opt/yt-dlp");
+        let error = spawn_error(&command, io::ErrorKind::PermissionDenied.into());
+        assert!(matches!(
+            &error,
+            DownloadError::SpawnPermissionDenied { path } if path == Path::new("/opt/yt-dlp")
+        ));
+        assert!(error_message(&error).contains("chmod +x /opt/yt-dlp"));
+        assert!(matches!(
+            spawn_error(&command, io::ErrorKind::NotFound.into()),
+            DownloadError::Spawn { .. }
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn non_executable_yt_dlp_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let yt_dlp = dir.path().join("yt-dlp");
+        std::fs::write(&yt_dlp, "#!/bin/sh\n").unwrap();
+        let advanced = AdvancedSettings {
+            yt_dlp_path: yt_dlp.clone(),
+            ..AdvancedSettings::default()
+        };
+
+        let error = sample_request()
+            .preview_thumbnail(&advanced)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            DownloadError::SpawnPermissionDenied { path } if path == yt_dlp
+        ));
+    }
+
+    #[tokio::test]
+    async fn scheduled_job_waits_for_its_start_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.advanced.yt_dlp_path = dir.path().join("missing-yt-dlp");
+        let service = DownloaderService::new_without_history(config);
+        let mut request = sample_request();
+        request.output_dir = dir.path().to_path_buf();
+
+        let start = Instant::now();
+        let at = Utc::now() + chrono::Duration::milliseconds(100);
+        let handle = service.queue_scheduled(request, at).await.unwrap();
+        assert_eq!(handle.scheduled_at(), Some(at));
+        assert_eq!(*handle.status_receiver().borrow(), JobStatus::Scheduled);
+
+        let mut events = handle.take_events().unwrap();
+        let mut statuses = Vec::new();
+        while let Some(event) = events.recv().await {
+            if let DownloadEvent::Status(status) = event {
+                statuses.push(status);
+                if status == JobStatus::Running {
+                    break;
+                }
+            }
+        }
+        assert_eq!(statuses, [JobStatus::Queued, JobStatus::Running]);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test]
+    async fn canceling_scheduled_job_does_not_wait_for_start_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = DownloaderService::new_without_history(Config::default());
+        let mut request = sample_request();
+        request.output_dir = dir.path().to_path_buf();
+
+        let at = Utc::now() + chrono::Duration::hours(1);
+        let handle = service.queue_scheduled(request, at).await.unwrap();
+        handle.cancel();
+        let summary = time::timeout(Duration::from_secs(5), handle.wait())
+            .await
+            .expect("canceled before the start time");
+        assert_eq!(summary.status, JobStatus::Canceled);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn running_job_progress_is_saved_until_it_finishes() {
+        let dir = tempfile::tempdir().unwrap();
+        let yt_dlp = MockYtDlp::builder()
+            .stderr("[download]  67.0% of 10.00MiB at 1.00MiB/s ETA 00:05")
+            .sleep(Duration::from_secs(1))
+            .build()
+            .unwrap();
+
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        let in_progress = dir.path().join("in_progress");
+        let service = DownloaderService::builder()
+            .config(config)
+            .history(HistoryRepository::open_in_memory().unwrap())
+            .in_progress_dir(in_progress.clone())
+            .build()
+            .unwrap();
+
+        let handle = service.queue(sample_request()).await.unwrap();
+        let progress_file = in_progress.join(format!("{}.json", handle.id));
+        for _ in 0..500 {
+            if progress_file.exists() {
+                break;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        let saved: JobProgress =
+            serde_json::from_str(&std::fs::read_to_string(&progress_file).unwrap()).unwrap();
+        assert_eq!(saved.id, handle.id);
+        assert_eq!(saved.percent, Some(67.0));
+
+        handle.wait().await;
+        // The file is removed while the job is finalized, just after the
+        // completion event that `wait` returns on.
+        for _ in 0..500 {
+            if !progress_file.exists() {
+                break;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(!progress_file.exists());
+    }
+
+    #[test]
+    fn interrupted_jobs_are_read_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let progress = JobProgress {
+            id: Uuid::new_v4(),
+            url: "https://x.com/i/spaces/1".to_string(),
+            percent: Some(67.0),
+            downloaded_bytes: Some(1024),
+            eta: None,
+        };
+        std::fs::write(
+            dir.path().join(format!("{}.json", progress.id)),
+            serde_json::to_string(&progress).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("broken.json"), "{").unwrap();
+
+        assert_eq!(JobProgress::take_interrupted(dir.path()), vec![progress]);
+        assert!(JobProgress::take_interrupted(dir.path()).is_empty());
+        assert!(JobProgress::take_interrupted(&dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn normalize_url_strips_youtube_tracking_params() {
+        assert_eq!(
+            normalize_url("https://www.youtube.com/watch?v=abc&si=xyz&utm_source=share"),
+            "https://www.youtube.com/watch?v=abc"
+        );
+        assert_eq!(
+            normalize_url("https://youtu.be/abc?si=xyz"),
+            "https://youtu.be/abc"
+        );
+        assert_eq!(
+            normalize_url("https://x.com/i/spaces/1?si=xyz"),
+            "https://x.com/i/spaces/1?si=xyz"
+        );
+    }
+
+    #[tokio::test]
+    async fn queue_url_list_reports_each_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        let service = DownloaderService::new_without_history(config);
+
+        // Hold the only download slot so the queued job never starts.
+        let semaphore = service.inner.semaphore.clone();
+        let _slot = semaphore.acquire_owned().await.unwrap();
+
+        let urls = vec![
+            " https://x.com/i/spaces/1 ".to_string(),
+            String::new(),
+            "not a url".to_string(),
+        ];
+        let results = service
+            .queue_url_list(&urls, DownloadSettings::default())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        let handle = results[0].as_ref().unwrap();
+        assert_eq!(handle.url, "https://x.com/i/spaces/1");
+        handle.cancel();
+        assert!(matches!(results[1], Err(DownloadError::InvalidUrl(_))));
+
+        assert!(service
+            .queue_url_list(&[], DownloadSettings::default())
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            service
+                .queue_url_list(&["nope".to_string()], DownloadSettings::default())
+                .await,
+            Err(SpaceDownloaderError::Download(DownloadError::EmptyBatch))
+        ));
+    }
+
+    #[tokio::test]
+    async fn queue_uses_caller_supplied_job_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        let service = DownloaderService::new_without_history(config);
+
+        // Hold the only download slot so the job stays queued.
+        let semaphore = service.inner.semaphore.clone();
+        let _slot = semaphore.acquire_owned().await.unwrap();
+
+        let id = Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+        let mut request = sample_request();
+        request.output_dir = dir.path().to_path_buf();
+        request.id = Some(id);
+        let handle = service.queue(request.clone()).await.unwrap();
+        assert_eq!(handle.id, id);
+        assert!(service.inner.history.entry_exists(id).unwrap());
+
+        assert!(matches!(
+            service.queue(request.clone()).await,
+            Err(DownloadError::DuplicateJobId(duplicate)) if duplicate == id
+        ));
+
+        request.id = None;
+        let other = service.queue(request).await.unwrap();
+        assert_ne!(other.id, id);
+        handle.cancel();
+        other.cancel();
+    }
+
+    #[tokio::test]
+    async fn queue_rejects_ids_of_finished_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let earlier = Uuid::new_v4();
+        history
+            .record_queued(earlier, "https://x.com/i/spaces/0", AudioFormat::M4a)
+            .unwrap();
+        history
+            .mark_completed(earlier, JobStatus::Succeeded, None, None, None)
+            .unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        let service = DownloaderService::builder()
+            .config(config)
+            .history(history)
+            .build()
+            .unwrap();
+        let semaphore = service.inner.semaphore.clone();
+        let _slot = semaphore.acquire_owned().await.unwrap();
+
+        let mut request = sample_request();
+        request.output_dir = dir.path().to_path_buf();
+        request.id = Some(earlier);
+        assert!(matches!(
+            service.queue(request.clone()).await,
+            Err(DownloadError::DuplicateJobId(duplicate)) if duplicate == earlier
+        ));
+
+        let id = Uuid::new_v4();
+        request.id = Some(id);
+        let handle = service.queue(request.clone()).await.unwrap();
+        handle.cancel();
+        assert_eq!(handle.wait().await.status, JobStatus::Canceled);
+        assert!(matches!(
+            service.queue(request).await,
+            Err(DownloadError::DuplicateJobId(duplicate)) if duplicate == id
+        ));
+    }
+
+    #[tokio::test]
+    async fn requeue_failed_skips_permanent_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let record = |url: &str, status: JobStatus, error_code: Option<&str>| {
+            let job_id = Uuid::new_v4();
+            history
+                .record_queued(job_id, url, AudioFormat::Mp3)
+                .unwrap();
+            history
+                .mark_completed(job_id, status, None, error_code, None)
+                .unwrap();
+        };
+        record(
+            "https://x.com/i/spaces/1",
+            JobStatus::Failed,
+            Some("Failed"),
+        );
+        record(
+            "https://x.com/i/spaces/1",
+            JobStatus::Failed,
+            Some("RateLimited"),
+        );
+        record(
+            "https://x.com/i/spaces/2",
+            JobStatus::Failed,
+            Some("UnsupportedSite"),
+        );
+        record(
+            "https://x.com/i/spaces/3",
+            JobStatus::Failed,
+            Some("GeoBlocked"),
+        );
+        record(
+            "https://x.com/i/spaces/4",
+            JobStatus::Failed,
+            Some("RateLimited"),
+        );
+        record(
+            "https://x.com/i/spaces/5",
+            JobStatus::Failed,
+            Some("Failed"),
+        );
+        record("https://x.com/i/spaces/5", JobStatus::Succeeded, None);
+        record(
+            "https://x.com/i/spaces/6",
+            JobStatus::Canceled,
+            Some("Canceled"),
+        );
+
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().join("out");
+        let service = DownloaderService::new_without_history(config);
+        // Hold the only download slot so the jobs stay queued.
+        let semaphore = service.inner.semaphore.clone();
+        let _slot = semaphore.acquire_owned().await.unwrap();
+
+        let handles: Vec<JobHandle> = service
+            .requeue_failed(&history, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        let mut urls: Vec<&str> = handles.iter().map(|handle| handle.url.as_str()).collect();
+        urls.sort_unstable();
+        assert_eq!(
+            urls,
+            ["https://x.com/i/spaces/1", "https://x.com/i/spaces/4"]
+        );
+        for handle in &handles {
+            handle.cancel();
+        }
+    }
+
+    type CapturedSpans = HashMap<tracing::span::Id, (String, Option<String>)>;
+
+    /// Collects span names and their `job_id` field.
+    #[derive(Clone, Default)]
+    struct SpanCapture(Arc<ParkingMutex<CapturedSpans>>);
+
+    struct JobIdVisitor<'a>(&'a mut Option<String>);
+
+    impl tracing::field::Visit for JobIdVisitor<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "job_id" {
+                *self.0 = Some(format!("{value:?}"));
+            }
+        }
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanCapture {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            id: &tracing::span::Id,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut job_id = None;
+            attrs.record(&mut JobIdVisitor(&mut job_id));
+            self.0
+                .lock()
+                .insert(id.clone(), (attrs.metadata().name().to_string(), job_id));
+        }
+
+        fn on_record(
+            &self,
+            id: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            if let Some((_, job_id)) = self.0.lock().get_mut(id) {
+                values.record(&mut JobIdVisitor(job_id));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn public_methods_emit_spans_with_the_job_id() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let capture = SpanCapture::default();
+        let _default =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        let service = DownloaderService::new_without_history(config.clone());
+        service.update_config(config).await;
+        // Hold the only download slot so the job stays queued.
+        let semaphore = service.inner.semaphore.clone();
+        let _slot = semaphore.acquire_owned().await.unwrap();
+        let mut request = sample_request();
+        request.output_dir = dir.path().to_path_buf();
+        let handle = service.queue(request).await.unwrap();
+        tokio::task::yield_now().await;
+
+        let spans: Vec<(String, Option<String>)> = capture.0.lock().values().cloned().collect();
+        let job_id = Some(handle.id.to_string());
+        assert!(
+            spans.contains(&("update_config".to_string(), None)),
+            "{spans:?}"
+        );
+        assert!(
+            spans.contains(&("queue".to_string(), job_id.clone())),
+            "{spans:?}"
+        );
+        assert!(
+            spans.contains(&("download_job".to_string(), job_id)),
+            "{spans:?}"
+        );
+        handle.cancel();
+    }
+
+    #[tokio::test]
+    async fn get_config_returns_the_current_config() {
+        let mut config = Config::default();
+        config.general.output_dir = PathBuf::from("spaces");
+        config.download.format = AudioFormat::Opus;
+        let service = DownloaderService::new_without_history(config);
+
+        let current = service.get_config().await;
+        assert_eq!(current.general.output_dir, PathBuf::from("spaces"));
+        assert_eq!(current.download.format, AudioFormat::Opus);
+
+        let mut updated = current.clone();
+        updated.download.format = AudioFormat::Mp3;
+        updated.download.concurrency = 3;
+        service.update_config(updated).await;
+        let current = service.get_config().await;
+        assert_eq!(current.download.format, AudioFormat::Mp3);
+        assert_eq!(current.download.concurrency, 3);
+    }
+
+    #[tokio::test]
+    async fn set_max_concurrency_resizes_semaphore() {
+        let service = DownloaderService::new_without_history(Config::default());
+
+        service.set_max_concurrency(2).await;
+        assert_eq!(service.inner.semaphore.available_permits(), 2);
+        assert_eq!(service.inner.config.read().await.download.concurrency, 2);
+
+        service.set_max_concurrency(10).await;
+        assert_eq!(
+            service.inner.semaphore.available_permits(),
+            crate::config::MAX_CONCURRENCY
+        );
+    }
+
+    #[tokio::test]
+    async fn update_settings_leaves_concurrency_alone() {
+        let service = DownloaderService::new_without_history(Config::default());
+        service.set_max_concurrency(2).await;
+
+        let mut config = Config::default();
+        config.download.concurrency = 4;
+        config.download.prefer_free_formats = true;
+        config.advanced.force_overwrites = true;
+        service
+            .update_settings(config.download, config.advanced)
+            .await;
+
+        let current = service.get_config().await;
+        assert!(current.download.prefer_free_formats);
+        assert!(current.advanced.force_overwrites);
+        assert_eq!(current.download.concurrency, 2);
+        assert_eq!(service.inner.semaphore.available_permits(), 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn raising_concurrency_starts_queued_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let yt_dlp = MockYtDlp::builder()
+            .sleep(Duration::from_secs(10))
+            .build()
+            .unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        config.download.concurrency = 1;
+        let service = DownloaderService::new_without_history(config);
+
+        let mut handles = Vec::new();
+        for index in 0..3 {
+            let mut request = sample_request();
+            request.url = format!("https://x.com/i/spaces/{index}");
+            request.output_dir = dir.path().to_path_buf();
+            handles.push(service.queue(request).await.unwrap());
+        }
+        let running = |handles: &[JobHandle]| {
+            handles
+                .iter()
+                .filter(|handle| *handle.status_receiver().borrow() == JobStatus::Running)
+                .count()
+        };
+        async fn wait_until(condition: impl Fn() -> bool) -> bool {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while !condition() {
+                if Instant::now() >= deadline {
+                    return false;
+                }
+                time::sleep(Duration::from_millis(20)).await;
+            }
+            true
+        }
+        assert!(wait_until(|| running(&handles) == 1).await);
+        time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(running(&handles), 1);
+
+        service.set_max_concurrency(3).await;
+        assert!(wait_until(|| running(&handles) == 3).await);
+
+        // Lowering the limit below the slots in use retires them as the
+        // running jobs finish.
+        service.set_max_concurrency(1).await;
+        for handle in &handles {
+            handle.cancel();
+        }
+        let semaphore = service.inner.semaphore.clone();
+        assert!(wait_until(|| service.running_job_ids().is_empty()).await);
+        assert!(wait_until(|| semaphore.available_permits() == 1).await);
+        assert_eq!(service.inner.slot_limit.lock().to_retire, 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn raising_concurrency_starts_boosted_jobs_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let yt_dlp = MockYtDlp::builder()
+            .sleep(Duration::from_secs(10))
+            .build()
+            .unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        config.download.concurrency = 1;
+        let service = DownloaderService::new_without_history(config);
+
+        let mut handles = Vec::new();
+        for index in 0..3 {
+            let mut request = sample_request();
+            request.url = format!("https://x.com/i/spaces/{index}");
+            request.output_dir = dir.path().to_path_buf();
+            handles.push(service.queue(request).await.unwrap());
+        }
+        let status = |handle: &JobHandle| *handle.status_receiver().borrow();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while status(&handles[0]) != JobStatus::Running {
+            assert!(Instant::now() < deadline, "first job did not start");
+            time::sleep(Duration::from_millis(20)).await;
+        }
+        service.queue_with_priority_boost(handles[2].id).unwrap();
+        while service.inner.boosted_waiters.lock().is_empty() {
+            assert!(Instant::now() < deadline, "boosted job is not waiting");
+            time::sleep(Duration::from_millis(20)).await;
+        }
+
+        service.set_max_concurrency(2).await;
+        while status(&handles[2]) != JobStatus::Running {
+            assert!(Instant::now() < deadline, "boosted job did not start");
+            time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(status(&handles[1]), JobStatus::Queued);
+
+        for handle in &handles {
+            handle.cancel();
+        }
+        // The handed-over slot returns to the semaphore once released.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while service.inner.semaphore.available_permits() != 2 {
+            assert!(Instant::now() < deadline, "slots were not released");
+            time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limit_line_emits_event_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let (status_tx, _status_rx) = watch::channel(JobStatus::Running);
+        let (progress_tx, _progress_rx) = watch::channel(None);
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let advanced = AdvancedSettings {
+            rate_limit_cooldown_sec: 5,
+            ..AdvancedSettings::default()
+        };
+        let job = JobRuntime {
+            id: Uuid::new_v4(),
+            request: Arc::new(ParkingMutex::new(sample_request())),
+            status_tx,
+            progress_tx,
+            events_tx,
+            cancel_token: CancellationToken::new(),
+            download_settings: DownloadSettings::default(),
+            advanced_settings: advanced.clone(),
+            history: history.into(),
+            history_row_id: ParkingMutex::new(None),
+            progress_file: None,
+            progress_saved_at: ParkingMutex::new(None),
+            counters: Arc::default(),
+            running_ids: Arc::default(),
+            tracked_jobs: Arc::default(),
+            destination: Arc::default(),
+        };
+
+        let mut state = AttemptState::default();
+        let line = "ERROR: [twitter] 1yNGaYDqjVgxj: HTTP Error 429: Too Many Requests";
+        handle_process_line(&job, line, &mut state).await;
+        handle_process_line(&job, line, &mut state).await;
+        drop(job);
+
+        assert!(state.rate_limited);
+        let mut cooldowns = Vec::new();
+        while let Some(event) = events_rx.recv().await {
+            if let DownloadEvent::RateLimited(cooldown) = event {
+                cooldowns.push(cooldown);
+            }
+        }
+        assert_eq!(cooldowns, vec![Duration::from_secs(5)]);
+
+        assert_eq!(
+            retry_wait(&DownloadError::RateLimited, 1, &advanced),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            retry_wait(&DownloadError::Timeout(30), 1, &advanced),
+            retry_delay(1)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_received_bytes_from_net_dev() {
+        let content = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets
+    lo:  900000    1000    0    0    0     0          0         0   900000    1000
+  eth0: 1500000    2000    0    0    0     0          0         0   300000     900
+ wlan0:     500      10    0    0    0     0          0         0      100       2
+";
+        assert_eq!(parse_net_dev_received(content), 1_500_500);
+    }
+
+    #[test]
+    fn detects_rate_limit_messages() {
+        assert!(is_rate_limit_line(
+            "ERROR: HTTP Error 429: Too Many Requests"
+        ));
+        assert!(is_rate_limit_line("WARNING: Rate limit exceeded"));
+        assert!(!is_rate_limit_line("[download]  42.0% of 10.00MiB"));
+    }
+
+    #[test]
+    fn retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay(0), Duration::from_secs(1));
+        assert_eq!(retry_delay(1), Duration::from_secs(2));
+        assert_eq!(retry_delay(3), Duration::from_secs(8));
+        assert_eq!(retry_delay(6), Duration::from_secs(60));
+        assert_eq!(retry_delay(u8::MAX), Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn status_reports_known_jobs_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        let service = DownloaderService::builder()
+            .config(config)
+            .history(history)
+            .build()
+            .unwrap();
+
+        assert!(service.status(Uuid::new_v4()).is_none());
+
+        let semaphore = service.inner.semaphore.clone();
+        let _slot = semaphore.acquire_owned().await.unwrap();
+        let url = "https://x.com/i/spaces/1".to_string();
+        let handle = service
+            .queue(DownloadRequest::new(
+                url.clone(),
+                PathBuf::new(),
+                AudioFormat::M4a,
+            ))
+            .await
+            .unwrap();
+
+        let state = service.status(handle.id).expect("queued job is tracked");
+        assert_eq!(state.id, handle.id);
+        assert_eq!(state.url, url);
+        assert_eq!(state.status, JobStatus::Queued);
+        assert!(state.progress.is_none());
+    }
+
+    #[tokio::test]
+    async fn clone_with_config_keeps_existing_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        let service = DownloaderService::builder()
+            .config(config.clone())
+            .history(history)
+            .build()
+            .unwrap();
+
+        let semaphore = service.inner.semaphore.clone();
+        let _slot = semaphore.acquire_owned().await.unwrap();
+        let handle = service
+            .queue(DownloadRequest::new(
+                "https://x.com/i/spaces/1".to_string(),
+                PathBuf::new(),
+                AudioFormat::M4a,
+            ))
+            .await
+            .unwrap();
+
+        config.download.concurrency = 3;
+        let updated = service.clone_with_config(config).await;
+
+        assert!(Arc::ptr_eq(&service.inner, &updated.inner));
+        assert_eq!(updated.inner.config.read().await.download.concurrency, 3);
+        assert_eq!(service.running_job_ids(), vec![handle.id]);
+        assert_eq!(*handle.status_receiver().borrow(), JobStatus::Queued);
+    }
+
+    #[tokio::test]
+    async fn builder_rejects_invalid_config() {
+        let mut config = Config::default();
+        config.download.audio_bitrate_kbps = Some(16);
+        let result = DownloaderService::builder()
+            .config(config)
+            .history(HistoryRepository::open_in_memory().unwrap())
+            .build();
+        assert!(matches!(result, Err(SpaceDownloaderError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn from_files_loads_config_and_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().join("downloads");
+        config.download.concurrency = 2;
+        config.save(&config_path).unwrap();
+
+        let (service, history) =
+            DownloaderService::from_files(&config_path, dir.path().join("history.db")).unwrap();
+        assert_eq!(service.inner.config.read().await.download.concurrency, 2);
+
+        let semaphore = service.inner.semaphore.clone();
+        let _slots = semaphore.acquire_many_owned(2).await.unwrap();
+        let handle = service
+            .queue(DownloadRequest::new(
+                "https://x.com/i/spaces/1".to_string(),
+                PathBuf::new(),
+                AudioFormat::M4a,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(history.recent(10).unwrap().len(), 1);
+        handle.cancel();
+    }
+
+    #[tokio::test]
+    async fn builder_enables_deduplication_and_event_sink() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        let (sink_tx, mut sink_rx) = mpsc::channel(16);
+        let service = DownloaderService::builder()
+            .config(config)
+            .history(HistoryRepository::open_in_memory().unwrap())
+            .event_sink(sink_tx)
+            .completed_job_ttl(Duration::from_secs(5))
+            .enable_deduplication(true)
+            .build()
+            .unwrap();
+        assert_eq!(service.inner.completed_job_ttl, Duration::from_secs(5));
+
+        let semaphore = service.inner.semaphore.clone();
+        let _slot = semaphore.acquire_owned().await.unwrap();
+        let request = DownloadRequest::new(
+            "https://x.com/i/spaces/1".to_string(),
+            PathBuf::new(),
+            AudioFormat::M4a,
+        );
+        let handle = service.queue(request.clone()).await.unwrap();
+        assert!(matches!(
+            service.queue(request).await,
+            Err(DownloadError::AlreadyQueued(_))
+        ));
+
+        handle.cancel();
+        let (id, event) = sink_rx.recv().await.unwrap();
+        assert_eq!(id, handle.id);
+        assert_eq!(event, DownloadEvent::Status(JobStatus::Canceled));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_queues_of_one_url_are_deduplicated() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        let service = DownloaderService::builder()
+            .config(config)
+            .history(HistoryRepository::open_in_memory().unwrap())
+            .enable_deduplication(true)
+            .build()
+            .unwrap();
+        let semaphore = service.inner.semaphore.clone();
+        let _slot = semaphore.acquire_owned().await.unwrap();
+
+        let attempts = (0..8).map(|_| {
+            let service = service.clone();
+            tokio::spawn(async move {
+                service
+                    .queue(DownloadRequest::new(
+                        "https://x.com/i/spaces/1".to_string(),
+                        PathBuf::new(),
+                        AudioFormat::M4a,
+                    ))
+                    .await
+            })
+        });
+        let mut queued = Vec::new();
+        for attempt in attempts.collect::<Vec<_>>() {
+            match attempt.await.unwrap() {
+                Ok(handle) => queued.push(handle),
+                Err(err) => assert!(matches!(err, DownloadError::AlreadyQueued(_))),
+            }
+        }
+        assert_eq!(queued.len(), 1);
+        queued[0].cancel();
+    }
+
+    #[test]
+    fn classify_detects_unsupported_site() {
+        let stderr = "WARNING: [generic] Falling back on generic information extractor\n\
+                      ERROR: Unsupported URL: https://example.com/page\n";
+        assert!(matches!(
+            classify_process_result(1, stderr, "https://example.com/page"),
+            DownloadError::UnsupportedSite(url) if url == "https://example.com/page"
+        ));
+    }
+
+    #[test]
+    fn classify_detects_quota_exceeded() {
+        let url = "https://www.youtube.com/watch?v=abc";
+        for stderr in [
+            "ERROR: [youtube] abc: Download quota exceeded for this IP",
+            "ERROR: unable to download video data: HTTP Error 403: Forbidden\n\
+             The download QUOTA for this network has been reached",
+            "ERROR: [youtube] abc: Sign in to confirm you're not a bot. \
+             Use --cookies-from-browser or --cookies for the authentication.",
+            "ERROR: [youtube] abc: Sign in to confirm you\u{2019}re not a bot.",
+        ] {
+            let error = classify_process_result(1, stderr, url);
+            assert!(
+                matches!(&error, DownloadError::Quota(quota_url) if quota_url == url),
+                "{stderr}: {error:?}"
+            );
+            assert!(!error.is_retriable());
+            assert_eq!(error_message(&error), "download quota exceeded");
+        }
+        for stderr in [
+            "ERROR: HTTP Error 403: Forbidden",
+            "ERROR: unable to write data: OSError: [Errno 122] Disk quota exceeded",
+        ] {
+            assert!(
+                matches!(
+                    classify_process_result(1, stderr, url),
+                    DownloadError::CommandFailed { .. }
+                ),
+                "{stderr}"
+            );
+        }
+    }
+
+    #[test]
+    fn classify_detects_out_of_memory() {
+        assert!(matches!(
+            classify_process_result(137, "", ""),
+            DownloadError::OutOfMemory
+        ));
+        assert!(matches!(
+            classify_process_result(1, "Traceback (most recent call last):\nMemoryError", ""),
+            DownloadError::OutOfMemory
+        ));
+        assert!(matches!(
+            classify_process_result(1, "[download] 12.0% of 1.00GiB\nKilled", ""),
+            DownloadError::OutOfMemory
+        ));
+    }
+
+    #[test]
+    fn classify_keeps_other_failures_as_command_failed() {
+        match classify_process_result(1, "ERROR: Unable to download webpage", "") {
+            DownloadError::CommandFailed { status, stderr } => {
+                assert_eq!(status, Some(1));
+                assert_eq!(stderr, "ERROR: Unable to download webpage");
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+        assert!(matches!(
+            classify_process_result(2, "ERROR: Video was killed by the uploader", ""),
+            DownloadError::CommandFailed { .. }
+        ));
+    }
+
+    #[test]
+    fn metadata_reads_view_and_like_counts() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("space.info.json"),
+            serde_json::json!({
+                "title": "Space",
+                "view_count": 12_345_678_901u64,
+                "like_count": 42,
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let metadata = read_latest_metadata(dir.path()).unwrap();
+        assert_eq!(metadata.view_count, Some(12_345_678_901));
+        assert_eq!(metadata.like_count, Some(42));
+        assert!(!metadata.has_comments);
+    }
+
+    #[test]
+    fn metadata_reads_canonical_webpage_url() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("track.info.json"),
+            serde_json::json!({
+                "title": "Track",
+                "original_url": "https://on.soundcloud.com/AbCdEf",
+                "webpage_url": "https://soundcloud.com/artist/track",
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let metadata = read_latest_metadata(dir.path()).unwrap();
+        assert_eq!(
+            metadata.source_url.as_deref(),
+            Some("https://soundcloud.com/artist/track")
+        );
+
+        std::fs::write(
+            dir.path().join("track.info.json"),
+            serde_json::json!({ "title": "Track" }).to_string(),
+        )
+        .unwrap();
+        assert_eq!(read_latest_metadata(dir.path()).unwrap().source_url, None);
+    }
+
+    #[test]
+    fn metadata_notes_comments_without_keeping_them() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("space.info.json"),
+            serde_json::json!({
+                "title": "Space",
+                "comments": [{"id": "1", "text": "first", "replies": []}],
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let metadata = read_latest_metadata(dir.path()).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Space"));
+        assert!(metadata.has_comments);
+    }
+
+    #[test]
+    fn info_json_is_removed_unless_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let info_path = dir.path().join("space.info.json");
+        let info = serde_json::json!({ "title": "Space" }).to_string();
+
+        std::fs::write(&info_path, &info).unwrap();
+        let metadata = take_latest_metadata(dir.path(), true).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Space"));
+        assert!(info_path.exists());
+
+        let metadata = take_latest_metadata(dir.path(), false).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Space"));
+        assert!(!info_path.exists());
+    }
+
+    #[test]
+    fn fragment_retries_are_passed_only_when_changed() {
+        let args = build_args(
+            &sample_request(),
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert_eq!(arg_after(&args, "--fragment-retries"), None);
+
+        for retries in [0, 25] {
+            let advanced = AdvancedSettings {
+                fragment_retries: retries,
+                ..AdvancedSettings::default()
+            };
+            let args = build_args(&sample_request(), &DownloadSettings::default(), &advanced);
+            assert_eq!(
+                arg_after(&args, "--fragment-retries"),
+                Some(&OsString::from(retries.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn quiet_keeps_progress_output() {
+        let args = build_args(
+            &sample_request(),
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert!(!args
+            .iter()
+            .any(|arg| arg == "--quiet" || arg == "--no-warnings"));
+
+        let advanced = AdvancedSettings {
+            quiet: true,
+            ..AdvancedSettings::default()
+        };
+        let args = build_args(&sample_request(), &DownloadSettings::default(), &advanced);
+        for flag in ["--quiet", "--no-warnings", "--progress"] {
+            assert!(args.iter().any(|arg| arg == flag), "missing {flag}");
+        }
+    }
+
+    #[test]
+    fn print_traffic_adds_flag_and_widens_event_channel() {
+        let args = build_args(
+            &sample_request(),
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert!(!args.iter().any(|arg| arg == "--print-traffic"));
+        assert_eq!(
+            event_channel_capacity(&AdvancedSettings::default()),
+            EVENT_CHANNEL_CAPACITY
+        );
+
+        let advanced = AdvancedSettings {
+            print_traffic: true,
+            ..AdvancedSettings::default()
+        };
+        let args = build_args(&sample_request(), &DownloadSettings::default(), &advanced);
+        assert!(args.iter().any(|arg| arg == "--print-traffic"));
+        assert_eq!(
+            event_channel_capacity(&advanced),
+            TRAFFIC_EVENT_CHANNEL_CAPACITY
+        );
+    }
+
+    #[test]
+    fn force_overwrites_overrides_extra_args() {
+        let mut request = sample_request();
+        request.extra_args = vec!["--no-overwrites".to_string()];
+        let args = build_args(
+            &request,
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert!(!args.iter().any(|arg| arg == "--force-overwrites"));
+
+        let advanced = AdvancedSettings {
+            force_overwrites: true,
+            ..AdvancedSettings::default()
+        };
+        let args = build_args(&request, &DownloadSettings::default(), &advanced);
+        let position = |flag: &str| args.iter().position(|arg| arg == flag).unwrap();
+        // yt-dlp applies the last overwrite flag it sees.
+        assert!(position("--force-overwrites") > position("--no-overwrites"));
+        assert_eq!(args.last(), Some(&OsString::from(&request.url)));
+    }
+
+    #[test]
+    fn live_settings_add_flags() {
+        let args = build_args(
+            &sample_request(),
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert!(!args.iter().any(|arg| arg == "--live-from-start"));
+        assert!(!args.iter().any(|arg| arg == "--wait-for-video"));
+
+        let advanced = AdvancedSettings {
+            live_from_start: true,
+            wait_for_video: Some((30, 300)),
+            ..AdvancedSettings::default()
+        };
+        let args = build_args(&sample_request(), &DownloadSettings::default(), &advanced);
+        assert!(args.iter().any(|arg| arg == "--live-from-start"));
+        assert_eq!(
+            arg_after(&args, "--wait-for-video"),
+            Some(&OsString::from("30-300"))
+        );
+    }
+
+    #[test]
+    fn write_comments_adds_flag() {
+        let download = DownloadSettings {
+            write_comments: true,
+            ..DownloadSettings::default()
+        };
+        let args = build_args(&sample_request(), &download, &AdvancedSettings::default());
+        assert!(args.iter().any(|arg| arg == "--write-comments"));
+        let args = build_args(
+            &sample_request(),
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert!(!args.iter().any(|arg| arg == "--write-comments"));
+    }
+
+    #[test]
+    fn prefer_free_formats_adds_flag() {
+        let download = DownloadSettings {
+            prefer_free_formats: true,
+            ..DownloadSettings::default()
+        };
+        let args = build_args(&sample_request(), &download, &AdvancedSettings::default());
+        assert!(args.iter().any(|arg| arg == "--prefer-free-formats"));
+        let args = build_args(
+            &sample_request(),
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert!(!args.iter().any(|arg| arg == "--prefer-free-formats"));
+    }
+
+    #[test]
+    fn age_limit_adds_flag_only_when_set() {
+        let download = DownloadSettings {
+            age_limit: Some(18),
+            ..DownloadSettings::default()
+        };
+        let args = build_args(&sample_request(), &download, &AdvancedSettings::default());
+        assert_eq!(arg_after(&args, "--age-limit"), Some(&OsString::from("18")));
+        let args = build_args(
+            &sample_request(),
+            &DownloadSettings::default(),
+            &AdvancedSettings::default(),
+        );
+        assert!(!args.iter().any(|arg| arg == "--age-limit"));
+    }
+
+    #[test]
+    fn metadata_description_is_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let long_description = "あ".repeat(DESCRIPTION_MAX_CHARS + 20);
+        std::fs::write(
+            dir.path().join("space.info.json"),
+            serde_json::json!({
+                "title": "Space",
+                "description": long_description,
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let metadata = read_latest_metadata(dir.path()).unwrap();
+        let description = metadata.description.unwrap();
+        assert_eq!(description.chars().count(), DESCRIPTION_MAX_CHARS);
+        assert!(long_description.starts_with(&description));
+    }
+}
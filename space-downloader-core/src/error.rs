@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use uuid::Uuid;
 
 #[derive(Debug, Error)]
 pub enum ConfigError {
@@ -21,6 +23,11 @@ pub enum ConfigError {
         #[source]
         source: toml::ser::Error,
     },
+    #[error("invalid config value for {field}: {message}")]
+    Invalid {
+        field: &'static str,
+        message: String,
+    },
 }
 
 #[derive(Debug, Error)]
@@ -72,12 +79,24 @@ pub enum HistoryError {
         #[source]
         source: rusqlite::Error,
     },
+    #[error("history database was opened read-only")]
+    ReadOnly,
+    #[error("history task failed: {source}")]
+    Join {
+        #[source]
+        source: tokio::task::JoinError,
+    },
 }
 
 #[derive(Debug, Error)]
 pub enum DownloadError {
     #[error("invalid url: {0}")]
     InvalidUrl(String),
+    #[error("invalid download request: {source}")]
+    InvalidRequest {
+        #[source]
+        source: ConfigError,
+    },
     #[error("missing dependency: {0}")]
     MissingDependency(String),
     #[error("failed to spawn yt-dlp process: {source}")]
@@ -85,12 +104,40 @@ pub enum DownloadError {
         #[source]
         source: std::io::Error,
     },
+    #[error("permission denied running yt-dlp at {path:?}")]
+    SpawnPermissionDenied { path: PathBuf },
     #[error("download command failed with status {status:?}: {stderr}")]
     CommandFailed { status: Option<i32>, stderr: String },
+    #[error("download killed by OS (low memory)")]
+    OutOfMemory,
+    #[error("rate limited by the server")]
+    RateLimited,
+    /// YouTube's download quota for this IP is used up. Carries the URL.
+    #[error("download quota exceeded for {0}")]
+    Quota(String),
     #[error("download canceled")]
     Canceled,
     #[error("download timed out after {0} seconds")]
     Timeout(u64),
+    #[error("none of the URLs in the batch are valid")]
+    EmptyBatch,
+    #[error("download job failed: {0}")]
+    JobFailed(String),
+    #[error("{0} is already queued")]
+    AlreadyQueued(String),
+    #[error("job {0} already exists")]
+    DuplicateJobId(Uuid),
+    #[error("job {0} is not waiting for a download slot")]
+    NotQueued(Uuid),
+    #[error("job {0} has already started")]
+    JobAlreadyStarted(Uuid),
+    #[error("unsupported URL: {0}")]
+    UnsupportedSite(String),
+    #[error("http request failed: {source}")]
+    Http {
+        #[source]
+        source: reqwest::Error,
+    },
     #[error("io error: {source}")]
     Io {
         #[source]
@@ -103,6 +150,122 @@ pub enum DownloadError {
     },
 }
 
+impl DownloadError {
+    /// Whether the failure is likely transient and the download worth retrying.
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            DownloadError::CommandFailed { stderr, .. } => {
+                !(requires_login(stderr) || is_geo_blocked(stderr))
+            }
+            DownloadError::Spawn { .. }
+            | DownloadError::Io { .. }
+            | DownloadError::Timeout(_)
+            | DownloadError::Http { .. }
+            | DownloadError::RateLimited => true,
+            DownloadError::InvalidUrl(_)
+            | DownloadError::InvalidRequest { .. }
+            | DownloadError::EmptyBatch
+            | DownloadError::JobFailed(_)
+            | DownloadError::AlreadyQueued(_)
+            | DownloadError::DuplicateJobId(_)
+            | DownloadError::NotQueued(_)
+            | DownloadError::JobAlreadyStarted(_)
+            | DownloadError::UnsupportedSite(_)
+            | DownloadError::Quota(_)
+            | DownloadError::MissingDependency(_)
+            | DownloadError::SpawnPermissionDenied { .. }
+            | DownloadError::OutOfMemory
+            | DownloadError::Canceled
+            | DownloadError::Join { .. } => false,
+        }
+    }
+
+    /// A suggested remedy to show below the error message, if there is one.
+    pub fn help(&self) -> Option<ErrorHelp> {
+        match self {
+            DownloadError::InvalidUrl(_) => Some(ErrorHelp::CheckUrl),
+            DownloadError::MissingDependency(_) => Some(ErrorHelp::InstallDependency),
+            DownloadError::CommandFailed { stderr, .. } if requires_login(stderr) => {
+                Some(ErrorHelp::AddCookieFile)
+            }
+            DownloadError::CommandFailed { stderr, .. } if is_geo_blocked(stderr) => {
+                Some(ErrorHelp::UseProxy)
+            }
+            DownloadError::RateLimited => Some(ErrorHelp::WaitForRateLimit),
+            DownloadError::UnsupportedSite(_) => Some(ErrorHelp::SupportedSites),
+            DownloadError::Quota(_) => Some(ErrorHelp::WaitForQuota),
+            DownloadError::SpawnPermissionDenied { .. } => Some(ErrorHelp::MakeExecutable),
+            _ => None,
+        }
+    }
+
+    /// [`DownloadError::help`] in English.
+    pub fn help_text(&self) -> Option<&'static str> {
+        self.help().map(ErrorHelp::text)
+    }
+
+    /// The code stored in the history `error_code` column for a failed job.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            DownloadError::UnsupportedSite(_) => "UnsupportedSite",
+            DownloadError::CommandFailed { stderr, .. } if is_geo_blocked(stderr) => "GeoBlocked",
+            DownloadError::RateLimited => "RateLimited",
+            DownloadError::Quota(_) => "Quota",
+            DownloadError::Canceled => "Canceled",
+            _ => "Failed",
+        }
+    }
+}
+
+/// A remedy to suggest for a [`DownloadError`]. Frontends with their own
+/// translations can map each variant to a message; [`ErrorHelp::text`] is the
+/// English one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorHelp {
+    CheckUrl,
+    InstallDependency,
+    AddCookieFile,
+    UseProxy,
+    WaitForRateLimit,
+    SupportedSites,
+    WaitForQuota,
+    MakeExecutable,
+}
+
+impl ErrorHelp {
+    pub fn text(self) -> &'static str {
+        match self {
+            ErrorHelp::CheckUrl => "Check that the URL starts with http:// or https://",
+            ErrorHelp::InstallDependency => {
+                "Install the missing dependency or check AdvancedSettings.yt_dlp_path"
+            }
+            ErrorHelp::AddCookieFile => "Add a cookie file via AdvancedSettings.cookie_file",
+            ErrorHelp::UseProxy => "Try using a VPN or proxy",
+            ErrorHelp::WaitForRateLimit => {
+                "Wait a while before retrying or raise AdvancedSettings.rate_limit_cooldown_sec"
+            }
+            ErrorHelp::SupportedSites => UNSUPPORTED_SITE_HELP,
+            ErrorHelp::WaitForQuota => QUOTA_HELP,
+            ErrorHelp::MakeExecutable => {
+                "Check that the yt-dlp binary is executable. On Unix: `chmod +x <path to yt-dlp>`"
+            }
+        }
+    }
+}
+
+pub(crate) const QUOTA_HELP: &str =
+    "Try again later or use a different network connection or cookie authentication.";
+
+pub(crate) const UNSUPPORTED_SITE_HELP: &str = "Try checking https://github.com/yt-dlp/yt-dlp#supported-sites for the list of supported platforms.";
+
+fn requires_login(stderr: &str) -> bool {
+    stderr.contains("Private") || stderr.contains("Sign in") || stderr.contains("login required")
+}
+
+fn is_geo_blocked(stderr: &str) -> bool {
+    stderr.contains("GeoBlocked") || stderr.contains("not available in your country")
+}
+
 #[derive(Debug, Error)]
 pub enum SpaceDownloaderError {
     #[error(transparent)]
@@ -116,3 +279,96 @@ pub enum SpaceDownloaderError {
 }
 
 pub type Result<T> = std::result::Result<T, SpaceDownloaderError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_failed(stderr: &str) -> DownloadError {
+        DownloadError::CommandFailed {
+            status: Some(1),
+            stderr: stderr.to_string(),
+        }
+    }
+
+    #[test]
+    fn error_codes_name_permanent_failures() {
+        assert_eq!(
+            DownloadError::UnsupportedSite("example.com".into()).error_code(),
+            "UnsupportedSite"
+        );
+        assert_eq!(
+            command_failed("ERROR: This video is not available in your country").error_code(),
+            "GeoBlocked"
+        );
+        assert_eq!(DownloadError::RateLimited.error_code(), "RateLimited");
+        assert_eq!(
+            command_failed("ERROR: HTTP Error 500").error_code(),
+            "Failed"
+        );
+    }
+
+    #[test]
+    fn transient_errors_are_retriable() {
+        assert!(command_failed("ERROR: Unable to download webpage").is_retriable());
+        assert!(DownloadError::Spawn {
+            source: std::io::Error::other("spawn"),
+        }
+        .is_retriable());
+        assert!(DownloadError::Io {
+            source: std::io::Error::other("io"),
+        }
+        .is_retriable());
+        assert!(DownloadError::Timeout(30).is_retriable());
+        assert!(DownloadError::RateLimited.is_retriable());
+    }
+
+    #[test]
+    fn permanent_errors_are_not_retriable() {
+        assert!(!command_failed("ERROR: This Space is Private").is_retriable());
+        assert!(!command_failed("ERROR: GeoBlocked in your country").is_retriable());
+        assert!(!command_failed("ERROR: Sign in to view this Space").is_retriable());
+        assert!(!command_failed("ERROR: login required").is_retriable());
+        assert!(
+            !command_failed("ERROR: This video is not available in your country").is_retriable()
+        );
+        assert!(!DownloadError::InvalidUrl("nope".to_string()).is_retriable());
+        assert!(!DownloadError::MissingDependency("yt-dlp".to_string()).is_retriable());
+        assert!(!DownloadError::OutOfMemory.is_retriable());
+        assert!(!DownloadError::Canceled.is_retriable());
+        assert!(!DownloadError::EmptyBatch.is_retriable());
+        assert!(!DownloadError::UnsupportedSite("https://example.com".to_string()).is_retriable());
+        assert!(!DownloadError::SpawnPermissionDenied {
+            path: PathBuf::from("yt-dlp"),
+        }
+        .is_retriable());
+    }
+
+    #[test]
+    fn actionable_errors_have_help_text() {
+        assert!(DownloadError::InvalidUrl("nope".to_string())
+            .help_text()
+            .is_some());
+        assert!(DownloadError::MissingDependency("yt-dlp".to_string())
+            .help_text()
+            .is_some());
+        assert_eq!(
+            command_failed("ERROR: This Space is Private").help_text(),
+            Some("Add a cookie file via AdvancedSettings.cookie_file")
+        );
+        assert_eq!(
+            command_failed("ERROR: GeoBlocked in your country").help_text(),
+            Some("Try using a VPN or proxy")
+        );
+        assert!(DownloadError::RateLimited.help_text().is_some());
+        assert_eq!(
+            command_failed("ERROR: Unable to download webpage").help_text(),
+            None
+        );
+        assert_eq!(DownloadError::Canceled.help_text(), None);
+        assert_eq!(
+            DownloadError::Quota("https://youtube.com/watch?v=1".to_string()).help(),
+            Some(ErrorHelp::WaitForQuota)
+        );
+    }
+}
@@ -1,17 +1,25 @@
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use directories::ProjectDirs;
 use once_cell::sync::Lazy;
-use rusqlite::{params, Connection, Row};
+use parking_lot::Mutex;
+use rusqlite::types::{ToSql, Value};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use tracing::warn;
 use uuid::Uuid;
 
 use crate::config::{AudioFormat, ParseAudioFormatError};
-use crate::download::JobStatus;
+use crate::download::{DownloadRequest, JobStatus};
 use crate::error::HistoryError;
 
+pub use rusqlite::OpenFlags;
+
 static DEFAULT_DB_PATH: Lazy<PathBuf> = Lazy::new(|| {
     #[cfg(target_os = "macos")]
     {
@@ -36,31 +44,445 @@ static DEFAULT_DB_PATH: Lazy<PathBuf> = Lazy::new(|| {
     }
 });
 
+/// Columns added after the initial `downloads` schema, with their definitions.
+/// Adding entry `n` (1-based) is schema migration `n`.
+const ADDED_COLUMNS: &[(&str, &str)] = &[
+    ("description", "TEXT"),
+    ("archived", "INTEGER NOT NULL DEFAULT 0"),
+    ("download_archive", "TEXT"),
+    ("view_count", "INTEGER"),
+    ("like_count", "INTEGER"),
+    ("source_url", "TEXT"),
+    ("owner_id", "TEXT"),
+];
+
+/// Schema version of a fully migrated database; see
+/// [`HistoryRepository::schema_version`].
+pub const SCHEMA_VERSION: u32 = ADDED_COLUMNS.len() as u32;
+
+/// How long [`HistoryRepository::open`] lets SQLite wait for a lock held by
+/// another connection before failing with `SQLITE_BUSY`.
+pub const DEFAULT_BUSY_TIMEOUT_MS: u32 = 5_000;
+
+/// Number of uploaders returned by [`HistoryRepository::get_uploader_stats`].
+pub const TOP_UPLOADERS_LIMIT: usize = 20;
+
+/// File systems on which SQLite's WAL mode does not work reliably.
+#[cfg(target_os = "linux")]
+const NETWORK_FILESYSTEMS: &[&str] = &["nfs", "nfs4", "cifs", "smb3", "smbfs"];
+
+/// Columns read by [`map_entry`], in order.
+const ENTRY_COLUMNS: &str = "id, job_id, url, format, title, uploader, description, status, started_at, ended_at, file_path, error_code, error_message, archived, view_count, like_count, source_url";
+
+/// Filters shared by the history listing queries. Unset filters match
+/// everything.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    include_archived: bool,
+    format: Option<AudioFormat>,
+    status: Option<JobStatus>,
+    url_contains: Option<String>,
+    date_after: Option<DateTime<Utc>>,
+    date_before: Option<DateTime<Utc>>,
+    limit: Option<usize>,
+    offset: usize,
+}
+
+impl HistoryQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also return entries hidden with [`HistoryRepository::mark_archived`].
+    pub fn include_archived(mut self, include: bool) -> Self {
+        self.include_archived = include;
+        self
+    }
+
+    /// Only return downloads in `format`.
+    pub fn format(mut self, format: impl Into<Option<AudioFormat>>) -> Self {
+        self.format = format.into();
+        self
+    }
+
+    /// Only return downloads whose last recorded status is `status`.
+    pub fn status(mut self, status: impl Into<Option<JobStatus>>) -> Self {
+        self.status = status.into();
+        self
+    }
+
+    /// Only return downloads whose URL contains `text` (case-sensitive).
+    pub fn url_contains(mut self, text: impl Into<String>) -> Self {
+        self.url_contains = Some(text.into());
+        self
+    }
+
+    /// Only return downloads started at or after `date`.
+    pub fn date_after(mut self, date: DateTime<Utc>) -> Self {
+        self.date_after = Some(date);
+        self
+    }
+
+    /// Only return downloads started before `date`.
+    pub fn date_before(mut self, date: DateTime<Utc>) -> Self {
+        self.date_before = Some(date);
+        self
+    }
+
+    /// Return at most `limit` entries from [`HistoryRepository::search_advanced`].
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skip the first `offset` matching entries in
+    /// [`HistoryRepository::search_advanced`].
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// The `WHERE` clause for the set filters, or an empty string if there are
+    /// none, along with its named parameters.
+    fn where_clause(&self) -> (String, Vec<(&'static str, Value)>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if !self.include_archived {
+            conditions.push("archived = 0");
+        }
+        if let Some(format) = self.format {
+            conditions.push("format = :format");
+            params.push((":format", Value::Text(format.to_string())));
+        }
+        if let Some(status) = self.status {
+            conditions.push("status = :status");
+            params.push((":status", Value::Text(status.as_str().to_string())));
+        }
+        if let Some(text) = &self.url_contains {
+            // `instr` rather than `LIKE` so `%` and `_` in the text match literally.
+            conditions.push("instr(url, :url_contains) > 0");
+            params.push((":url_contains", Value::Text(text.clone())));
+        }
+        if let Some(date) = self.date_after {
+            conditions.push("started_at >= :date_after");
+            params.push((":date_after", Value::Text(date.to_rfc3339())));
+        }
+        if let Some(date) = self.date_before {
+            conditions.push("started_at < :date_before");
+            params.push((":date_before", Value::Text(date.to_rfc3339())));
+        }
+
+        if conditions.is_empty() {
+            (String::new(), params)
+        } else {
+            (format!("WHERE {}", conditions.join(" AND ")), params)
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct HistoryRepository {
     path: PathBuf,
+    /// Keeps a shared-cache in-memory database alive; SQLite drops it once the
+    /// last connection to it closes.
+    _keep_alive: Option<Arc<Mutex<Connection>>>,
+    flags: OpenFlags,
+    wal: bool,
+    busy_timeout: Duration,
+    /// Recorded with every queued row; see [`HistoryRepository::cleanup_running`].
+    owner: Arc<Owner>,
+}
+
+/// Identifies the repository that queued a row. While the repository is
+/// open it holds a lock on `<db>.owners/<id>.lock`, which tells other
+/// instances sharing the database that its unfinished rows are still live.
+struct Owner {
+    id: Uuid,
+    lock: Option<(PathBuf, fs::File)>,
+}
+
+impl Owner {
+    fn new() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            lock: None,
+        }
+    }
+
+    /// A new owner holding its lock file next to the database at `db_path`.
+    fn locked(db_path: &Path) -> Self {
+        let mut owner = Self::new();
+        let path = owner_lock_path(db_path, &owner.id.to_string());
+        let locked = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::File::create(&path))
+            .and_then(|file| {
+                file.try_lock().map_err(io::Error::from)?;
+                Ok(file)
+            });
+        match locked {
+            Ok(file) => owner.lock = Some((path, file)),
+            Err(error) => warn!("Failed to lock {}: {}", path.display(), error),
+        }
+        owner
+    }
+}
+
+impl Drop for Owner {
+    fn drop(&mut self) {
+        if let Some((path, file)) = self.lock.take() {
+            drop(file);
+            fs::remove_file(path).ok();
+        }
+    }
+}
+
+fn owner_lock_path(db_path: &Path, owner_id: &str) -> PathBuf {
+    let mut dir = db_path.as_os_str().to_owned();
+    dir.push(".owners");
+    PathBuf::from(dir).join(format!("{owner_id}.lock"))
+}
+
+/// Whether the repository `owner_id` still holds its lock file. A missing
+/// file, or one that can be locked, means the owner has exited.
+fn owner_is_alive(db_path: &Path, owner_id: &str) -> bool {
+    let path = owner_lock_path(db_path, owner_id);
+    let Ok(file) = fs::File::open(&path) else {
+        return false;
+    };
+    match file.try_lock() {
+        Err(fs::TryLockError::WouldBlock) => true,
+        Ok(()) => {
+            // Left behind by an owner that crashed.
+            drop(file);
+            fs::remove_file(&path).ok();
+            false
+        }
+        Err(fs::TryLockError::Error(_)) => false,
+    }
 }
 
 impl HistoryRepository {
     pub fn open(path: Option<PathBuf>) -> Result<Self, HistoryError> {
         let resolved = path.unwrap_or_else(|| DEFAULT_DB_PATH.clone());
-        if let Some(parent) = resolved.parent() {
+        Self::open_with_timeout(resolved, DEFAULT_BUSY_TIMEOUT_MS)
+    }
+
+    /// Like [`HistoryRepository::open`], but every connection waits up to
+    /// `busy_timeout_ms` (`PRAGMA busy_timeout`) for locks held by other
+    /// processes, such as a second app instance or a backup tool, instead of
+    /// failing right away.
+    pub fn open_with_timeout(path: PathBuf, busy_timeout_ms: u32) -> Result<Self, HistoryError> {
+        if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).map_err(|source| HistoryError::Io {
                 path: parent.to_path_buf(),
                 source,
             })?;
         }
-        let repo = Self { path: resolved };
+        let wal = wal_supported(&path, true);
+        Self::open_with(
+            path,
+            OpenFlags::default(),
+            wal,
+            Duration::from_millis(busy_timeout_ms.into()),
+        )
+    }
+
+    /// Open the database at `path`, using SQLite's write-ahead log only when
+    /// `wal` is set. WAL mode is also skipped, with a warning, when `path` is
+    /// on a network file system (NFS/SMB), where it is unsupported.
+    pub fn open_with_wal_mode(path: PathBuf, wal: bool) -> Result<Self, HistoryError> {
+        let wal = wal_supported(&path, wal);
+        Self::open_with(path, OpenFlags::default(), wal, default_busy_timeout())
+    }
+
+    /// Where [`HistoryRepository::open`] keeps the database when no path is given.
+    pub fn default_path() -> PathBuf {
+        DEFAULT_DB_PATH.clone()
+    }
+
+    /// Like [`HistoryRepository::open`], then fails the downloads a previous run
+    /// left unfinished; see [`HistoryRepository::cleanup_running`]. Returns the
+    /// repository and how many rows were cleaned up.
+    pub fn open_and_cleanup_running(path: PathBuf) -> Result<(Self, usize), HistoryError> {
+        let repository = Self::open(Some(path))?;
+        let cleaned = repository.cleanup_running()?;
+        Ok((repository, cleaned))
+    }
+
+    /// Mark the unfinished downloads of runs that have exited as failed with
+    /// error code `Interrupted`: jobs do not survive a restart, so their rows
+    /// belong to a run that crashed or was killed. Rows queued by this
+    /// repository, or by another instance that still has the database open,
+    /// are left alone. Returns how many rows were updated.
+    pub fn cleanup_running(&self) -> Result<usize, HistoryError> {
+        let connection = self.writable_connection()?;
+        let unfinished = [
+            JobStatus::Scheduled.as_str(),
+            JobStatus::Queued.as_str(),
+            JobStatus::Running.as_str(),
+        ];
+        let owners = connection
+            .prepare("SELECT DISTINCT owner_id FROM downloads WHERE status IN (?, ?, ?)")
+            .and_then(|mut statement| {
+                statement
+                    .query_map(unfinished, |row| row.get::<_, Option<String>>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .map_err(|source| HistoryError::Query { source })?;
+
+        let own_id = self.owner.id.to_string();
+        let mut cleaned = 0;
+        // Rows from before owners were recorded have none.
+        for owner in owners {
+            if owner
+                .as_deref()
+                .is_some_and(|owner| owner == own_id || owner_is_alive(&self.path, owner))
+            {
+                continue;
+            }
+            cleaned += connection
+                .execute(
+                    "UPDATE downloads
+                     SET status = ?, ended_at = ?, error_code = 'Interrupted',
+                         error_message = 'Application crashed'
+                     WHERE status IN (?, ?, ?) AND owner_id IS ?",
+                    params![
+                        JobStatus::Failed.as_str(),
+                        Utc::now().to_rfc3339(),
+                        unfinished[0],
+                        unfinished[1],
+                        unfinished[2],
+                        owner,
+                    ],
+                )
+                .map_err(|source| HistoryError::Query { source })?;
+        }
+        Ok(cleaned)
+    }
+
+    /// Like [`HistoryRepository::open`], but a corrupt database (or a file that
+    /// is not a database at all) is moved aside to
+    /// `<name>.corrupt.<timestamp>` and replaced with an empty one.
+    pub fn open_or_recover(path: PathBuf) -> Result<Self, HistoryError> {
+        match Self::open(Some(path.clone())) {
+            Err(HistoryError::Initialize { source, .. }) if is_corruption(&source) => {
+                let file_name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "history.db".to_string());
+                let suffix = format!(".corrupt.{}", Utc::now().format("%Y%m%d%H%M%S"));
+                let backup = path.with_file_name(format!("{file_name}{suffix}"));
+                warn!(
+                    "history database {} is corrupt ({source}); moving it to {} and starting over",
+                    path.display(),
+                    backup.display()
+                );
+                fs::rename(&path, &backup).map_err(|source| HistoryError::Io {
+                    path: path.clone(),
+                    source,
+                })?;
+                // The journal files belong to the corrupt database.
+                for sidecar in ["-wal", "-shm"] {
+                    let journal = path.with_file_name(format!("{file_name}{sidecar}"));
+                    if journal.exists() {
+                        let moved = path.with_file_name(format!("{file_name}{sidecar}{suffix}"));
+                        fs::rename(&journal, moved).map_err(|source| HistoryError::Io {
+                            path: journal.clone(),
+                            source,
+                        })?;
+                    }
+                }
+                Self::open(Some(path))
+            }
+            result => result,
+        }
+    }
+
+    /// Open the database at `path` with explicit SQLite flags, e.g. for URI
+    /// paths or custom SQLite builds. Unless `flags` is read-only, the schema
+    /// is created and migrated as with [`HistoryRepository::open`].
+    pub fn open_with_flags(path: PathBuf, flags: OpenFlags) -> Result<Self, HistoryError> {
+        Self::open_with(path, flags, true, default_busy_timeout())
+    }
+
+    fn open_with(
+        path: PathBuf,
+        flags: OpenFlags,
+        wal: bool,
+        busy_timeout: Duration,
+    ) -> Result<Self, HistoryError> {
+        let read_only = flags.contains(OpenFlags::SQLITE_OPEN_READ_ONLY);
+        let owner = if read_only {
+            Owner::new()
+        } else {
+            Owner::locked(&path)
+        };
+        let repo = Self {
+            path,
+            _keep_alive: None,
+            flags,
+            wal,
+            busy_timeout,
+            owner: Arc::new(owner),
+        };
+        if repo.is_read_only() {
+            repo.connection()?;
+        } else {
+            repo.initialize()?;
+        }
+        Ok(repo)
+    }
+
+    /// Open a history that lives only in memory and is discarded when the last
+    /// clone of the repository is dropped.
+    pub fn open_in_memory() -> Result<Self, HistoryError> {
+        let path = PathBuf::from(format!(
+            "file:space-downloader-{}?mode=memory&cache=shared",
+            Uuid::new_v4()
+        ));
+        let keep_alive = Connection::open(&path).map_err(|source| HistoryError::Initialize {
+            path: path.clone(),
+            source,
+        })?;
+        let repo = Self {
+            path,
+            _keep_alive: Some(Arc::new(Mutex::new(keep_alive))),
+            flags: OpenFlags::default(),
+            wal: false,
+            busy_timeout: default_busy_timeout(),
+            owner: Arc::new(Owner::new()),
+        };
         repo.initialize()?;
         Ok(repo)
     }
 
+    /// Open an existing history database for inspection only. The file is
+    /// never created or migrated, and every mutation returns
+    /// [`HistoryError::ReadOnly`].
+    pub fn open_read_only(path: &Path) -> Result<Self, HistoryError> {
+        Self::open_with_flags(
+            path.to_path_buf(),
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX
+                | OpenFlags::SQLITE_OPEN_URI,
+        )
+    }
+
     fn initialize(&self) -> Result<(), HistoryError> {
         let connection = self.connection()?;
+        if self.wal {
+            connection
+                .execute_batch("PRAGMA journal_mode=WAL;")
+                .map_err(|source| HistoryError::Initialize {
+                    path: self.path.clone(),
+                    source,
+                })?;
+        }
         connection
             .execute_batch(
-                "PRAGMA journal_mode=WAL;
-                 CREATE TABLE IF NOT EXISTS downloads (
+                "CREATE TABLE IF NOT EXISTS downloads (
                      id INTEGER PRIMARY KEY AUTOINCREMENT,
                      job_id TEXT NOT NULL,
                      url TEXT NOT NULL,
@@ -74,31 +496,91 @@ impl HistoryRepository {
                      error_code TEXT,
                      error_message TEXT
                  );
-                 CREATE INDEX IF NOT EXISTS idx_downloads_job_id ON downloads(job_id);",
+                 CREATE INDEX IF NOT EXISTS idx_downloads_job_id ON downloads(job_id);
+                 CREATE INDEX IF NOT EXISTS idx_downloads_file_path ON downloads(file_path);",
             )
             .map_err(|source| HistoryError::Initialize {
                 path: self.path.clone(),
                 source,
             })?;
+        self.migrate(&connection)?;
         Ok(())
     }
 
+    /// Add columns introduced after the initial schema to databases created by
+    /// older versions.
+    fn migrate(&self, connection: &Connection) -> Result<(), HistoryError> {
+        let initialize_error = |source| HistoryError::Initialize {
+            path: self.path.clone(),
+            source,
+        };
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS schema_migrations (version INTEGER NOT NULL);",
+            )
+            .map_err(initialize_error)?;
+        let version = read_schema_version(connection).map_err(initialize_error)?;
+        if version >= SCHEMA_VERSION {
+            return Ok(());
+        }
+
+        // Databases created before versions were tracked report 0 but may
+        // already have some of the columns, so each one is still checked.
+        for (column, definition) in ADDED_COLUMNS.iter().skip(version as usize) {
+            let exists = connection
+                .prepare("SELECT 1 FROM pragma_table_info('downloads') WHERE name = ?")
+                .and_then(|mut statement| statement.exists([column]))
+                .map_err(|source| HistoryError::Initialize {
+                    path: self.path.clone(),
+                    source,
+                })?;
+            if !exists {
+                connection
+                    .execute(
+                        &format!("ALTER TABLE downloads ADD COLUMN {column} {definition}"),
+                        [],
+                    )
+                    .map_err(|source| HistoryError::Initialize {
+                        path: self.path.clone(),
+                        source,
+                    })?;
+            }
+        }
+        connection
+            .execute_batch(&format!(
+                "BEGIN;
+                 DELETE FROM schema_migrations;
+                 INSERT INTO schema_migrations (version) VALUES ({SCHEMA_VERSION});
+                 COMMIT;"
+            ))
+            .map_err(initialize_error)
+    }
+
+    /// The migration the database is on, [`SCHEMA_VERSION`] once opened
+    /// writable. `0` for databases no version of this crate has migrated yet.
+    pub fn schema_version(&self) -> Result<u32, HistoryError> {
+        let connection = self.connection()?;
+        read_schema_version(&connection).map_err(|source| HistoryError::Query { source })
+    }
+
     pub fn record_queued(
         &self,
         job_id: Uuid,
         url: &str,
         format: AudioFormat,
     ) -> Result<i64, HistoryError> {
-        let connection = self.connection()?;
+        let connection = self.writable_connection()?;
         connection
             .execute(
-                "INSERT INTO downloads (job_id, url, format, status, started_at) VALUES (?, ?, ?, ?, ?)",
+                "INSERT INTO downloads (job_id, url, format, status, started_at, owner_id)
+                 VALUES (?, ?, ?, ?, ?, ?)",
                 params![
                     job_id.to_string(),
                     url,
                     format.to_string(),
                     JobStatus::Queued.as_str(),
                     Utc::now().to_rfc3339(),
+                    self.owner.id.to_string(),
                 ],
             )
             .map_err(|source| HistoryError::Query { source })?;
@@ -110,12 +592,72 @@ impl HistoryRepository {
         job_id: Uuid,
         title: Option<&str>,
         uploader: Option<&str>,
+        description: Option<&str>,
     ) -> Result<(), HistoryError> {
-        let connection = self.connection()?;
+        let connection = self.writable_connection()?;
+        connection
+            .execute(
+                "UPDATE downloads
+                 SET title = COALESCE(?, title),
+                     uploader = COALESCE(?, uploader),
+                     description = COALESCE(?, description)
+                 WHERE job_id = ?",
+                params![title, uploader, description, job_id.to_string()],
+            )
+            .map_err(|source| HistoryError::Query { source })?;
+        Ok(())
+    }
+
+    /// Store the view and like counts reported in the info JSON. Counts that
+    /// are `None` leave the stored value unchanged.
+    pub fn update_counts(
+        &self,
+        job_id: Uuid,
+        view_count: Option<u64>,
+        like_count: Option<u64>,
+    ) -> Result<(), HistoryError> {
+        let to_sql = |count: u64| i64::try_from(count).unwrap_or(i64::MAX);
+        let connection = self.writable_connection()?;
+        connection
+            .execute(
+                "UPDATE downloads
+                 SET view_count = COALESCE(?, view_count),
+                     like_count = COALESCE(?, like_count)
+                 WHERE job_id = ?",
+                params![
+                    view_count.map(to_sql),
+                    like_count.map(to_sql),
+                    job_id.to_string()
+                ],
+            )
+            .map_err(|source| HistoryError::Query { source })?;
+        Ok(())
+    }
+
+    /// Record the canonical URL yt-dlp resolved the submitted URL to.
+    pub fn update_source_url(&self, job_id: Uuid, source_url: &str) -> Result<(), HistoryError> {
+        let connection = self.writable_connection()?;
+        connection
+            .execute(
+                "UPDATE downloads SET source_url = ? WHERE job_id = ?",
+                params![source_url, job_id.to_string()],
+            )
+            .map_err(|source| HistoryError::Query { source })?;
+        Ok(())
+    }
+
+    /// Record a queued job's edited URL and format.
+    pub fn update_request(
+        &self,
+        job_id: Uuid,
+        url: &str,
+        format: AudioFormat,
+    ) -> Result<(), HistoryError> {
+        let connection = self.writable_connection()?;
         connection
             .execute(
-                "UPDATE downloads SET title = COALESCE(?, title), uploader = COALESCE(?, uploader) WHERE job_id = ?",
-                params![title, uploader, job_id.to_string()],
+                "UPDATE downloads SET url = ?, format = ? WHERE job_id = ?",
+                params![url, format.to_string(), job_id.to_string()],
             )
             .map_err(|source| HistoryError::Query { source })?;
         Ok(())
@@ -129,7 +671,7 @@ impl HistoryRepository {
         error_code: Option<&str>,
         error_message: Option<&str>,
     ) -> Result<(), HistoryError> {
-        let connection = self.connection()?;
+        let connection = self.writable_connection()?;
         connection
             .execute(
                 "UPDATE downloads
@@ -152,19 +694,308 @@ impl HistoryRepository {
         Ok(())
     }
 
-    pub fn recent(&self, limit: usize) -> Result<Vec<DownloadHistoryEntry>, HistoryError> {
+    /// Like [`HistoryRepository::mark_completed`] for many jobs at once, e.g.
+    /// to fail jobs interrupted by a crash. All rows are updated in one
+    /// transaction, so either every update is applied or none is. Returns the
+    /// number of rows updated; unknown job IDs are ignored.
+    pub fn batch_mark_completed(
+        &self,
+        entries: &[(Uuid, JobStatus, Option<PathBuf>)],
+    ) -> Result<usize, HistoryError> {
+        let mut connection = self.writable_connection()?;
+        let transaction = connection
+            .transaction()
+            .map_err(|source| HistoryError::Query { source })?;
+        let now = Utc::now().to_rfc3339();
+        let mut updated = 0;
+        {
+            let mut statement = transaction
+                .prepare(
+                    "UPDATE downloads
+                     SET status = ?, ended_at = ?, file_path = ?
+                     WHERE job_id = ?",
+                )
+                .map_err(|source| HistoryError::Query { source })?;
+            for (job_id, status, file_path) in entries {
+                // Returning early drops the transaction, which rolls it back.
+                updated += statement
+                    .execute(params![
+                        status.as_str(),
+                        now,
+                        file_path.as_ref().map(|p| p.to_string_lossy().to_string()),
+                        job_id.to_string(),
+                    ])
+                    .map_err(|source| HistoryError::Query { source })?;
+            }
+        }
+        transaction
+            .commit()
+            .map_err(|source| HistoryError::Query { source })?;
+        Ok(updated)
+    }
+
+    /// Remember the yt-dlp `--download-archive` file a job was recorded in.
+    pub fn set_download_archive(&self, job_id: Uuid, archive: &Path) -> Result<(), HistoryError> {
+        let connection = self.writable_connection()?;
+        connection
+            .execute(
+                "UPDATE downloads SET download_archive = ? WHERE job_id = ?",
+                params![archive.to_string_lossy().to_string(), job_id.to_string()],
+            )
+            .map_err(|source| HistoryError::Query { source })?;
+        Ok(())
+    }
+
+    /// Remove a job's `<extractor> <id>` line from the yt-dlp download archive
+    /// it was recorded in, so yt-dlp will download it again. Returns whether a
+    /// line was removed; jobs without an archive or with an unrecognized URL
+    /// leave the file untouched.
+    pub fn remove_from_archive(&self, job_id: Uuid) -> Result<bool, HistoryError> {
+        let connection = self.writable_connection()?;
+        let row = connection
+            .query_row(
+                "SELECT url, download_archive FROM downloads
+                 WHERE job_id = ? ORDER BY id DESC LIMIT 1",
+                params![job_id.to_string()],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
+            )
+            .optional()
+            .map_err(|source| HistoryError::Query { source })?;
+        let Some((url, Some(archive))) = row else {
+            return Ok(false);
+        };
+        let Some(key) = archive_entry_key(&url) else {
+            return Ok(false);
+        };
+
+        let archive = PathBuf::from(archive);
+        let content = match fs::read_to_string(&archive) {
+            Ok(content) => content,
+            Err(source) if source.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(source) => {
+                return Err(HistoryError::Io {
+                    path: archive,
+                    source,
+                })
+            }
+        };
+        let kept: Vec<&str> = content
+            .lines()
+            .filter(|line| !line.trim().eq_ignore_ascii_case(&key))
+            .collect();
+        if kept.len() == content.lines().count() {
+            return Ok(false);
+        }
+
+        let mut updated = kept.join("\n");
+        if !updated.is_empty() {
+            updated.push('\n');
+        }
+        fs::write(&archive, updated).map_err(|source| HistoryError::Io {
+            path: archive,
+            source,
+        })?;
+        Ok(true)
+    }
+
+    /// Hide an entry from the default listings without deleting it.
+    pub fn mark_archived(&self, job_id: Uuid, archived: bool) -> Result<(), HistoryError> {
+        let connection = self.writable_connection()?;
+        connection
+            .execute(
+                "UPDATE downloads SET archived = ? WHERE job_id = ?",
+                params![archived, job_id.to_string()],
+            )
+            .map_err(|source| HistoryError::Query { source })?;
+        Ok(())
+    }
+
+    pub fn update_file_path(&self, job_id: Uuid, new_path: &Path) -> Result<(), HistoryError> {
+        let connection = self.writable_connection()?;
+        connection
+            .execute(
+                "UPDATE downloads SET file_path = ? WHERE job_id = ?",
+                params![new_path.to_string_lossy().to_string(), job_id.to_string()],
+            )
+            .map_err(|source| HistoryError::Query { source })?;
+        Ok(())
+    }
+
+    /// Return succeeded jobs whose recorded file no longer exists on disk.
+    pub fn verify_file_paths(&self) -> Result<Vec<(Uuid, PathBuf)>, HistoryError> {
         let connection = self.connection()?;
         let mut statement = connection
             .prepare(
-                "SELECT id, job_id, url, format, title, uploader, status, started_at, ended_at, file_path, error_code, error_message
-                 FROM downloads
-                 ORDER BY started_at DESC
-                 LIMIT ?",
+                "SELECT job_id, file_path FROM downloads
+                 WHERE status = ? AND file_path IS NOT NULL",
+            )
+            .map_err(|source| HistoryError::Query { source })?;
+
+        let rows = statement
+            .query_map(params![JobStatus::Succeeded.as_str()], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|source| HistoryError::Query { source })?;
+
+        let mut missing = Vec::new();
+        for row in rows {
+            let (job_id, file_path) = row.map_err(|source| HistoryError::Query { source })?;
+            let file_path = PathBuf::from(file_path);
+            if file_path.exists() {
+                continue;
+            }
+            let job_id = job_id.parse().map_err(|err| HistoryError::Query {
+                source: rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    Box::new(err),
+                ),
+            })?;
+            missing.push((job_id, file_path));
+        }
+
+        Ok(missing)
+    }
+
+    /// Import entries from a yt-dlp `--download-archive` file, where each line
+    /// is `<extractor> <id>`. Entries are recorded as succeeded downloads with
+    /// a reconstructed URL; lines from unknown extractors are skipped.
+    /// Returns the number of imported rows.
+    pub fn import_from_yt_dlp_archive(&self, path: &Path) -> Result<usize, HistoryError> {
+        let content = fs::read_to_string(path).map_err(|source| HistoryError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let mut connection = self.writable_connection()?;
+        let transaction = connection
+            .transaction()
+            .map_err(|source| HistoryError::Query { source })?;
+        let now = Utc::now().to_rfc3339();
+        let mut imported = 0;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((extractor, id)) = line.split_once(char::is_whitespace) else {
+                warn!("skipping malformed download archive line: {line}");
+                continue;
+            };
+            let Some(url) = archive_entry_url(extractor, id.trim()) else {
+                warn!("skipping download archive entry from unknown extractor {extractor}");
+                continue;
+            };
+
+            transaction
+                .execute(
+                    "INSERT INTO downloads
+                         (job_id, url, format, status, started_at, ended_at, download_archive)
+                     VALUES (?, ?, ?, ?, ?, ?, ?)",
+                    params![
+                        Uuid::new_v4().to_string(),
+                        url,
+                        AudioFormat::default().to_string(),
+                        JobStatus::Succeeded.as_str(),
+                        now,
+                        now,
+                        path.to_string_lossy().to_string(),
+                    ],
+                )
+                .map_err(|source| HistoryError::Query { source })?;
+            imported += 1;
+        }
+
+        transaction
+            .commit()
+            .map_err(|source| HistoryError::Query { source })?;
+        Ok(imported)
+    }
+
+    pub fn recent(&self, limit: usize) -> Result<Vec<DownloadHistoryEntry>, HistoryError> {
+        self.recent_with(limit, &HistoryQuery::default())
+    }
+
+    pub fn recent_with(
+        &self,
+        limit: usize,
+        query: &HistoryQuery,
+    ) -> Result<Vec<DownloadHistoryEntry>, HistoryError> {
+        let query = HistoryQuery {
+            limit: Some(limit),
+            offset: 0,
+            ..query.clone()
+        };
+        self.select_entries(&self.connection()?, &query)
+    }
+
+    /// Fetch one page of history, newest first, along with the total row count.
+    /// `page` is zero-based.
+    pub fn page(
+        &self,
+        page: usize,
+        per_page: usize,
+    ) -> Result<(Vec<DownloadHistoryEntry>, usize), HistoryError> {
+        self.page_with(page, per_page, &HistoryQuery::default())
+    }
+
+    pub fn page_with(
+        &self,
+        page: usize,
+        per_page: usize,
+        query: &HistoryQuery,
+    ) -> Result<(Vec<DownloadHistoryEntry>, usize), HistoryError> {
+        self.search_advanced(&HistoryQuery {
+            limit: Some(per_page),
+            offset: page.saturating_mul(per_page),
+            ..query.clone()
+        })
+    }
+
+    /// Entries matching every filter set on `query`, newest first, along with
+    /// the number of matching entries before `limit` and `offset` are applied.
+    pub fn search_advanced(
+        &self,
+        query: &HistoryQuery,
+    ) -> Result<(Vec<DownloadHistoryEntry>, usize), HistoryError> {
+        let connection = self.connection()?;
+        let (where_clause, params) = query.where_clause();
+        let total_count: i64 = connection
+            .query_row(
+                &format!("SELECT COUNT(*) FROM downloads {where_clause}"),
+                &*named_params(&params),
+                |row| row.get(0),
             )
             .map_err(|source| HistoryError::Query { source })?;
 
+        let entries = self.select_entries(&connection, query)?;
+        Ok((entries, total_count as usize))
+    }
+
+    fn select_entries(
+        &self,
+        connection: &Connection,
+        query: &HistoryQuery,
+    ) -> Result<Vec<DownloadHistoryEntry>, HistoryError> {
+        let (where_clause, mut params) = query.where_clause();
+        // SQLite treats a negative LIMIT as no limit.
+        let limit = query.limit.map_or(-1, |limit| limit as i64);
+        params.push((":limit", Value::Integer(limit)));
+        params.push((":offset", Value::Integer(query.offset as i64)));
+
+        let mut statement = connection
+            .prepare(&format!(
+                "SELECT {ENTRY_COLUMNS}
+                 FROM downloads
+                 {where_clause}
+                 ORDER BY started_at DESC, id DESC
+                 LIMIT :limit OFFSET :offset"
+            ))
+            .map_err(|source| HistoryError::Query { source })?;
+
         let mut rows = statement
-            .query(params![limit as i64])
+            .query(&*named_params(&params))
             .map_err(|source| HistoryError::Query { source })?;
 
         let mut entries = Vec::new();
@@ -178,34 +1009,451 @@ impl HistoryRepository {
         Ok(entries)
     }
 
-    fn connection(&self) -> Result<Connection, HistoryError> {
-        Connection::open(&self.path).map_err(|source| HistoryError::Initialize {
-            path: self.path.clone(),
-            source,
-        })
+    pub fn recent_by_format(
+        &self,
+        format: AudioFormat,
+        limit: usize,
+    ) -> Result<Vec<DownloadHistoryEntry>, HistoryError> {
+        self.recent_with(limit, &HistoryQuery::new().format(Some(format)))
     }
-}
 
-fn map_entry(row: &Row<'_>) -> Result<DownloadHistoryEntry, HistoryError> {
-    let started_at: String = row
-        .get("started_at")
-        .map_err(|source| HistoryError::Query { source })?;
-    let started_at = DateTime::parse_from_rfc3339(&started_at)
-        .map_err(|source| HistoryError::Query {
-            source: rusqlite::Error::FromSqlConversionFailure(
-                0,
-                rusqlite::types::Type::Text,
-                Box::new(source),
-            ),
-        })?
-        .with_timezone(&Utc);
+    /// Formats that appear in the history, for populating a filter.
+    pub fn distinct_formats(&self) -> Result<Vec<AudioFormat>, HistoryError> {
+        let connection = self.connection()?;
+        let mut statement = connection
+            .prepare("SELECT DISTINCT format FROM downloads ORDER BY format")
+            .map_err(|source| HistoryError::Query { source })?;
+        let rows = statement
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|source| HistoryError::Query { source })?;
 
-    let ended_at: Option<String> = row
-        .get("ended_at")
-        .map_err(|source| HistoryError::Query { source })?;
-    let ended_at = match ended_at {
-        Some(value) => Some(
-            DateTime::parse_from_rfc3339(&value)
+        let mut formats = Vec::new();
+        for row in rows {
+            let text = row.map_err(|source| HistoryError::Query { source })?;
+            formats.push(parse_format(&text)?);
+        }
+        Ok(formats)
+    }
+
+    /// URLs downloaded successfully more than once, with how many times,
+    /// most-duplicated first.
+    pub fn duplicate_downloads(&self) -> Result<Vec<(String, usize)>, HistoryError> {
+        let connection = self.connection()?;
+        let mut statement = connection
+            .prepare(
+                "SELECT url, COUNT(*) AS cnt FROM downloads
+                 WHERE status = ?
+                 GROUP BY url
+                 HAVING cnt > 1
+                 ORDER BY cnt DESC, url",
+            )
+            .map_err(|source| HistoryError::Query { source })?;
+
+        let rows = statement
+            .query_map(params![JobStatus::Succeeded.as_str()], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })
+            .map_err(|source| HistoryError::Query { source })?;
+
+        rows.collect::<Result<_, _>>()
+            .map_err(|source| HistoryError::Query { source })
+    }
+
+    /// Every successful download of `url`, newest first.
+    pub fn succeeded_downloads_of(
+        &self,
+        url: &str,
+    ) -> Result<Vec<DownloadHistoryEntry>, HistoryError> {
+        let connection = self.connection()?;
+        let mut statement = connection
+            .prepare(&format!(
+                "SELECT {ENTRY_COLUMNS}
+                 FROM downloads
+                 WHERE url = ? AND status = ?
+                 ORDER BY started_at DESC, id DESC"
+            ))
+            .map_err(|source| HistoryError::Query { source })?;
+
+        let mut rows = statement
+            .query(params![url, JobStatus::Succeeded.as_str()])
+            .map_err(|source| HistoryError::Query { source })?;
+
+        let mut entries = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .map_err(|source| HistoryError::Query { source })?
+        {
+            entries.push(map_entry(row)?);
+        }
+
+        Ok(entries)
+    }
+
+    /// The most recent entry whose recorded file is `path`.
+    ///
+    /// Paths are first compared as given; failing that, both sides are
+    /// canonicalized so trailing separators, `..` components, symlinks and
+    /// (on Windows) letter case do not prevent a match. Canonicalization only
+    /// works for files that still exist.
+    pub fn find_by_file_path(
+        &self,
+        path: &Path,
+    ) -> Result<Option<DownloadHistoryEntry>, HistoryError> {
+        let connection = self.connection()?;
+        let mut statement = connection
+            .prepare(&format!(
+                "SELECT {ENTRY_COLUMNS}
+                 FROM downloads
+                 WHERE file_path = ?
+                 ORDER BY started_at DESC, id DESC
+                 LIMIT 1"
+            ))
+            .map_err(|source| HistoryError::Query { source })?;
+        let mut rows = statement
+            .query(params![path.to_string_lossy().to_string()])
+            .map_err(|source| HistoryError::Query { source })?;
+        if let Some(row) = rows
+            .next()
+            .map_err(|source| HistoryError::Query { source })?
+        {
+            return map_entry(row).map(Some);
+        }
+
+        let Ok(canonical) = fs::canonicalize(path) else {
+            return Ok(None);
+        };
+        let mut statement = connection
+            .prepare(&format!(
+                "SELECT {ENTRY_COLUMNS}
+                 FROM downloads
+                 WHERE file_path IS NOT NULL
+                 ORDER BY started_at DESC, id DESC"
+            ))
+            .map_err(|source| HistoryError::Query { source })?;
+        let mut rows = statement
+            .query([])
+            .map_err(|source| HistoryError::Query { source })?;
+        while let Some(row) = rows
+            .next()
+            .map_err(|source| HistoryError::Query { source })?
+        {
+            let entry = map_entry(row)?;
+            let matches = entry
+                .file_path
+                .as_deref()
+                .and_then(|stored| fs::canonicalize(stored).ok())
+                .is_some_and(|stored| stored == canonical);
+            if matches {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whether any entry was recorded for `job_id`.
+    pub fn entry_exists(&self, job_id: Uuid) -> Result<bool, HistoryError> {
+        let connection = self.connection()?;
+        connection
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM downloads WHERE job_id = ? LIMIT 1)",
+                params![job_id.to_string()],
+                |row| row.get(0),
+            )
+            .map_err(|source| HistoryError::Query { source })
+    }
+
+    /// Whether `url` has been downloaded successfully before.
+    pub fn url_was_downloaded(&self, url: &str) -> Result<bool, HistoryError> {
+        let connection = self.connection()?;
+        connection
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM downloads WHERE url = ? AND status = ? LIMIT 1)",
+                params![url, JobStatus::Succeeded.as_str()],
+                |row| row.get(0),
+            )
+            .map_err(|source| HistoryError::Query { source })
+    }
+
+    /// Write an extended M3U playlist of every successful download whose file
+    /// still exists, newest first, for media players such as VLC. Durations
+    /// are not recorded, so every track is written as `-1` (unknown).
+    pub fn export_m3u(&self, path: &Path) -> Result<(), HistoryError> {
+        let query = HistoryQuery::new()
+            .include_archived(true)
+            .status(JobStatus::Succeeded);
+        let entries = self.select_entries(&self.connection()?, &query)?;
+
+        let mut playlist = String::from("#EXTM3U\n");
+        for entry in entries {
+            let Some(file_path) = entry.file_path.filter(|file| file.is_file()) else {
+                continue;
+            };
+            let file_path = std::path::absolute(&file_path).unwrap_or(file_path);
+            let title = entry
+                .title
+                .or_else(|| {
+                    file_path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                })
+                .unwrap_or(entry.url);
+            // A line break would end the #EXTINF line early.
+            let title = title.replace(['\r', '\n'], " ");
+            playlist.push_str(&format!("#EXTINF:-1,{title}\n{}\n", file_path.display()));
+        }
+
+        fs::write(path, playlist).map_err(|source| HistoryError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Uploaders with the most successful downloads, with how many, at most
+    /// [`TOP_UPLOADERS_LIMIT`] of them.
+    pub fn get_uploader_stats(&self) -> Result<Vec<(String, usize)>, HistoryError> {
+        let connection = self.connection()?;
+        let mut statement = connection
+            .prepare(
+                "SELECT uploader, COUNT(*) AS cnt FROM downloads
+                 WHERE uploader IS NOT NULL AND status = ?
+                 GROUP BY uploader
+                 ORDER BY cnt DESC, uploader
+                 LIMIT ?",
+            )
+            .map_err(|source| HistoryError::Query { source })?;
+
+        let rows = statement
+            .query_map(
+                params![JobStatus::Succeeded.as_str(), TOP_UPLOADERS_LIMIT as i64],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize)),
+            )
+            .map_err(|source| HistoryError::Query { source })?;
+
+        rows.collect::<Result<_, _>>()
+            .map_err(|source| HistoryError::Query { source })
+    }
+
+    pub fn stats(&self) -> Result<HistoryStats, HistoryError> {
+        Ok(HistoryStats {
+            total: self.count()?,
+            archived: self.archived_count()?,
+            duplicate_count: self.duplicate_downloads()?.len(),
+            top_uploaders: self.get_uploader_stats()?,
+        })
+    }
+
+    /// Number of entries that are not archived.
+    pub fn count(&self) -> Result<usize, HistoryError> {
+        self.count_where_archived(false)
+    }
+
+    pub fn archived_count(&self) -> Result<usize, HistoryError> {
+        self.count_where_archived(true)
+    }
+
+    fn count_where_archived(&self, archived: bool) -> Result<usize, HistoryError> {
+        let connection = self.connection()?;
+        let count: i64 = connection
+            .query_row(
+                "SELECT COUNT(*) FROM downloads WHERE archived = ?",
+                params![archived],
+                |row| row.get(0),
+            )
+            .map_err(|source| HistoryError::Query { source })?;
+        Ok(count as usize)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.flags.contains(OpenFlags::SQLITE_OPEN_READ_ONLY)
+    }
+
+    fn connection(&self) -> Result<Connection, HistoryError> {
+        Connection::open_with_flags(&self.path, self.flags)
+            .and_then(|connection| {
+                connection.busy_timeout(self.busy_timeout)?;
+                Ok(connection)
+            })
+            .map_err(|source| HistoryError::Initialize {
+                path: self.path.clone(),
+                source,
+            })
+    }
+
+    fn writable_connection(&self) -> Result<Connection, HistoryError> {
+        if self.is_read_only() {
+            return Err(HistoryError::ReadOnly);
+        }
+        self.connection()
+    }
+}
+
+fn read_schema_version(connection: &Connection) -> rusqlite::Result<u32> {
+    let tracked: bool = connection.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master
+         WHERE type = 'table' AND name = 'schema_migrations')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !tracked {
+        return Ok(0);
+    }
+    connection.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )
+}
+
+fn default_busy_timeout() -> Duration {
+    Duration::from_millis(DEFAULT_BUSY_TIMEOUT_MS.into())
+}
+
+/// `wal`, unless `path` is on a network file system, where WAL mode is
+/// unsupported.
+fn wal_supported(path: &Path, wal: bool) -> bool {
+    if wal && is_network_filesystem(path) {
+        warn!(
+            "history database {} is on a network file system; not using WAL mode",
+            path.display()
+        );
+        false
+    } else {
+        wal
+    }
+}
+
+/// Whether `path` lives on a network share where WAL mode is unsupported.
+fn is_network_filesystem(path: &Path) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let (Ok(dir), Ok(mounts)) = (dir.canonicalize(), fs::read_to_string("/proc/self/mounts"))
+        else {
+            return false;
+        };
+        mount_fs_type(&mounts, &dir).is_some_and(|fs_type| NETWORK_FILESYSTEMS.contains(&fs_type))
+    }
+    #[cfg(target_os = "windows")]
+    {
+        path.to_string_lossy().starts_with(r"\\")
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+/// File system type of the innermost mount containing `dir`, read from the
+/// contents of `/proc/self/mounts`.
+#[cfg(target_os = "linux")]
+fn mount_fs_type<'a>(mounts: &'a str, dir: &Path) -> Option<&'a str> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?;
+            let fs_type = fields.next()?;
+            Some((mount_point, fs_type))
+        })
+        .filter(|(mount_point, _)| dir.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.len())
+        .map(|(_, fs_type)| fs_type)
+}
+
+/// Whether SQLite rejected the file as damaged or as not being a database.
+fn is_corruption(error: &rusqlite::Error) -> bool {
+    matches!(
+        error,
+        rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error {
+                code: rusqlite::ErrorCode::DatabaseCorrupt | rusqlite::ErrorCode::NotADatabase,
+                ..
+            },
+            _,
+        )
+    )
+}
+
+fn archive_entry_url(extractor: &str, id: &str) -> Option<String> {
+    match extractor.to_ascii_lowercase().as_str() {
+        "youtube" => Some(format!("https://www.youtube.com/watch?v={id}")),
+        "vimeo" => Some(format!("https://vimeo.com/{id}")),
+        "twitterspaces" => Some(format!("https://x.com/i/spaces/{id}")),
+        _ => None,
+    }
+}
+
+/// The `<extractor> <id>` line yt-dlp writes to its download archive for
+/// `url`; the inverse of [`archive_entry_url`].
+fn archive_entry_key(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    let host = parsed.host_str()?.trim_start_matches("www.");
+    let mut segments = parsed
+        .path_segments()?
+        .filter(|segment| !segment.is_empty());
+    let (extractor, id) = match host {
+        "youtube.com" | "m.youtube.com" => (
+            "youtube",
+            parsed
+                .query_pairs()
+                .find(|(key, _)| key == "v")
+                .map(|(_, id)| id.into_owned())?,
+        ),
+        "youtu.be" => ("youtube", segments.next()?.to_string()),
+        "vimeo.com" => ("vimeo", segments.next()?.to_string()),
+        "x.com" | "twitter.com" => match (segments.next(), segments.next(), segments.next()) {
+            (Some("i"), Some("spaces"), Some(id)) => ("twitterspaces", id.to_string()),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(format!("{extractor} {id}"))
+}
+
+fn parse_format(text: &str) -> Result<AudioFormat, HistoryError> {
+    AudioFormat::from_str(text).map_err(|ParseAudioFormatError(value)| HistoryError::Query {
+        source: rusqlite::Error::FromSqlConversionFailure(
+            0,
+            rusqlite::types::Type::Text,
+            Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid audio format {value}"),
+            )),
+        ),
+    })
+}
+
+fn named_params<'a>(params: &'a [(&'static str, Value)]) -> Vec<(&'static str, &'a dyn ToSql)> {
+    params
+        .iter()
+        .map(|(name, value)| (*name, value as &dyn ToSql))
+        .collect()
+}
+
+fn map_entry(row: &Row<'_>) -> Result<DownloadHistoryEntry, HistoryError> {
+    let started_at: String = row
+        .get("started_at")
+        .map_err(|source| HistoryError::Query { source })?;
+    let started_at = DateTime::parse_from_rfc3339(&started_at)
+        .map_err(|source| HistoryError::Query {
+            source: rusqlite::Error::FromSqlConversionFailure(
+                0,
+                rusqlite::types::Type::Text,
+                Box::new(source),
+            ),
+        })?
+        .with_timezone(&Utc);
+
+    let ended_at: Option<String> = row
+        .get("ended_at")
+        .map_err(|source| HistoryError::Query { source })?;
+    let ended_at = match ended_at {
+        Some(value) => Some(
+            DateTime::parse_from_rfc3339(&value)
                 .map_err(|source| HistoryError::Query {
                     source: rusqlite::Error::FromSqlConversionFailure(
                         0,
@@ -221,18 +1469,7 @@ fn map_entry(row: &Row<'_>) -> Result<DownloadHistoryEntry, HistoryError> {
     let format_text: String = row
         .get("format")
         .map_err(|source| HistoryError::Query { source })?;
-    let format = AudioFormat::from_str(&format_text).map_err(|ParseAudioFormatError(value)| {
-        HistoryError::Query {
-            source: rusqlite::Error::FromSqlConversionFailure(
-                0,
-                rusqlite::types::Type::Text,
-                Box::new(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("invalid audio format {value}"),
-                )),
-            ),
-        }
-    })?;
+    let format = parse_format(&format_text)?;
 
     Ok(DownloadHistoryEntry {
         id: row
@@ -259,6 +1496,9 @@ fn map_entry(row: &Row<'_>) -> Result<DownloadHistoryEntry, HistoryError> {
         uploader: row
             .get("uploader")
             .map_err(|source| HistoryError::Query { source })?,
+        description: row
+            .get("description")
+            .map_err(|source| HistoryError::Query { source })?,
         status: JobStatus::parse_status(
             &row.get::<_, String>("status")
                 .map_err(|source| HistoryError::Query { source })?,
@@ -275,9 +1515,35 @@ fn map_entry(row: &Row<'_>) -> Result<DownloadHistoryEntry, HistoryError> {
         error_message: row
             .get("error_message")
             .map_err(|source| HistoryError::Query { source })?,
+        archived: row
+            .get("archived")
+            .map_err(|source| HistoryError::Query { source })?,
+        view_count: get_count(row, "view_count")?,
+        like_count: get_count(row, "like_count")?,
+        source_url: row
+            .get("source_url")
+            .map_err(|source| HistoryError::Query { source })?,
     })
 }
 
+fn get_count(row: &Row<'_>, column: &str) -> Result<Option<u64>, HistoryError> {
+    let count: Option<i64> = row
+        .get(column)
+        .map_err(|source| HistoryError::Query { source })?;
+    Ok(count.and_then(|count| u64::try_from(count).ok()))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryStats {
+    /// Entries that are not archived.
+    pub total: usize,
+    pub archived: usize,
+    /// URLs that were downloaded successfully more than once.
+    pub duplicate_count: usize,
+    /// See [`HistoryRepository::get_uploader_stats`].
+    pub top_uploaders: Vec<(String, usize)>,
+}
+
 #[derive(Debug, Clone)]
 pub struct DownloadHistoryEntry {
     pub id: i64,
@@ -286,12 +1552,26 @@ pub struct DownloadHistoryEntry {
     pub format: AudioFormat,
     pub title: Option<String>,
     pub uploader: Option<String>,
+    pub description: Option<String>,
     pub status: JobStatus,
     pub started_at: DateTime<Utc>,
     pub ended_at: Option<DateTime<Utc>>,
     pub file_path: Option<PathBuf>,
     pub error_code: Option<String>,
     pub error_message: Option<String>,
+    pub archived: bool,
+    pub view_count: Option<u64>,
+    pub like_count: Option<u64>,
+    /// Canonical URL yt-dlp resolved `url` to, when known.
+    pub source_url: Option<String>,
+}
+
+impl DownloadHistoryEntry {
+    /// A request to download `url` again in the same format. The output
+    /// directory is left empty so the configured one is used.
+    pub fn into_download_request(self) -> DownloadRequest {
+        DownloadRequest::new(self.url, PathBuf::new(), self.format)
+    }
 }
 
 impl std::fmt::Debug for HistoryRepository {
@@ -302,22 +1582,1184 @@ impl std::fmt::Debug for HistoryRepository {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
+/// Non-blocking wrapper around [`HistoryRepository`] for async callers. Each
+/// method runs the blocking SQLite call on tokio's blocking thread pool.
+#[derive(Debug, Clone)]
+pub struct AsyncHistoryRepository(HistoryRepository);
 
-    #[test]
-    fn initialize_and_store_history() {
-        let dir = tempdir().unwrap();
-        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
-        let job_id = Uuid::new_v4();
-        repo.record_queued(job_id, "https://example.com/space", AudioFormat::M4a)
-            .unwrap();
-        repo.mark_completed(job_id, JobStatus::Succeeded, None, None, None)
-            .unwrap();
-        let entries = repo.recent(10).unwrap();
-        assert_eq!(entries.len(), 1);
-        assert_eq!(entries[0].status, JobStatus::Succeeded);
+impl AsyncHistoryRepository {
+    pub fn new(repository: HistoryRepository) -> Self {
+        Self(repository)
+    }
+
+    /// The wrapped blocking repository.
+    pub fn blocking(&self) -> &HistoryRepository {
+        &self.0
+    }
+
+    async fn run<T, F>(&self, operation: F) -> Result<T, HistoryError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&HistoryRepository) -> Result<T, HistoryError> + Send + 'static,
+    {
+        let inner = self.0.clone();
+        tokio::task::spawn_blocking(move || operation(&inner))
+            .await
+            .map_err(|source| HistoryError::Join { source })?
+    }
+
+    pub async fn record_queued(
+        &self,
+        job_id: Uuid,
+        url: &str,
+        format: AudioFormat,
+    ) -> Result<i64, HistoryError> {
+        let url = url.to_string();
+        self.run(move |history| history.record_queued(job_id, &url, format))
+            .await
+    }
+
+    pub async fn update_metadata(
+        &self,
+        job_id: Uuid,
+        title: Option<&str>,
+        uploader: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<(), HistoryError> {
+        let title = title.map(str::to_string);
+        let uploader = uploader.map(str::to_string);
+        let description = description.map(str::to_string);
+        self.run(move |history| {
+            history.update_metadata(
+                job_id,
+                title.as_deref(),
+                uploader.as_deref(),
+                description.as_deref(),
+            )
+        })
+        .await
+    }
+
+    pub async fn update_counts(
+        &self,
+        job_id: Uuid,
+        view_count: Option<u64>,
+        like_count: Option<u64>,
+    ) -> Result<(), HistoryError> {
+        self.run(move |history| history.update_counts(job_id, view_count, like_count))
+            .await
+    }
+
+    pub async fn update_source_url(
+        &self,
+        job_id: Uuid,
+        source_url: &str,
+    ) -> Result<(), HistoryError> {
+        let source_url = source_url.to_string();
+        self.run(move |history| history.update_source_url(job_id, &source_url))
+            .await
+    }
+
+    pub async fn update_request(
+        &self,
+        job_id: Uuid,
+        url: &str,
+        format: AudioFormat,
+    ) -> Result<(), HistoryError> {
+        let url = url.to_string();
+        self.run(move |history| history.update_request(job_id, &url, format))
+            .await
+    }
+
+    pub async fn mark_completed(
+        &self,
+        job_id: Uuid,
+        status: JobStatus,
+        file_path: Option<&Path>,
+        error_code: Option<&str>,
+        error_message: Option<&str>,
+    ) -> Result<(), HistoryError> {
+        let file_path = file_path.map(Path::to_path_buf);
+        let error_code = error_code.map(str::to_string);
+        let error_message = error_message.map(str::to_string);
+        self.run(move |history| {
+            history.mark_completed(
+                job_id,
+                status,
+                file_path.as_deref(),
+                error_code.as_deref(),
+                error_message.as_deref(),
+            )
+        })
+        .await
+    }
+
+    pub async fn batch_mark_completed(
+        &self,
+        entries: &[(Uuid, JobStatus, Option<PathBuf>)],
+    ) -> Result<usize, HistoryError> {
+        let entries = entries.to_vec();
+        self.run(move |history| history.batch_mark_completed(&entries))
+            .await
+    }
+
+    pub async fn set_download_archive(
+        &self,
+        job_id: Uuid,
+        archive: &Path,
+    ) -> Result<(), HistoryError> {
+        let archive = archive.to_path_buf();
+        self.run(move |history| history.set_download_archive(job_id, &archive))
+            .await
+    }
+
+    pub async fn remove_from_archive(&self, job_id: Uuid) -> Result<bool, HistoryError> {
+        self.run(move |history| history.remove_from_archive(job_id))
+            .await
+    }
+
+    pub async fn mark_archived(&self, job_id: Uuid, archived: bool) -> Result<(), HistoryError> {
+        self.run(move |history| history.mark_archived(job_id, archived))
+            .await
+    }
+
+    pub async fn update_file_path(
+        &self,
+        job_id: Uuid,
+        new_path: &Path,
+    ) -> Result<(), HistoryError> {
+        let new_path = new_path.to_path_buf();
+        self.run(move |history| history.update_file_path(job_id, &new_path))
+            .await
+    }
+
+    pub async fn verify_file_paths(&self) -> Result<Vec<(Uuid, PathBuf)>, HistoryError> {
+        self.run(|history| history.verify_file_paths()).await
+    }
+
+    pub async fn import_from_yt_dlp_archive(&self, path: &Path) -> Result<usize, HistoryError> {
+        let path = path.to_path_buf();
+        self.run(move |history| history.import_from_yt_dlp_archive(&path))
+            .await
+    }
+
+    pub async fn recent(&self, limit: usize) -> Result<Vec<DownloadHistoryEntry>, HistoryError> {
+        self.run(move |history| history.recent(limit)).await
+    }
+
+    pub async fn recent_with(
+        &self,
+        limit: usize,
+        query: HistoryQuery,
+    ) -> Result<Vec<DownloadHistoryEntry>, HistoryError> {
+        self.run(move |history| history.recent_with(limit, &query))
+            .await
+    }
+
+    pub async fn page(
+        &self,
+        page: usize,
+        per_page: usize,
+    ) -> Result<(Vec<DownloadHistoryEntry>, usize), HistoryError> {
+        self.run(move |history| history.page(page, per_page)).await
+    }
+
+    pub async fn page_with(
+        &self,
+        page: usize,
+        per_page: usize,
+        query: HistoryQuery,
+    ) -> Result<(Vec<DownloadHistoryEntry>, usize), HistoryError> {
+        self.run(move |history| history.page_with(page, per_page, &query))
+            .await
+    }
+
+    pub async fn search_advanced(
+        &self,
+        query: HistoryQuery,
+    ) -> Result<(Vec<DownloadHistoryEntry>, usize), HistoryError> {
+        self.run(move |history| history.search_advanced(&query))
+            .await
+    }
+
+    pub async fn recent_by_format(
+        &self,
+        format: AudioFormat,
+        limit: usize,
+    ) -> Result<Vec<DownloadHistoryEntry>, HistoryError> {
+        self.run(move |history| history.recent_by_format(format, limit))
+            .await
+    }
+
+    pub async fn distinct_formats(&self) -> Result<Vec<AudioFormat>, HistoryError> {
+        self.run(|history| history.distinct_formats()).await
+    }
+
+    pub async fn duplicate_downloads(&self) -> Result<Vec<(String, usize)>, HistoryError> {
+        self.run(|history| history.duplicate_downloads()).await
+    }
+
+    pub async fn succeeded_downloads_of(
+        &self,
+        url: &str,
+    ) -> Result<Vec<DownloadHistoryEntry>, HistoryError> {
+        let url = url.to_string();
+        self.run(move |history| history.succeeded_downloads_of(&url))
+            .await
+    }
+
+    pub async fn find_by_file_path(
+        &self,
+        path: &Path,
+    ) -> Result<Option<DownloadHistoryEntry>, HistoryError> {
+        let path = path.to_path_buf();
+        self.run(move |history| history.find_by_file_path(&path))
+            .await
+    }
+
+    pub async fn entry_exists(&self, job_id: Uuid) -> Result<bool, HistoryError> {
+        self.run(move |history| history.entry_exists(job_id)).await
+    }
+
+    pub async fn url_was_downloaded(&self, url: &str) -> Result<bool, HistoryError> {
+        let url = url.to_string();
+        self.run(move |history| history.url_was_downloaded(&url))
+            .await
+    }
+
+    pub async fn export_m3u(&self, path: &Path) -> Result<(), HistoryError> {
+        let path = path.to_path_buf();
+        self.run(move |history| history.export_m3u(&path)).await
+    }
+
+    pub async fn get_uploader_stats(&self) -> Result<Vec<(String, usize)>, HistoryError> {
+        self.run(|history| history.get_uploader_stats()).await
+    }
+
+    pub async fn stats(&self) -> Result<HistoryStats, HistoryError> {
+        self.run(|history| history.stats()).await
+    }
+
+    pub async fn count(&self) -> Result<usize, HistoryError> {
+        self.run(|history| history.count()).await
+    }
+
+    pub async fn archived_count(&self) -> Result<usize, HistoryError> {
+        self.run(|history| history.archived_count()).await
+    }
+
+    pub async fn schema_version(&self) -> Result<u32, HistoryError> {
+        self.run(|history| history.schema_version()).await
+    }
+}
+
+impl From<HistoryRepository> for AsyncHistoryRepository {
+    fn from(repository: HistoryRepository) -> Self {
+        Self::new(repository)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn journal_mode(repo: &HistoryRepository) -> String {
+        repo.connection()
+            .unwrap()
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn wal_pragma_is_skipped_when_disabled() {
+        let dir = tempdir().unwrap();
+        let repo =
+            HistoryRepository::open_with_wal_mode(dir.path().join("plain.db"), false).unwrap();
+        assert_eq!(journal_mode(&repo), "delete");
+
+        let repo = HistoryRepository::open_with_wal_mode(dir.path().join("wal.db"), true).unwrap();
+        assert_eq!(journal_mode(&repo), "wal");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn finds_innermost_mount_type() {
+        let mounts = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+server:/export /mnt/share nfs4 rw,relatime 0 0
+tmpfs /mnt/share/tmp tmpfs rw 0 0
+";
+        let fs_type = |dir: &str| mount_fs_type(mounts, Path::new(dir));
+        assert_eq!(fs_type("/home/user"), Some("ext4"));
+        assert_eq!(fs_type("/mnt/share/spaces"), Some("nfs4"));
+        assert_eq!(fs_type("/mnt/share/tmp"), Some("tmpfs"));
+        assert_eq!(fs_type("/mnt/shared"), Some("ext4"));
+    }
+
+    #[tokio::test]
+    async fn async_repository_delegates_to_blocking_calls() {
+        let history = AsyncHistoryRepository::new(HistoryRepository::open_in_memory().unwrap());
+        let job_id = Uuid::new_v4();
+        history
+            .record_queued(job_id, "https://x.com/i/spaces/1", AudioFormat::M4a)
+            .await
+            .unwrap();
+        history
+            .update_metadata(job_id, Some("Space"), Some("host"), None)
+            .await
+            .unwrap();
+        history
+            .mark_completed(
+                job_id,
+                JobStatus::Succeeded,
+                Some(Path::new("Space.m4a")),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let recent = history.recent(10).await.unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].title.as_deref(), Some("Space"));
+        assert_eq!(history.count().await.unwrap(), 1);
+
+        history.mark_archived(job_id, true).await.unwrap();
+        assert_eq!(history.archived_count().await.unwrap(), 1);
+        let (page, total) = history
+            .page_with(0, 10, HistoryQuery::new().include_archived(true))
+            .await
+            .unwrap();
+        assert_eq!((page.len(), total), (1, 1));
+    }
+
+    #[test]
+    fn in_memory_history_persists_across_connections() {
+        let repo = HistoryRepository::open_in_memory().unwrap();
+        let job_id = Uuid::new_v4();
+        repo.record_queued(job_id, "https://example.com/space", AudioFormat::M4a)
+            .unwrap();
+        let clone = repo.clone();
+        assert_eq!(clone.recent(10).unwrap().len(), 1);
+
+        let other = HistoryRepository::open_in_memory().unwrap();
+        assert!(other.recent(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn read_only_history_allows_queries_but_rejects_writes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        let repo = HistoryRepository::open(Some(path.clone())).unwrap();
+        repo.record_queued(
+            Uuid::new_v4(),
+            "https://example.com/space",
+            AudioFormat::M4a,
+        )
+        .unwrap();
+
+        let read_only = HistoryRepository::open_read_only(&path).unwrap();
+        assert_eq!(read_only.recent(10).unwrap().len(), 1);
+        assert!(matches!(
+            read_only.record_queued(
+                Uuid::new_v4(),
+                "https://example.com/other",
+                AudioFormat::M4a
+            ),
+            Err(HistoryError::ReadOnly)
+        ));
+    }
+
+    #[test]
+    fn open_with_explicit_flags_behaves_like_open() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open_with_flags(
+            dir.path().join("history.db"),
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE,
+        )
+        .unwrap();
+        let job_id = Uuid::new_v4();
+        repo.record_queued(job_id, "https://example.com/space", AudioFormat::M4a)
+            .unwrap();
+        repo.mark_completed(job_id, JobStatus::Succeeded, None, None, None)
+            .unwrap();
+        let entries = repo.recent(10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].status, JobStatus::Succeeded);
+    }
+
+    #[test]
+    fn read_only_history_does_not_create_missing_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("missing.db");
+        assert!(matches!(
+            HistoryRepository::open_read_only(&path),
+            Err(HistoryError::Initialize { .. })
+        ));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn open_or_recover_replaces_corrupt_database() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        fs::write(&path, b"this is definitely not an sqlite database file").unwrap();
+        assert!(HistoryRepository::open(Some(path.clone())).is_err());
+
+        let repo = HistoryRepository::open_or_recover(path.clone()).unwrap();
+        repo.record_queued(
+            Uuid::new_v4(),
+            "https://example.com/space",
+            AudioFormat::M4a,
+        )
+        .unwrap();
+        assert_eq!(repo.recent(10).unwrap().len(), 1);
+
+        let backups: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("history.db.corrupt."))
+            .collect();
+        assert_eq!(backups.len(), 1);
+    }
+
+    #[test]
+    fn initialize_and_store_history() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let job_id = Uuid::new_v4();
+        repo.record_queued(job_id, "https://example.com/space", AudioFormat::M4a)
+            .unwrap();
+        repo.mark_completed(job_id, JobStatus::Succeeded, None, None, None)
+            .unwrap();
+        let entries = repo.recent(10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].status, JobStatus::Succeeded);
+    }
+
+    #[test]
+    fn migrates_legacy_schema_with_description_column() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE downloads (
+                     id INTEGER PRIMARY KEY AUTOINCREMENT,
+                     job_id TEXT NOT NULL,
+                     url TEXT NOT NULL,
+                     format TEXT NOT NULL,
+                     title TEXT,
+                     uploader TEXT,
+                     status TEXT NOT NULL,
+                     started_at TEXT NOT NULL,
+                     ended_at TEXT,
+                     file_path TEXT,
+                     error_code TEXT,
+                     error_message TEXT
+                 );",
+            )
+            .unwrap();
+
+        let repo = HistoryRepository::open(Some(path.clone())).unwrap();
+        // Re-opening must not try to add the column a second time.
+        let repo_again = HistoryRepository::open(Some(path)).unwrap();
+        let job_id = Uuid::new_v4();
+        repo.record_queued(job_id, "https://example.com/space", AudioFormat::M4a)
+            .unwrap();
+        repo.update_metadata(job_id, Some("Title"), None, Some("About the space"))
+            .unwrap();
+        repo.update_counts(job_id, Some(1_234), None).unwrap();
+        repo.update_source_url(job_id, "https://example.com/canonical")
+            .unwrap();
+
+        let entries = repo_again.recent(10).unwrap();
+        assert_eq!(entries[0].title.as_deref(), Some("Title"));
+        assert_eq!(entries[0].description.as_deref(), Some("About the space"));
+        assert_eq!(entries[0].view_count, Some(1_234));
+        assert_eq!(entries[0].like_count, None);
+        assert_eq!(
+            entries[0].source_url.as_deref(),
+            Some("https://example.com/canonical")
+        );
+    }
+
+    #[test]
+    fn schema_version_tracks_migrations() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        let repo = HistoryRepository::open(Some(path.clone())).unwrap();
+        assert_eq!(repo.schema_version().unwrap(), SCHEMA_VERSION);
+
+        // Roll the last migration back by hand.
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch(&format!(
+                "ALTER TABLE downloads DROP COLUMN owner_id;
+                 UPDATE schema_migrations SET version = {};",
+                SCHEMA_VERSION - 1
+            ))
+            .unwrap();
+        assert_eq!(repo.schema_version().unwrap(), SCHEMA_VERSION - 1);
+
+        let repo = HistoryRepository::open(Some(path)).unwrap();
+        assert_eq!(repo.schema_version().unwrap(), SCHEMA_VERSION);
+        let job_id = Uuid::new_v4();
+        repo.record_queued(job_id, "https://example.com/space", AudioFormat::M4a)
+            .unwrap();
+        repo.update_source_url(job_id, "https://example.com/canonical")
+            .unwrap();
+    }
+
+    #[test]
+    fn untracked_database_reports_version_zero() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE downloads (id INTEGER PRIMARY KEY);")
+            .unwrap();
+        let repo = HistoryRepository::open_read_only(&path).unwrap();
+        assert_eq!(repo.schema_version().unwrap(), 0);
+    }
+
+    #[test]
+    fn remove_from_archive_drops_matching_line() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let archive = dir.path().join("archive.txt");
+        fs::write(
+            &archive,
+            "youtube dQw4w9WgXcQ\ntwitterspaces 1yNGaYDqjVgxj\n",
+        )
+        .unwrap();
+
+        let job_id = Uuid::new_v4();
+        repo.record_queued(
+            job_id,
+            "https://x.com/i/spaces/1yNGaYDqjVgxj",
+            AudioFormat::M4a,
+        )
+        .unwrap();
+        assert!(!repo.remove_from_archive(job_id).unwrap());
+
+        repo.set_download_archive(job_id, &archive).unwrap();
+        assert!(repo.remove_from_archive(job_id).unwrap());
+        assert_eq!(
+            fs::read_to_string(&archive).unwrap(),
+            "youtube dQw4w9WgXcQ\n"
+        );
+        assert!(!repo.remove_from_archive(job_id).unwrap());
+    }
+
+    #[test]
+    fn archive_entry_key_inverts_archive_entry_url() {
+        for (extractor, id) in [
+            ("youtube", "dQw4w9WgXcQ"),
+            ("vimeo", "76979871"),
+            ("twitterspaces", "1yNGaYDqjVgxj"),
+        ] {
+            let url = archive_entry_url(extractor, id).unwrap();
+            assert_eq!(archive_entry_key(&url), Some(format!("{extractor} {id}")));
+        }
+        assert_eq!(
+            archive_entry_key("https://youtu.be/dQw4w9WgXcQ?si=abc").as_deref(),
+            Some("youtube dQw4w9WgXcQ")
+        );
+        assert_eq!(archive_entry_key("https://example.com/video"), None);
+    }
+
+    #[test]
+    fn imports_yt_dlp_download_archive() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let archive = dir.path().join("archive.txt");
+        fs::write(
+            &archive,
+            "youtube dQw4w9WgXcQ\nvimeo 76979871\n\nsoundcloud 12345\ntwitterspaces 1yNGaYDqjVgxj\n",
+        )
+        .unwrap();
+
+        assert_eq!(repo.import_from_yt_dlp_archive(&archive).unwrap(), 3);
+
+        let mut urls: Vec<_> = repo
+            .recent(10)
+            .unwrap()
+            .into_iter()
+            .map(|entry| {
+                assert_eq!(entry.status, JobStatus::Succeeded);
+                assert_eq!(entry.format, AudioFormat::M4a);
+                assert!(entry.title.is_none());
+                entry.url
+            })
+            .collect();
+        urls.sort();
+        assert_eq!(
+            urls,
+            vec![
+                "https://vimeo.com/76979871",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+                "https://x.com/i/spaces/1yNGaYDqjVgxj",
+            ]
+        );
+    }
+
+    #[test]
+    fn archived_entries_are_hidden_by_default() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let kept = Uuid::new_v4();
+        let archived = Uuid::new_v4();
+        repo.record_queued(kept, "https://example.com/kept", AudioFormat::M4a)
+            .unwrap();
+        repo.record_queued(archived, "https://example.com/archived", AudioFormat::M4a)
+            .unwrap();
+        repo.mark_archived(archived, true).unwrap();
+
+        let entries = repo.recent(10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].job_id, kept);
+        let (page, total) = repo.page(0, 10).unwrap();
+        assert_eq!((page.len(), total), (1, 1));
+        assert_eq!(repo.count().unwrap(), 1);
+        assert_eq!(repo.archived_count().unwrap(), 1);
+
+        let query = HistoryQuery::new().include_archived(true);
+        let entries = repo.recent_with(10, &query).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries
+            .iter()
+            .any(|entry| entry.job_id == archived && entry.archived));
+        let (page, total) = repo.page_with(0, 10, &query).unwrap();
+        assert_eq!((page.len(), total), (2, 2));
+
+        repo.mark_archived(archived, false).unwrap();
+        assert_eq!(repo.count().unwrap(), 2);
+        assert_eq!(repo.archived_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn duplicate_downloads_counts_repeated_urls() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let record = |url: &str, status: JobStatus| {
+            let job_id = Uuid::new_v4();
+            repo.record_queued(job_id, url, AudioFormat::M4a).unwrap();
+            repo.mark_completed(job_id, status, None, None, None)
+                .unwrap();
+        };
+        for _ in 0..3 {
+            record("https://example.com/repeated", JobStatus::Succeeded);
+        }
+        record("https://example.com/repeated", JobStatus::Failed);
+        record("https://example.com/once", JobStatus::Succeeded);
+
+        assert_eq!(
+            repo.duplicate_downloads().unwrap(),
+            vec![("https://example.com/repeated".to_string(), 3)]
+        );
+        assert_eq!(
+            repo.succeeded_downloads_of("https://example.com/repeated")
+                .unwrap()
+                .len(),
+            3
+        );
+        assert_eq!(repo.stats().unwrap().duplicate_count, 1);
+    }
+
+    #[test]
+    fn uploader_stats_rank_successful_downloads() {
+        let repo = HistoryRepository::open_in_memory().unwrap();
+        let record = |uploader: Option<&str>, status: JobStatus| {
+            let job_id = Uuid::new_v4();
+            repo.record_queued(job_id, "https://example.com/space", AudioFormat::M4a)
+                .unwrap();
+            repo.update_metadata(job_id, None, uploader, None).unwrap();
+            repo.mark_completed(job_id, status, None, None, None)
+                .unwrap();
+        };
+        for _ in 0..3 {
+            record(Some("alice"), JobStatus::Succeeded);
+        }
+        record(Some("bob"), JobStatus::Succeeded);
+        for _ in 0..2 {
+            record(Some("carol"), JobStatus::Succeeded);
+        }
+        for _ in 0..5 {
+            record(Some("bob"), JobStatus::Failed);
+        }
+        record(None, JobStatus::Succeeded);
+
+        let expected = vec![
+            ("alice".to_string(), 3),
+            ("carol".to_string(), 2),
+            ("bob".to_string(), 1),
+        ];
+        assert_eq!(repo.get_uploader_stats().unwrap(), expected);
+        assert_eq!(repo.stats().unwrap().top_uploaders, expected);
+
+        for index in 0..TOP_UPLOADERS_LIMIT {
+            record(Some(&format!("uploader {index:02}")), JobStatus::Succeeded);
+        }
+        let stats = repo.get_uploader_stats().unwrap();
+        assert_eq!(stats.len(), TOP_UPLOADERS_LIMIT);
+        assert_eq!(stats[0], ("alice".to_string(), 3));
+    }
+
+    #[test]
+    fn existence_checks() {
+        let repo = HistoryRepository::open_in_memory().unwrap();
+        let job_id = Uuid::new_v4();
+        let url = "https://example.com/space";
+        assert!(!repo.entry_exists(job_id).unwrap());
+        assert!(!repo.url_was_downloaded(url).unwrap());
+
+        repo.record_queued(job_id, url, AudioFormat::M4a).unwrap();
+        assert!(repo.entry_exists(job_id).unwrap());
+        assert!(!repo.entry_exists(Uuid::new_v4()).unwrap());
+        assert!(!repo.url_was_downloaded(url).unwrap());
+
+        repo.mark_completed(job_id, JobStatus::Succeeded, None, None, None)
+            .unwrap();
+        assert!(repo.url_was_downloaded(url).unwrap());
+        assert!(!repo
+            .url_was_downloaded("https://example.com/other")
+            .unwrap());
+    }
+
+    #[test]
+    fn recent_by_format_filters_entries() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        for format in [AudioFormat::Mp3, AudioFormat::M4a, AudioFormat::Mp3] {
+            repo.record_queued(Uuid::new_v4(), "https://example.com/space", format)
+                .unwrap();
+        }
+
+        let mp3 = repo.recent_by_format(AudioFormat::Mp3, 10).unwrap();
+        assert_eq!(mp3.len(), 2);
+        assert!(mp3.iter().all(|entry| entry.format == AudioFormat::Mp3));
+        assert!(repo
+            .recent_by_format(AudioFormat::Opus, 10)
+            .unwrap()
+            .is_empty());
+
+        let query = HistoryQuery::new().format(Some(AudioFormat::M4a));
+        let (page, total) = repo.page_with(0, 10, &query).unwrap();
+        assert_eq!((page.len(), total), (1, 1));
+
+        assert_eq!(
+            repo.distinct_formats().unwrap(),
+            vec![AudioFormat::M4a, AudioFormat::Mp3]
+        );
+    }
+
+    #[test]
+    fn where_clause_includes_only_set_filters() {
+        let (clause, params) = HistoryQuery::new().include_archived(true).where_clause();
+        assert_eq!(clause, "");
+        assert!(params.is_empty());
+
+        let (clause, _) = HistoryQuery::new().where_clause();
+        assert_eq!(clause, "WHERE archived = 0");
+
+        let cutoff = Utc::now();
+        let (clause, params) = HistoryQuery::new()
+            .status(JobStatus::Failed)
+            .format(AudioFormat::Mp3)
+            .url_contains("youtube")
+            .date_after(cutoff)
+            .date_before(cutoff)
+            .where_clause();
+        assert_eq!(
+            clause,
+            "WHERE archived = 0 AND format = :format AND status = :status \
+             AND instr(url, :url_contains) > 0 AND started_at >= :date_after \
+             AND started_at < :date_before"
+        );
+        let names: Vec<_> = params.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            [
+                ":format",
+                ":status",
+                ":url_contains",
+                ":date_after",
+                ":date_before"
+            ]
+        );
+        assert_eq!(params[1].1, Value::Text("Failed".to_string()));
+    }
+
+    #[test]
+    fn search_advanced_applies_each_filter() {
+        let repo = HistoryRepository::open_in_memory().unwrap();
+        let record = |url: &str, format: AudioFormat, status: JobStatus| {
+            let job_id = Uuid::new_v4();
+            repo.record_queued(job_id, url, format).unwrap();
+            repo.mark_completed(job_id, status, None, None, None)
+                .unwrap();
+            job_id
+        };
+        let failed_mp3 = record(
+            "https://youtube.com/watch?v=1",
+            AudioFormat::Mp3,
+            JobStatus::Failed,
+        );
+        record(
+            "https://x.com/i/spaces/1",
+            AudioFormat::Mp3,
+            JobStatus::Succeeded,
+        );
+        record(
+            "https://youtube.com/watch?v=2",
+            AudioFormat::M4a,
+            JobStatus::Failed,
+        );
+        let between = Utc::now();
+
+        let search = |query: HistoryQuery| repo.search_advanced(&query).unwrap();
+        assert_eq!(search(HistoryQuery::new()).1, 3);
+        assert_eq!(search(HistoryQuery::new().status(JobStatus::Failed)).1, 2);
+        assert_eq!(search(HistoryQuery::new().format(AudioFormat::Mp3)).1, 2);
+        assert_eq!(search(HistoryQuery::new().url_contains("youtube")).1, 2);
+        assert_eq!(search(HistoryQuery::new().url_contains("%")).1, 0);
+        assert_eq!(search(HistoryQuery::new().date_after(between)).1, 0);
+        assert_eq!(search(HistoryQuery::new().date_before(between)).1, 3);
+
+        let (entries, total) = search(
+            HistoryQuery::new()
+                .status(JobStatus::Failed)
+                .format(AudioFormat::Mp3)
+                .url_contains("youtube"),
+        );
+        assert_eq!(total, 1);
+        assert_eq!(entries[0].job_id, failed_mp3);
+
+        let (entries, total) = search(HistoryQuery::new().limit(1).offset(1));
+        assert_eq!((entries.len(), total), (1, 3));
+        let (entries, total) = search(HistoryQuery::new().offset(5));
+        assert_eq!((entries.len(), total), (0, 3));
+    }
+
+    #[test]
+    fn vorbis_format_round_trips_through_history() {
+        let repo = HistoryRepository::open_in_memory().unwrap();
+        repo.record_queued(
+            Uuid::new_v4(),
+            "https://example.com/space",
+            AudioFormat::Vorbis,
+        )
+        .unwrap();
+        assert_eq!(repo.recent(1).unwrap()[0].format, AudioFormat::Vorbis);
+        assert_eq!(repo.distinct_formats().unwrap(), vec![AudioFormat::Vorbis]);
+    }
+
+    #[test]
+    fn verify_and_update_file_paths() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let job_id = Uuid::new_v4();
+        let original = dir.path().join("space.m4a");
+        let moved = dir.path().join("renamed.m4a");
+        fs::write(&original, b"audio").unwrap();
+        repo.record_queued(job_id, "https://example.com/space", AudioFormat::M4a)
+            .unwrap();
+        repo.mark_completed(job_id, JobStatus::Succeeded, Some(&original), None, None)
+            .unwrap();
+        assert!(repo.verify_file_paths().unwrap().is_empty());
+
+        fs::rename(&original, &moved).unwrap();
+        assert_eq!(repo.verify_file_paths().unwrap(), vec![(job_id, original)]);
+
+        repo.update_file_path(job_id, &moved).unwrap();
+        assert!(repo.verify_file_paths().unwrap().is_empty());
+        assert_eq!(repo.recent(1).unwrap()[0].file_path, Some(moved));
+    }
+
+    #[test]
+    fn find_by_file_path_matches_exact_and_canonical_paths() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let job_id = Uuid::new_v4();
+        let file = dir.path().join("space.m4a");
+        fs::write(&file, b"audio").unwrap();
+        repo.record_queued(job_id, "https://example.com/space", AudioFormat::M4a)
+            .unwrap();
+        repo.mark_completed(job_id, JobStatus::Succeeded, Some(&file), None, None)
+            .unwrap();
+
+        let found = repo.find_by_file_path(&file).unwrap().unwrap();
+        assert_eq!(found.job_id, job_id);
+
+        let roundabout = dir.path().join("sub").join("..").join("space.m4a");
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        let found = repo.find_by_file_path(&roundabout).unwrap().unwrap();
+        assert_eq!(found.job_id, job_id);
+
+        assert!(repo
+            .find_by_file_path(&dir.path().join("other.m4a"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn batch_mark_completed_updates_every_job() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let job_ids: Vec<Uuid> = (0..10)
+            .map(|i| {
+                let job_id = Uuid::new_v4();
+                repo.record_queued(
+                    job_id,
+                    &format!("https://x.com/i/spaces/{i}"),
+                    AudioFormat::M4a,
+                )
+                .unwrap();
+                job_id
+            })
+            .collect();
+
+        let file = dir.path().join("space.m4a");
+        let mut entries: Vec<(Uuid, JobStatus, Option<PathBuf>)> = job_ids
+            .iter()
+            .map(|job_id| (*job_id, JobStatus::Failed, None))
+            .collect();
+        entries[0] = (job_ids[0], JobStatus::Succeeded, Some(file.clone()));
+        entries.push((Uuid::new_v4(), JobStatus::Failed, None));
+
+        assert_eq!(repo.batch_mark_completed(&entries).unwrap(), 10);
+
+        let recent = repo.recent(20).unwrap();
+        assert_eq!(recent.len(), 10);
+        for entry in recent {
+            assert!(entry.ended_at.is_some());
+            if entry.job_id == job_ids[0] {
+                assert_eq!(entry.status, JobStatus::Succeeded);
+                assert_eq!(entry.file_path.as_deref(), Some(file.as_path()));
+            } else {
+                assert_eq!(entry.status, JobStatus::Failed);
+            }
+        }
+        assert_eq!(repo.batch_mark_completed(&[]).unwrap(), 0);
+    }
+
+    #[test]
+    fn export_m3u_lists_existing_downloads() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        let record = |name: &str, status: JobStatus, create: bool| {
+            let job_id = Uuid::new_v4();
+            let file = dir.path().join(format!("{name}.m4a"));
+            if create {
+                fs::write(&file, b"").unwrap();
+            }
+            repo.record_queued(
+                job_id,
+                &format!("https://x.com/i/spaces/{name}"),
+                AudioFormat::M4a,
+            )
+            .unwrap();
+            repo.mark_completed(job_id, status, Some(&file), None, None)
+                .unwrap();
+            (job_id, file)
+        };
+        let (first, first_file) = record("first", JobStatus::Succeeded, true);
+        let (_, second_file) = record("second", JobStatus::Succeeded, true);
+        let (_, third_file) = record("third", JobStatus::Succeeded, true);
+        record("deleted", JobStatus::Succeeded, false);
+        record("failed", JobStatus::Failed, true);
+        repo.update_metadata(first, Some("Morning Space"), None, None)
+            .unwrap();
+
+        let playlist = dir.path().join("library.m3u");
+        repo.export_m3u(&playlist).unwrap();
+
+        let content = fs::read_to_string(&playlist).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], "#EXTM3U");
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|line| line.starts_with("#EXTINF:"))
+                .count(),
+            3
+        );
+        assert_eq!(lines.len(), 7);
+        let third = third_file.display().to_string();
+        let second = second_file.display().to_string();
+        let first = first_file.display().to_string();
+        assert_eq!(
+            lines[1..],
+            [
+                "#EXTINF:-1,third",
+                third.as_str(),
+                "#EXTINF:-1,second",
+                second.as_str(),
+                "#EXTINF:-1,Morning Space",
+                first.as_str(),
+            ]
+        );
+    }
+
+    #[test]
+    fn open_and_cleanup_running_fails_unfinished_downloads() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        let repo = HistoryRepository::open(Some(path.clone())).unwrap();
+        let running = [Uuid::new_v4(), Uuid::new_v4()];
+        for job_id in running {
+            repo.record_queued(job_id, "https://x.com/i/spaces/1", AudioFormat::M4a)
+                .unwrap();
+        }
+        repo.connection()
+            .unwrap()
+            .execute(
+                "UPDATE downloads SET status = 'Running' WHERE job_id = ?",
+                params![running[0].to_string()],
+            )
+            .unwrap();
+        let finished = Uuid::new_v4();
+        repo.record_queued(finished, "https://x.com/i/spaces/2", AudioFormat::M4a)
+            .unwrap();
+        repo.mark_completed(finished, JobStatus::Succeeded, None, None, None)
+            .unwrap();
+        drop(repo);
+
+        let (repo, cleaned) = HistoryRepository::open_and_cleanup_running(path.clone()).unwrap();
+        assert_eq!(cleaned, 2);
+        for entry in repo.recent(10).unwrap() {
+            if entry.job_id == finished {
+                assert_eq!(entry.status, JobStatus::Succeeded);
+                assert_eq!(entry.error_code, None);
+            } else {
+                assert_eq!(entry.status, JobStatus::Failed);
+                assert_eq!(entry.error_code.as_deref(), Some("Interrupted"));
+                assert_eq!(entry.error_message.as_deref(), Some("Application crashed"));
+                assert!(entry.ended_at.is_some());
+            }
+        }
+
+        let (_, cleaned) = HistoryRepository::open_and_cleanup_running(path).unwrap();
+        assert_eq!(cleaned, 0);
+    }
+
+    #[test]
+    fn cleanup_running_keeps_downloads_of_open_instances() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        let first = HistoryRepository::open(Some(path.clone())).unwrap();
+        let job_id = Uuid::new_v4();
+        first
+            .record_queued(job_id, "https://x.com/i/spaces/1", AudioFormat::M4a)
+            .unwrap();
+
+        let (second, cleaned) = HistoryRepository::open_and_cleanup_running(path.clone()).unwrap();
+        assert_eq!(cleaned, 0);
+        assert_eq!(first.cleanup_running().unwrap(), 0);
+        assert_eq!(second.recent(1).unwrap()[0].status, JobStatus::Queued);
+
+        drop(first);
+        assert_eq!(second.cleanup_running().unwrap(), 1);
+        assert_eq!(second.recent(1).unwrap()[0].status, JobStatus::Failed);
+    }
+
+    #[test]
+    fn file_path_index_is_created() {
+        let repo = HistoryRepository::open_in_memory().unwrap();
+        let connection = repo.connection().unwrap();
+        let exists: bool = connection
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_master
+                 WHERE type = 'index' AND name = 'idx_downloads_file_path')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(exists);
+    }
+
+    #[test]
+    fn busy_timeout_waits_for_a_competing_writer() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        let waiting = HistoryRepository::open_with_timeout(path.clone(), 5_000).unwrap();
+        let impatient = HistoryRepository::open_with_timeout(path.clone(), 0).unwrap();
+
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let holder = std::thread::spawn(move || {
+            let connection = Connection::open(&path).unwrap();
+            connection.execute_batch("BEGIN IMMEDIATE;").unwrap();
+            locked_tx.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(300));
+            connection.execute_batch("COMMIT;").unwrap();
+        });
+        locked_rx.recv().unwrap();
+
+        assert!(impatient
+            .record_queued(Uuid::new_v4(), "https://example.com/a", AudioFormat::M4a)
+            .is_err());
+        let started = std::time::Instant::now();
+        waiting
+            .record_queued(Uuid::new_v4(), "https://example.com/b", AudioFormat::M4a)
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        holder.join().unwrap();
+        assert_eq!(waiting.count().unwrap(), 1);
+    }
+
+    #[test]
+    fn count_matches_recent() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        assert_eq!(repo.count().unwrap(), 0);
+        for _ in 0..3 {
+            repo.record_queued(
+                Uuid::new_v4(),
+                "https://example.com/space",
+                AudioFormat::Mp3,
+            )
+            .unwrap();
+        }
+        assert_eq!(repo.count().unwrap(), 3);
+        assert_eq!(
+            repo.count().unwrap(),
+            repo.recent(usize::MAX).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn page_through_history() {
+        let dir = tempdir().unwrap();
+        let repo = HistoryRepository::open(Some(dir.path().join("history.db"))).unwrap();
+        for index in 0..25 {
+            repo.record_queued(
+                Uuid::new_v4(),
+                &format!("https://example.com/space/{index}"),
+                AudioFormat::M4a,
+            )
+            .unwrap();
+        }
+
+        let (first, total) = repo.page(0, 10).unwrap();
+        assert_eq!(first.len(), 10);
+        assert_eq!(total, 25);
+
+        let (second, total) = repo.page(1, 10).unwrap();
+        assert_eq!(second.len(), 10);
+        assert_eq!(total, 25);
+        assert!(second
+            .iter()
+            .all(|entry| first.iter().all(|f| f.id != entry.id)));
+
+        let (last, total) = repo.page(2, 10).unwrap();
+        assert_eq!(last.len(), 5);
+        assert_eq!(total, 25);
+
+        let (beyond, total) = repo.page(3, 10).unwrap();
+        assert!(beyond.is_empty());
+        assert_eq!(total, 25);
     }
 }
@@ -1,20 +1,32 @@
+pub mod batch;
 pub mod config;
 pub mod dependency;
 pub mod download;
 pub mod error;
 pub mod history;
 pub mod logging;
+pub mod sponsorblock;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+#[cfg(feature = "tower-service")]
+pub mod tower_service;
 
+pub use batch::{BatchJob, BatchJobResult};
 pub use config::{
-    AdvancedSettings, AudioFormat, Config, DownloadSettings, GeneralSettings, LogSettings,
+    AdvancedSettings, AudioFormat, Config, DownloadSettings, GeneralSettings, GeoBypassMode,
+    LogSettings, VideoContainer,
 };
 pub use dependency::{DependencyCheck, DependencyStatus};
 pub use download::{
-    DownloadEvent, DownloadRequest, DownloadSummary, DownloaderService, JobHandle, JobState,
-    JobStatus, ProgressSnapshot,
+    DownloadEvent, DownloadMode, DownloadRequest, DownloadSummary, DownloaderMetrics,
+    DownloaderService, DownloaderServiceBuilder, JobHandle, JobProgress, JobState, JobStatus,
+    ProgressSnapshot,
 };
-pub use error::{ConfigError, DependencyError, DownloadError, HistoryError, SpaceDownloaderError};
-pub use history::{DownloadHistoryEntry, HistoryRepository};
+pub use error::{
+    ConfigError, DependencyError, DownloadError, ErrorHelp, HistoryError, SpaceDownloaderError,
+};
+pub use history::{AsyncHistoryRepository, DownloadHistoryEntry, HistoryRepository};
 pub use logging::{LogManager, LogManagerBuilder};
+pub use sponsorblock::SponsorBlockCategory;
 
 pub type Result<T> = std::result::Result<T, SpaceDownloaderError>;
@@ -1,14 +1,18 @@
+use std::fmt::Write as _;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use directories::ProjectDirs;
 use once_cell::sync::Lazy;
-use tracing::Level;
+use tokio::sync::broadcast;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::filter::LevelFilter;
-use tracing_subscriber::layer::{Layer, SubscriberExt};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
 use tracing_subscriber::{fmt, util::SubscriberInitExt, EnvFilter};
 
-use crate::config::{LogLevel, LogSettings};
+use crate::config::{LogLevel, LogSettings, DEFAULT_LOG_FILE_PREFIX, DEFAULT_MAX_LOG_FILES};
 
 static DEFAULT_LOG_DIR: Lazy<PathBuf> = Lazy::new(|| {
     #[cfg(target_os = "macos")]
@@ -34,10 +38,15 @@ static DEFAULT_LOG_DIR: Lazy<PathBuf> = Lazy::new(|| {
     }
 });
 
+/// Lines buffered for [`LogManager::subscribe`] receivers. A receiver that
+/// falls further behind skips ahead to the oldest buffered line.
+const LOG_BROADCAST_CAPACITY: usize = 1000;
+
 pub struct LogManager {
     _guard: Option<tracing_appender::non_blocking::WorkerGuard>,
     level: Level,
     log_dir: PathBuf,
+    line_tx: broadcast::Sender<String>,
 }
 
 impl LogManager {
@@ -52,6 +61,12 @@ impl LogManager {
     pub fn log_dir(&self) -> &PathBuf {
         &self.log_dir
     }
+
+    /// Receive every log line recorded from now on, formatted as
+    /// `LEVEL target: message`.
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.line_tx.subscribe()
+    }
 }
 
 pub struct LogManagerBuilder {
@@ -59,6 +74,8 @@ pub struct LogManagerBuilder {
     enable_file: bool,
     log_dir: PathBuf,
     enable_stdout: bool,
+    file_name_prefix: String,
+    max_files: u8,
 }
 
 impl Default for LogManagerBuilder {
@@ -68,6 +85,8 @@ impl Default for LogManagerBuilder {
             enable_file: true,
             log_dir: DEFAULT_LOG_DIR.clone(),
             enable_stdout: true,
+            file_name_prefix: DEFAULT_LOG_FILE_PREFIX.to_string(),
+            max_files: DEFAULT_MAX_LOG_FILES,
         }
     }
 }
@@ -76,6 +95,8 @@ impl LogManagerBuilder {
     pub fn with_settings(mut self, settings: &LogSettings) -> Self {
         self.level = level_from_config(&settings.level);
         self.enable_file = settings.enabled;
+        self.file_name_prefix = settings.file_name_prefix.clone();
+        self.max_files = settings.max_files;
         self
     }
 
@@ -89,6 +110,19 @@ impl LogManagerBuilder {
         self
     }
 
+    /// Name log files `<prefix>.log.<date>` instead of `space_downloader.log.<date>`.
+    pub fn file_name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.file_name_prefix = prefix.into();
+        self
+    }
+
+    /// Keep at most `max_files` log files, deleting the oldest. Clamped to at
+    /// least one.
+    pub fn max_files(mut self, max_files: u8) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
     pub fn enable_file(mut self, enable: bool) -> Self {
         self.enable_file = enable;
         self
@@ -106,9 +140,19 @@ impl LogManagerBuilder {
 
         let level_filter = EnvFilter::from_default_env().add_directive(self.level.into());
 
+        let mut prune_error = None;
         let (file_writer, file_guard) = if self.enable_file {
-            let file_appender =
-                tracing_appender::rolling::daily(&self.log_dir, "space_downloader.log");
+            let max_files = usize::from(self.max_files.max(1));
+            // The appender only prunes when the date rolls over, which a
+            // short-lived app rarely sees, so clean up at startup as well.
+            let file_appender = RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .filename_prefix(format!("{}.log", self.file_name_prefix))
+                .max_log_files(max_files)
+                .build(&self.log_dir)
+                .map_err(io::Error::other)?;
+            // Old files left behind are harmless, so this never fails the build.
+            prune_error = prune_log_files(&self.log_dir, &self.file_name_prefix, max_files).err();
             tracing_appender::non_blocking(file_appender)
         } else {
             tracing_appender::non_blocking(io::sink())
@@ -131,12 +175,25 @@ impl LogManagerBuilder {
                 LevelFilter::OFF
             });
 
+        let (line_tx, _) = broadcast::channel(LOG_BROADCAST_CAPACITY);
+
         tracing_subscriber::registry()
             .with(level_filter)
             .with(file_layer)
             .with(stdout_layer)
+            .with(BroadcastLayer {
+                tx: line_tx.clone(),
+            })
             .init();
 
+        // Reported once the subscriber is installed so it reaches the log.
+        if let Some(err) = prune_error {
+            tracing::warn!(
+                "Failed to remove old log files in {}: {err}",
+                self.log_dir.display()
+            );
+        }
+
         Ok(LogManager {
             _guard: if self.enable_file {
                 Some(file_guard)
@@ -145,10 +202,59 @@ impl LogManagerBuilder {
             },
             level: self.level,
             log_dir: self.log_dir,
+            line_tx,
         })
     }
 }
 
+/// Forwards formatted events to [`LogManager::subscribe`] receivers.
+struct BroadcastLayer {
+    tx: broadcast::Sender<String>,
+}
+
+impl<S: Subscriber> Layer<S> for BroadcastLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+        let metadata = event.metadata();
+        let mut line = format!("{} {}:", metadata.level(), metadata.target());
+        event.record(&mut LineVisitor(&mut line));
+        let _ = self.tx.send(line);
+    }
+}
+
+struct LineVisitor<'a>(&'a mut String);
+
+impl Visit for LineVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+/// Delete all but the newest `max_files` files named `<prefix>.log*` in
+/// `log_dir`. Returns how many were deleted.
+fn prune_log_files(log_dir: &Path, prefix: &str, max_files: usize) -> io::Result<usize> {
+    let stem = format!("{prefix}.log");
+    let mut names: Vec<String> = std::fs::read_dir(log_dir)?
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.starts_with(&stem))
+        .collect();
+    // Dated names (`<prefix>.log.YYYY-MM-DD`) sort oldest first.
+    names.sort_unstable();
+    let excess = names.len().saturating_sub(max_files);
+    for name in &names[..excess] {
+        std::fs::remove_file(log_dir.join(name))?;
+    }
+    Ok(excess)
+}
+
 fn level_from_config(level: &LogLevel) -> Level {
     match level {
         LogLevel::Error => Level::ERROR,
@@ -157,3 +263,78 @@ fn level_from_config(level: &LogLevel) -> Level {
         LogLevel::Debug => Level::DEBUG,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broadcast_layer_forwards_formatted_events() {
+        let (tx, mut rx) = broadcast::channel(LOG_BROADCAST_CAPACITY);
+        let subscriber = tracing_subscriber::registry().with(BroadcastLayer { tx });
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(job = 7, "download stalled");
+        });
+
+        let line = rx.try_recv().unwrap();
+        assert!(line.starts_with("WARN "), "{line}");
+        assert!(line.ends_with(": download stalled job=7"), "{line}");
+    }
+
+    #[test]
+    fn prune_log_files_keeps_the_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        for day in 1..=10 {
+            let name = format!("space_downloader.log.2025-01-{day:02}");
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        std::fs::write(dir.path().join("other.log.2025-01-01"), "").unwrap();
+
+        assert_eq!(
+            prune_log_files(dir.path(), "space_downloader", 3).unwrap(),
+            7
+        );
+
+        let mut names: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "other.log.2025-01-01",
+                "space_downloader.log.2025-01-08",
+                "space_downloader.log.2025-01-09",
+                "space_downloader.log.2025-01-10",
+            ]
+        );
+    }
+
+    #[test]
+    fn log_file_uses_custom_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = LogManager::builder()
+            .log_dir(dir.path())
+            .enable_stdout(false)
+            .file_name_prefix("instance-1-space_downloader")
+            .build()
+            .unwrap();
+        tracing::info!("written to the prefixed log file");
+        // Dropping the manager flushes the non-blocking writer.
+        drop(manager);
+
+        let names: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        assert!(
+            names
+                .iter()
+                .any(|name| name.starts_with("instance-1-space_downloader.log")),
+            "{names:?}"
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+/// SponsorBlock segment categories understood by yt-dlp's `--sponsorblock-*`
+/// options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SponsorBlockCategory {
+    Sponsor,
+    Intro,
+    Outro,
+    Selfpromo,
+    Preview,
+    Filler,
+    Interaction,
+    MusicOfftopic,
+}
+
+impl SponsorBlockCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SponsorBlockCategory::Sponsor => "sponsor",
+            SponsorBlockCategory::Intro => "intro",
+            SponsorBlockCategory::Outro => "outro",
+            SponsorBlockCategory::Selfpromo => "selfpromo",
+            SponsorBlockCategory::Preview => "preview",
+            SponsorBlockCategory::Filler => "filler",
+            SponsorBlockCategory::Interaction => "interaction",
+            SponsorBlockCategory::MusicOfftopic => "music_offtopic",
+        }
+    }
+}
+
+impl std::fmt::Display for SponsorBlockCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Join categories into the comma-separated list yt-dlp expects.
+pub fn category_list(categories: &[SponsorBlockCategory]) -> String {
+    categories
+        .iter()
+        .map(SponsorBlockCategory::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
+}
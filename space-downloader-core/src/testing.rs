@@ -0,0 +1,273 @@
+//! Test helpers for exercising downloads without a real yt-dlp installation.
+//!
+//! Available in this crate's own tests and, for other crates, behind the
+//! `testing` feature.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tempfile::TempDir;
+
+/// A stand-in yt-dlp executable that replays a fixed script.
+///
+/// Point [`AdvancedSettings::yt_dlp_path`](crate::config::AdvancedSettings::yt_dlp_path)
+/// at [`MockYtDlp::path`]. The script lives in a temporary directory that is
+/// removed when the mock is dropped, so keep it alive for the whole test.
+#[derive(Debug)]
+pub struct MockYtDlp {
+    _dir: TempDir,
+    path: PathBuf,
+}
+
+impl MockYtDlp {
+    pub fn builder() -> MockYtDlpBuilder {
+        MockYtDlpBuilder::default()
+    }
+
+    /// Report `file_path` as the destination, create it and exit successfully.
+    pub fn immediate_success(file_path: impl AsRef<Path>) -> io::Result<Self> {
+        let file_path = file_path.as_ref();
+        Self::builder()
+            .stderr(format!("[download] Destination: {}", file_path.display()))
+            .create_file(file_path)
+            .build()
+    }
+
+    /// Print `stderr` and exit with `exit_code`.
+    pub fn fail_with(exit_code: i32, stderr: &str) -> io::Result<Self> {
+        Self::builder().stderr(stderr).exit_code(exit_code).build()
+    }
+
+    /// Report `destination`, print each of `progress_lines`, then create the
+    /// file and exit successfully.
+    pub fn progress_then_succeed(
+        progress_lines: &[&str],
+        destination: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        let destination = destination.as_ref();
+        progress_lines
+            .iter()
+            .fold(
+                Self::builder()
+                    .stderr(format!("[download] Destination: {}", destination.display())),
+                |builder, line| builder.stderr(*line),
+            )
+            .create_file(destination)
+            .build()
+    }
+
+    /// The executable to use as `yt_dlp_path`.
+    pub fn path(&self) -> PathBuf {
+        self.path.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Stdout(String),
+    Stderr(String),
+    PrintArgs,
+    CreateFile(PathBuf),
+    WriteFile(PathBuf, String),
+    Sleep(Duration),
+}
+
+/// Builds the script a [`MockYtDlp`] runs, one step at a time.
+///
+/// yt-dlp progress is read from stderr, so progress and destination lines
+/// should go through [`MockYtDlpBuilder::stderr`].
+#[derive(Debug, Clone, Default)]
+pub struct MockYtDlpBuilder {
+    steps: Vec<Step>,
+    exit_code: i32,
+}
+
+impl MockYtDlpBuilder {
+    /// Print `line` to stdout.
+    pub fn stdout(mut self, line: impl Into<String>) -> Self {
+        self.steps.push(Step::Stdout(line.into()));
+        self
+    }
+
+    /// Print `line` to stderr.
+    pub fn stderr(mut self, line: impl Into<String>) -> Self {
+        self.steps.push(Step::Stderr(line.into()));
+        self
+    }
+
+    /// Print each argument the mock was called with to stderr, one per line,
+    /// so tests can check the command line through the job's log events.
+    pub fn print_args(mut self) -> Self {
+        self.steps.push(Step::PrintArgs);
+        self
+    }
+
+    /// Create an empty file at `path`.
+    pub fn create_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.steps.push(Step::CreateFile(path.into()));
+        self
+    }
+
+    /// Write `contents` to `path`, e.g. an `.info.json` for the job to read.
+    pub fn write_file(mut self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.steps
+            .push(Step::WriteFile(path.into(), contents.into()));
+        self
+    }
+
+    /// Pause for `duration`, e.g. to keep the job running while a test acts on it.
+    /// Durations are rounded up to whole seconds on Windows.
+    pub fn sleep(mut self, duration: Duration) -> Self {
+        self.steps.push(Step::Sleep(duration));
+        self
+    }
+
+    /// Exit with `code` after the other steps. Defaults to 0.
+    pub fn exit_code(mut self, code: i32) -> Self {
+        self.exit_code = code;
+        self
+    }
+
+    /// Write the script to a new temporary directory.
+    pub fn build(self) -> io::Result<MockYtDlp> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(SCRIPT_NAME);
+        fs::write(&path, self.script())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(MockYtDlp { _dir: dir, path })
+    }
+
+    #[cfg(not(windows))]
+    fn script(&self) -> String {
+        let mut script = String::from("#!/bin/sh\n");
+        for step in &self.steps {
+            let line = match step {
+                Step::Stdout(text) => format!("printf '%s\\n' {}", sh_quote(text)),
+                Step::Stderr(text) => format!("printf '%s\\n' {} >&2", sh_quote(text)),
+                Step::PrintArgs => r#"printf '%s\n' "$@" >&2"#.to_string(),
+                Step::CreateFile(path) => {
+                    format!(": > {}", sh_quote(&path.to_string_lossy()))
+                }
+                Step::WriteFile(path, contents) => format!(
+                    "printf '%s' {} > {}",
+                    sh_quote(contents),
+                    sh_quote(&path.to_string_lossy())
+                ),
+                Step::Sleep(duration) => format!("sleep {}", duration.as_secs_f64()),
+            };
+            script.push_str(&line);
+            script.push('\n');
+        }
+        script.push_str(&format!("exit {}\n", self.exit_code));
+        script
+    }
+
+    #[cfg(windows)]
+    fn script(&self) -> String {
+        let mut script = String::from("@echo off\r\n");
+        for step in &self.steps {
+            let line = match step {
+                Step::Stdout(text) => format!("echo({}", cmd_escape(text)),
+                Step::Stderr(text) => format!("echo({} 1>&2", cmd_escape(text)),
+                Step::PrintArgs => "for %%a in (%*) do echo(%%~a 1>&2".to_string(),
+                Step::CreateFile(path) => format!("type nul > \"{}\"", path.display()),
+                Step::WriteFile(path, contents) => {
+                    let mut lines = format!("type nul > \"{}\"", path.display());
+                    for line in contents.lines() {
+                        lines.push_str(&format!(
+                            "\r\necho({}>> \"{}\"",
+                            cmd_escape(line),
+                            path.display()
+                        ));
+                    }
+                    lines
+                }
+                Step::Sleep(duration) => {
+                    let seconds = duration.as_secs() + u64::from(duration.subsec_nanos() > 0);
+                    format!("ping -n {} 127.0.0.1 > nul", seconds + 1)
+                }
+            };
+            script.push_str(&line);
+            script.push_str("\r\n");
+        }
+        script.push_str(&format!("exit /b {}\r\n", self.exit_code));
+        script
+    }
+}
+
+#[cfg(not(windows))]
+const SCRIPT_NAME: &str = "yt-dlp";
+#[cfg(windows)]
+const SCRIPT_NAME: &str = "yt-dlp.cmd";
+
+#[cfg(not(windows))]
+fn sh_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+#[cfg(windows)]
+fn cmd_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '%' => escaped.push_str("%%"),
+            '^' | '&' | '|' | '<' | '>' | '(' | ')' => {
+                escaped.push('^');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn script_replays_output_and_exit_code() {
+        let mock = MockYtDlp::builder()
+            .stdout("it's on stdout")
+            .stderr("[download]  50.0% of 1.00MiB")
+            .exit_code(3)
+            .build()
+            .unwrap();
+
+        let output = tokio::process::Command::new(mock.path())
+            .output()
+            .await
+            .unwrap();
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            "it's on stdout"
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr).trim(),
+            "[download]  50.0% of 1.00MiB"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn immediate_success_creates_the_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let destination = dir.path().join("Space.m4a");
+        let mock = MockYtDlp::immediate_success(&destination).unwrap();
+
+        let status = tokio::process::Command::new(mock.path())
+            .status()
+            .await
+            .unwrap();
+        assert!(status.success());
+        assert!(destination.exists());
+    }
+}
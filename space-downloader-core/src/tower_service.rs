@@ -0,0 +1,123 @@
+//! [`tower::Service`] adapter so the downloader can sit behind Tower middleware.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio_util::sync::PollSemaphore;
+
+use crate::download::{DownloadRequest, DownloaderService, JobHandle};
+use crate::error::DownloadError;
+
+/// Queues each [`DownloadRequest`] it is called with. The service reports
+/// ready once a download slot is free, so middleware can apply backpressure.
+#[derive(Clone)]
+pub struct DownloaderTowerService {
+    service: Arc<DownloaderService>,
+    slots: Option<PollSemaphore>,
+}
+
+impl DownloaderTowerService {
+    pub fn new(service: Arc<DownloaderService>) -> Self {
+        Self {
+            service,
+            slots: None,
+        }
+    }
+}
+
+impl tower::Service<DownloadRequest> for DownloaderTowerService {
+    type Response = JobHandle;
+    type Error = DownloadError;
+    type Future = Pin<Box<dyn Future<Output = Result<JobHandle, DownloadError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let slots = self
+            .slots
+            .get_or_insert_with(|| PollSemaphore::new(self.service.semaphore()));
+        let closed = || DownloadError::JobFailed("download slots closed".to_string());
+        if slots.clone_inner().is_closed() {
+            return Poll::Ready(Err(closed()));
+        }
+        if slots.available_permits() > 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        // The permit is only used to wait for a free slot; the job acquires its
+        // own once it is queued.
+        match slots.poll_acquire(cx) {
+            Poll::Ready(Some(_)) => Poll::Ready(Ok(())),
+            Poll::Ready(None) => Poll::Ready(Err(closed())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn call(&mut self, request: DownloadRequest) -> Self::Future {
+        let service = self.service.clone();
+        Box::pin(async move { service.queue(request).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::poll_fn;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use tower::Service;
+
+    use super::*;
+    use crate::config::{AudioFormat, Config};
+
+    #[tokio::test]
+    async fn ready_only_when_a_slot_is_free() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        let downloader = Arc::new(DownloaderService::new_without_history(config));
+        let mut service = DownloaderTowerService::new(downloader.clone());
+
+        let semaphore = downloader.semaphore();
+        let slot = semaphore.clone().acquire_owned().await.unwrap();
+        let pending = tokio::time::timeout(
+            Duration::from_millis(50),
+            poll_fn(|cx| service.poll_ready(cx)),
+        )
+        .await;
+        assert!(pending.is_err());
+
+        drop(slot);
+        poll_fn(|cx| service.poll_ready(cx)).await.unwrap();
+
+        // Hold the slot again so the queued job never starts.
+        let _slot = semaphore.acquire_owned().await.unwrap();
+        let handle = service
+            .call(DownloadRequest::new(
+                "https://x.com/i/spaces/1".to_string(),
+                PathBuf::new(),
+                AudioFormat::M4a,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(handle.url, "https://x.com/i/spaces/1");
+        handle.cancel();
+    }
+
+    #[tokio::test]
+    async fn closed_slots_are_an_error() {
+        let downloader = Arc::new(DownloaderService::new_without_history(Config::default()));
+        let mut service = DownloaderTowerService::new(downloader.clone());
+
+        let semaphore = downloader.semaphore();
+        let _slot = semaphore.clone().acquire_owned().await.unwrap();
+        let mut cx = Context::from_waker(std::task::Waker::noop());
+        assert!(service.poll_ready(&mut cx).is_pending());
+
+        semaphore.close();
+        assert!(matches!(
+            service.poll_ready(&mut cx),
+            Poll::Ready(Err(DownloadError::JobFailed(message))) if message == "download slots closed"
+        ));
+    }
+}
@@ -1,5 +1,11 @@
 app-title = Space Downloader
 
+# Initialization
+init-title = Initializing…
+init-checking-dependencies = Checking dependencies…
+init-opening-history = Opening history database…
+init-initializing-logger = Initializing logger…
+
 # Tabs
 tab-download = Download
 tab-history = History
@@ -10,12 +16,18 @@ input-url-label = Space URL
 button-download = Download
 button-paste = Paste
 button-cancel = Cancel
+button-cancel-delete = Cancel and Delete
 button-save-settings = Save Settings
 button-reset-settings = Reset to Defaults
+button-dismiss = Dismiss
+button-edit-config = Edit Config File
 button-dependency-check = Check Dependencies
 button-history-refresh = Refresh History
+button-previous = Previous
+button-next = Next
 
 # Status
+status-scheduled = Scheduled
 status-queued = Queued
 status-running = Running
 status-succeeded = Completed
@@ -24,8 +36,30 @@ status-canceled = Canceled
 
 # Sections
 download-active = Active Downloads
+download-bytes-remaining = ~{ $size } remaining
 logs-panel-title = Activity Log
+notice-interrupted = A previous download was interrupted at { $percent }%
+notice-interrupted-unknown = A previous download was interrupted
+notice-interrupted-count = { $count } interrupted downloads detected.
+notice-config-reset = Reset to default: { $error }
+notice-config-reload-failed = Could not reload the settings: { $error }
 history-empty = No downloads yet.
+history-page = Page { $page } of { $total }
+history-show-archived = Show archived
+history-format-all = All formats
+history-archive = Archive
+history-unarchive = Unarchive
+history-force-retry = Force retry
+history-views = { $count } views
+history-likes = { $count } likes
+history-source-url = Resolved to { $url }
+history-retry-failed = Retry All Failed
+history-duplicates = Duplicates
+history-all = All History
+history-duplicates-empty = No URL has been downloaded more than once.
+history-duplicate-entry = { $url } (×{ $count })
+history-top-uploaders = Top Uploaders
+history-uploader-entry = { $uploader } ({ $count })
 dependencies-ok = All dependencies are available.
 dependencies-missing = Missing dependencies detected.
 yt-dlp-version = yt-dlp: { $version }
@@ -37,6 +71,7 @@ settings-download = Download
 settings-advanced = Advanced
 settings-logging = Logging
 settings-output-dir = Output directory
+language-community = { $language } (community)
 settings-language = Language
 settings-theme = Theme
 settings-theme-light = Light
@@ -51,12 +86,57 @@ settings-cookie-file = Cookie file
 settings-extra-args = Extra arguments
 settings-save-logs = Save logs to file
 settings-log-level = Log level
+settings-windows-no-console = Hide yt-dlp console window
+settings-prefer-free-formats = Prefer free formats
+settings-force-overwrites = Force overwrite all files
+settings-live-from-start = Record live streams from the start
+settings-geo-bypass = Geo bypass
+settings-geo-bypass-off = Off
+settings-geo-bypass-auto = Auto
+settings-geo-bypass-country = Country
+settings-age-limit = Age limit
+settings-audio-bitrate = Bitrate (kbps)
+settings-audio-bitrate-best = Best
+
+# Progress
+progress-percent = { $percent }%
+progress-size = { $downloaded } / { $total }
+progress-downloaded = { $downloaded } downloaded
+progress-speed = { $speed }/s
+progress-eta = ETA { $eta }
+progress-completes-at = completes at { $time }
+progress-starts-in = starts in { $eta }
+progress-fragment = Fragment { $current }/{ $total }
+eta-minutes = { $minutes }m { $seconds }s
+eta-seconds = { $seconds }s
+size-bytes = { $value } B
+size-kb = { $value } KB
+size-mb = { $value } MB
+size-gb = { $value } GB
 
 # History
 history-entry-title = { $title } by { $uploader }
 job-open-folder = Open Folder
 job-retry = Retry
 job-copy-path = Copy Path
+job-copy-logs = Copy Logs
+job-logs-copied = Copied!
+job-description-more = Show more
+job-description-less = Show less
+job-tracks-extracted = { $count } tracks extracted
+job-chapters = Chapters
+job-sponsorblock-mark = { $start } – { $end } ({ $category })
 
 # Validation
 error-invalid-url = Please enter a valid X Spaces URL.
+url-previously-downloaded = This URL has been downloaded before.
+
+# Error help
+help-check-url = Check that the URL starts with http:// or https://.
+help-install-dependency = Install the missing dependency or check the yt-dlp path in the settings.
+help-add-cookie-file = Add a cookie file in the advanced settings.
+help-use-proxy = Try using a VPN or proxy.
+help-wait-rate-limit = Wait a while before retrying, or raise the rate limit cooldown.
+help-supported-sites = See https://github.com/yt-dlp/yt-dlp#supported-sites for the supported platforms.
+help-wait-quota = Try again later, from a different network connection, or with a cookie file.
+help-make-executable = Check that the yt-dlp binary is executable. On Unix: chmod +x <path to yt-dlp>
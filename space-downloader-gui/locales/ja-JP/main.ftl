@@ -1,5 +1,11 @@
 app-title = スペースダウンローダー
 
+# 初期化
+init-title = 初期化しています…
+init-checking-dependencies = 依存関係を確認しています…
+init-opening-history = 履歴データベースを開いています…
+init-initializing-logger = ロガーを初期化しています…
+
 # タブ
 tab-download = ダウンロード
 tab-history = 履歴
@@ -10,12 +16,18 @@ input-url-label = スペースのURL
 button-download = ダウンロード
 button-paste = 貼り付け
 button-cancel = キャンセル
+button-cancel-delete = キャンセルして削除
 button-save-settings = 設定を保存
 button-reset-settings = 初期設定に戻す
+button-dismiss = 閉じる
+button-edit-config = 設定ファイルを編集
 button-dependency-check = 依存関係を確認
 button-history-refresh = 履歴を更新
+button-previous = 前へ
+button-next = 次へ
 
 # ステータス
+status-scheduled = 予約済み
 status-queued = キュー待ち
 status-running = ダウンロード中
 status-succeeded = 完了
@@ -24,8 +36,30 @@ status-canceled = キャンセル済み
 
 # セクション
 download-active = 実行中のダウンロード
+download-bytes-remaining = 残り約 { $size }
 logs-panel-title = ログ
+notice-interrupted = 前回のダウンロードが { $percent }% で中断されました
+notice-interrupted-unknown = 前回のダウンロードが中断されました
+notice-interrupted-count = 中断されたダウンロードが { $count } 件見つかりました。
+notice-config-reset = 初期値に戻しました: { $error }
+notice-config-reload-failed = 設定を再読み込みできませんでした: { $error }
 history-empty = ダウンロード履歴はまだありません。
+history-page = { $page } / { $total } ページ
+history-show-archived = アーカイブ済みを表示
+history-format-all = すべての形式
+history-archive = アーカイブ
+history-unarchive = アーカイブ解除
+history-force-retry = 強制的に再ダウンロード
+history-views = 再生 { $count } 回
+history-likes = いいね { $count } 件
+history-source-url = 解決後の URL: { $url }
+history-retry-failed = 失敗したものをすべて再試行
+history-duplicates = 重複
+history-all = すべての履歴
+history-duplicates-empty = 複数回ダウンロードされた URL はありません。
+history-duplicate-entry = { $url }（{ $count } 回）
+history-top-uploaders = よくダウンロードする配信者
+history-uploader-entry = { $uploader }（{ $count } 件）
 dependencies-ok = 依存コマンドはすべて利用可能です。
 dependencies-missing = 依存コマンドが見つかりません。
 yt-dlp-version = yt-dlp: { $version }
@@ -37,6 +71,7 @@ settings-download = ダウンロード
 settings-advanced = 上級者向け
 settings-logging = ログ
 settings-output-dir = 保存先フォルダー
+language-community = { $language }（コミュニティ翻訳）
 settings-language = 表示言語
 settings-theme = テーマ
 settings-theme-light = ライト
@@ -51,12 +86,57 @@ settings-cookie-file = Cookie ファイル
 settings-extra-args = 追加オプション
 settings-save-logs = ログを保存
 settings-log-level = ログレベル
+settings-windows-no-console = yt-dlp のコンソールを表示しない
+settings-prefer-free-formats = フリーなフォーマットを優先
+settings-force-overwrites = すべてのファイルを強制的に上書き
+settings-live-from-start = ライブ配信を最初から録音
+settings-geo-bypass = 地域制限の回避
+settings-geo-bypass-off = オフ
+settings-geo-bypass-auto = 自動
+settings-geo-bypass-country = 国を指定
+settings-age-limit = 年齢制限
+settings-audio-bitrate = ビットレート (kbps)
+settings-audio-bitrate-best = 最高
+
+# 進捗
+progress-percent = { $percent }%
+progress-size = { $downloaded } / { $total }
+progress-downloaded = { $downloaded } ダウンロード済み
+progress-speed = { $speed }/秒
+progress-eta = 残り { $eta }
+progress-completes-at = { $time } 完了予定
+progress-starts-in = 開始まで { $eta }
+progress-fragment = フラグメント { $current }/{ $total }
+eta-minutes = { $minutes }分{ $seconds }秒
+eta-seconds = { $seconds }秒
+size-bytes = { $value } B
+size-kb = { $value } KB
+size-mb = { $value } MB
+size-gb = { $value } GB
 
 # 履歴
 history-entry-title = { $title }（配信者: { $uploader }）
 job-open-folder = フォルダーを開く
 job-retry = 再ダウンロード
 job-copy-path = パスをコピー
+job-copy-logs = ログをコピー
+job-logs-copied = コピーしました
+job-description-more = もっと見る
+job-description-less = 閉じる
+job-tracks-extracted = { $count } 曲を抽出しました
+job-chapters = チャプター
+job-sponsorblock-mark = { $start } – { $end }（{ $category }）
 
 # バリデーション
 error-invalid-url = 正しい X スペースの URL を入力してください。
+url-previously-downloaded = この URL は以前にダウンロード済みです。
+
+# エラーの対処法
+help-check-url = URL が http:// または https:// で始まっているか確認してください。
+help-install-dependency = 不足している依存コマンドをインストールするか、設定の yt-dlp のパスを確認してください。
+help-add-cookie-file = 上級者向け設定で Cookie ファイルを指定してください。
+help-use-proxy = VPN またはプロキシを使ってみてください。
+help-wait-rate-limit = しばらく待ってから再試行するか、レート制限の待機時間を長くしてください。
+help-supported-sites = 対応サイトは https://github.com/yt-dlp/yt-dlp#supported-sites を確認してください。
+help-wait-quota = 時間をおくか、別のネットワーク接続や Cookie ファイルを使って再試行してください。
+help-make-executable = yt-dlp に実行権限があるか確認してください。Unix の場合: chmod +x <yt-dlp のパス>
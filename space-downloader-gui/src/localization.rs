@@ -1,5 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 
+use directories::BaseDirs;
+use fluent_bundle::types::{FluentNumber, FluentNumberOptions};
 use fluent_bundle::{FluentArgs, FluentBundle, FluentResource, FluentValue};
 use unic_langid::LanguageIdentifier;
 
@@ -14,33 +18,112 @@ macro_rules! lang {
     };
 }
 
+const DEFAULT_FALLBACK_CHAIN: &[&str] = &["en-US"];
+
 pub struct Localizer {
     current: LanguageIdentifier,
+    fallbacks: Vec<LanguageIdentifier>,
     bundles: HashMap<LanguageIdentifier, FluentBundle<FluentResource>>,
+    /// Languages whose bundle includes a user-supplied translation file.
+    external: HashSet<LanguageIdentifier>,
 }
 
 impl Localizer {
     pub fn new(default_language: &str) -> Self {
+        let mut chain = vec![default_language];
+        chain.extend_from_slice(DEFAULT_FALLBACK_CHAIN);
+        Self::with_fallback_chain(&chain)
+    }
+
+    /// Build a localizer that uses the first language in `langs` and looks up
+    /// missing messages in the remaining languages, in order.
+    pub fn with_fallback_chain(langs: &[&str]) -> Self {
+        Self::with_locales_dir(langs, external_locales_dir().as_deref())
+    }
+
+    /// Like [`Localizer::with_fallback_chain`], additionally loading
+    /// `<lang>/main.ftl` files from `locales_dir`. Messages in those files
+    /// override the embedded ones for the same language.
+    fn with_locales_dir(langs: &[&str], locales_dir: Option<&Path>) -> Self {
         let mut bundles = HashMap::new();
-        for lang in Self::available_languages() {
-            if let Some(bundle) = build_bundle(&lang) {
-                bundles.insert(lang.clone(), bundle);
+        let mut external = HashSet::new();
+        let external_langs = locales_dir.map(external_languages_in).unwrap_or_default();
+        let mut all_langs = Self::available_languages();
+        for lang in &external_langs {
+            if !all_langs.contains(lang) {
+                all_langs.push(lang.clone());
+            }
+        }
+
+        for lang in all_langs {
+            let mut bundle = new_bundle(&lang);
+            let has_embedded = match embedded_source(&lang) {
+                Some(source) => add_source(&mut bundle, source.to_owned(), false).is_ok(),
+                None => false,
+            };
+
+            let mut has_external = false;
+            if let (Some(dir), true) = (locales_dir, external_langs.contains(&lang)) {
+                let path = dir.join(lang.to_string()).join("main.ftl");
+                let loaded = fs::read_to_string(&path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|source| add_source(&mut bundle, source, true));
+                match loaded {
+                    Ok(()) => has_external = true,
+                    Err(error) => {
+                        tracing::warn!("ignoring translation {}: {error}", path.display());
+                    }
+                }
+            }
+
+            if has_embedded || has_external {
+                if has_external {
+                    external.insert(lang.clone());
+                }
+                bundles.insert(lang, bundle);
             }
         }
 
-        let mut current = parse_language(default_language).unwrap_or_else(|| lang!("en-US"));
-        if !bundles.contains_key(&current) {
-            current = lang!("en-US");
+        let mut chain = langs
+            .iter()
+            .filter_map(|lang| parse_language(lang))
+            .filter(|lang| bundles.contains_key(lang));
+        let current = chain.next().unwrap_or_else(|| lang!("en-US"));
+        let mut fallbacks: Vec<LanguageIdentifier> = Vec::new();
+        for lang in chain {
+            if lang != current && !fallbacks.contains(&lang) {
+                fallbacks.push(lang);
+            }
         }
 
-        Self { current, bundles }
+        Self {
+            current,
+            fallbacks,
+            bundles,
+            external,
+        }
     }
 
     pub fn available_languages() -> Vec<LanguageIdentifier> {
         vec![lang!("en-US"), lang!("ja-JP")]
     }
 
-    #[allow(dead_code)]
+    /// Every language that can be selected, embedded or user-supplied.
+    pub fn languages(&self) -> Vec<LanguageIdentifier> {
+        let mut langs: Vec<_> = self.bundles.keys().cloned().collect();
+        langs.sort_by_key(|lang| lang.to_string());
+        langs
+    }
+
+    /// Name to show in a language picker; community translations are marked.
+    pub fn language_label(&self, lang: &LanguageIdentifier) -> String {
+        if self.external.contains(lang) {
+            self.text_with_args("language-community", &[("language", &lang.to_string())])
+        } else {
+            lang.to_string()
+        }
+    }
+
     pub fn set_language(&mut self, language: &str) {
         if let Some(lang) = parse_language(language) {
             if self.bundles.contains_key(&lang) {
@@ -49,7 +132,6 @@ impl Localizer {
         }
     }
 
-    #[allow(dead_code)]
     pub fn current_language(&self) -> &LanguageIdentifier {
         &self.current
     }
@@ -58,23 +140,40 @@ impl Localizer {
         self.format(key, None)
     }
 
+    /// Format `key` with string arguments given as `(name, value)` pairs.
+    pub fn text_with_args(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, *value);
+        }
+        self.format(key, Some(&fluent_args))
+    }
+
+    /// Format `key`, trying the fallback languages when a message is missing.
+    /// A message that resolves with errors (e.g. a missing argument) is only
+    /// used when no language formats cleanly; unresolved placeables are left
+    /// as `{$name}` so the rest of the text still shows.
     pub fn format(&self, key: &str, args: Option<&FluentArgs>) -> String {
-        let bundle = match self.bundles.get(&self.current) {
-            Some(bundle) => bundle,
-            None => return key.to_string(),
-        };
-
-        if let Some(message) = bundle.get_message(key) {
-            if let Some(pattern) = message.value() {
-                let mut errors = Vec::new();
-                let value = bundle.format_pattern(pattern, args, &mut errors);
-                if errors.is_empty() {
-                    return value.to_string();
+        let chain = std::iter::once(&self.current).chain(self.fallbacks.iter());
+        let mut partial = None;
+        for lang in chain {
+            let Some(bundle) = self.bundles.get(lang) else {
+                continue;
+            };
+
+            if let Some(message) = bundle.get_message(key) {
+                if let Some(pattern) = message.value() {
+                    let mut errors = Vec::new();
+                    let value = bundle.format_pattern(pattern, args, &mut errors);
+                    if errors.is_empty() {
+                        return value.to_string();
+                    }
+                    partial.get_or_insert_with(|| value.to_string());
                 }
             }
         }
 
-        key.to_string()
+        partial.unwrap_or_else(|| key.to_string())
     }
 }
 
@@ -82,18 +181,178 @@ fn parse_language(language: &str) -> Option<LanguageIdentifier> {
     language.parse().ok()
 }
 
-fn build_bundle(lang: &LanguageIdentifier) -> Option<FluentBundle<FluentResource>> {
-    let source = match lang.to_string().as_str() {
-        "ja-JP" => JA_JP,
-        _ => EN_US,
+/// `$XDG_DATA_HOME/space-downloader/locales` or the platform equivalent.
+fn external_locales_dir() -> Option<PathBuf> {
+    BaseDirs::new().map(|dirs| dirs.data_dir().join("space-downloader").join("locales"))
+}
+
+fn external_languages_in(dir: &Path) -> Vec<LanguageIdentifier> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
     };
-    let resource = FluentResource::try_new(source.to_owned()).ok()?;
+    let mut langs: Vec<LanguageIdentifier> = entries
+        .flatten()
+        .filter(|entry| entry.path().join("main.ftl").is_file())
+        .filter_map(|entry| entry.file_name().to_str().and_then(parse_language))
+        .collect();
+    langs.sort_by_key(|lang| lang.to_string());
+    langs
+}
+
+fn embedded_source(lang: &LanguageIdentifier) -> Option<&'static str> {
+    match lang.to_string().as_str() {
+        "en-US" => Some(EN_US),
+        "ja-JP" => Some(JA_JP),
+        _ => None,
+    }
+}
+
+fn new_bundle(lang: &LanguageIdentifier) -> FluentBundle<FluentResource> {
     let mut bundle = FluentBundle::new(vec![lang.clone()]);
-    bundle.add_resource(resource).ok()?;
-    Some(bundle)
+    // Bidi isolation marks render as boxes in iced text widgets.
+    bundle.set_use_isolating(false);
+    bundle
 }
 
-#[allow(dead_code)]
-pub fn format_number(value: f64) -> FluentValue<'static> {
-    FluentValue::from(value)
+fn add_source(
+    bundle: &mut FluentBundle<FluentResource>,
+    source: String,
+    overriding: bool,
+) -> Result<(), String> {
+    let resource = FluentResource::try_new(source)
+        .map_err(|(_, errors)| format!("{} syntax error(s)", errors.len()))?;
+    if overriding {
+        bundle.add_resource_overriding(resource);
+        Ok(())
+    } else {
+        bundle
+            .add_resource(resource)
+            .map_err(|errors| format!("{} duplicate message(s)", errors.len()))
+    }
+}
+
+/// Numeric argument rounded to, and always shown with, `fraction_digits`
+/// decimal places.
+pub fn format_number(value: f64, fraction_digits: usize) -> FluentValue<'static> {
+    let scale = 10f64.powi(fraction_digits as i32);
+    let options = FluentNumberOptions {
+        minimum_fraction_digits: Some(fraction_digits),
+        ..FluentNumberOptions::default()
+    };
+    FluentValue::Number(FluentNumber::new((value * scale).round() / scale, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partial_bundle(lang: &LanguageIdentifier, source: &str) -> FluentBundle<FluentResource> {
+        let resource = FluentResource::try_new(source.to_owned()).unwrap();
+        let mut bundle = FluentBundle::new(vec![lang.clone()]);
+        bundle.add_resource(resource).unwrap();
+        bundle
+    }
+
+    #[test]
+    fn missing_key_falls_back_to_en_us() {
+        let mut localizer = Localizer::new("ja-JP");
+        let ja = lang!("ja-JP");
+        localizer.bundles.insert(
+            ja.clone(),
+            partial_bundle(&ja, "app-title = スペースダウンローダー"),
+        );
+
+        assert_eq!(localizer.text("app-title"), "スペースダウンローダー");
+        assert_eq!(localizer.text("button-download"), "Download");
+        assert_eq!(localizer.text("no-such-key"), "no-such-key");
+    }
+
+    #[test]
+    fn empty_fallback_chain_returns_raw_key() {
+        let mut localizer = Localizer::with_fallback_chain(&["ja-JP"]);
+        let ja = lang!("ja-JP");
+        localizer
+            .bundles
+            .insert(ja.clone(), partial_bundle(&ja, "app-title = Space"));
+
+        assert_eq!(localizer.text("button-download"), "button-download");
+    }
+
+    #[test]
+    fn text_with_args_substitutes_values() {
+        let localizer = Localizer::new("en-US");
+        assert_eq!(
+            localizer.text_with_args("yt-dlp-version", &[("version", "2024.08.06")]),
+            "yt-dlp: 2024.08.06"
+        );
+        assert_eq!(
+            localizer.text_with_args("history-page", &[("page", "2"), ("total", "5")]),
+            "Page 2 of 5"
+        );
+    }
+
+    #[test]
+    fn missing_args_leave_placeholders() {
+        let localizer = Localizer::new("en-US");
+        assert_eq!(
+            localizer.text_with_args("history-page", &[("page", "2")]),
+            "Page 2 of {$total}"
+        );
+    }
+
+    #[test]
+    fn numeric_args_use_fixed_fraction_digits() {
+        let localizer = Localizer::new("en-US");
+        let mut args = FluentArgs::new();
+        args.set("value", format_number(1.5, 2));
+        assert_eq!(localizer.format("size-mb", Some(&args)), "1.50 MB");
+
+        args.set("value", format_number(12.3456, 2));
+        assert_eq!(localizer.format("size-mb", Some(&args)), "12.35 MB");
+    }
+
+    fn write_locale(dir: &Path, lang: &str, source: &str) {
+        let lang_dir = dir.join(lang);
+        fs::create_dir_all(&lang_dir).unwrap();
+        fs::write(lang_dir.join("main.ftl"), source).unwrap();
+    }
+
+    #[test]
+    fn external_locales_are_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        write_locale(dir.path(), "fr-FR", "app-title = Téléchargeur d'espaces");
+        write_locale(dir.path(), "ja-JP", "button-download = 保存");
+
+        assert_eq!(
+            external_languages_in(dir.path()),
+            vec![lang!("fr-FR"), lang!("ja-JP")]
+        );
+
+        let localizer = Localizer::with_locales_dir(&["fr-FR", "en-US"], Some(dir.path()));
+        assert_eq!(localizer.text("app-title"), "Téléchargeur d'espaces");
+        assert_eq!(localizer.text("button-cancel"), "Cancel");
+        assert_eq!(
+            localizer.language_label(&lang!("fr-FR")),
+            "fr-FR (community)"
+        );
+        assert_eq!(
+            localizer.languages(),
+            vec![lang!("en-US"), lang!("fr-FR"), lang!("ja-JP")]
+        );
+
+        // External messages override the embedded ones; the rest are kept.
+        let localizer = Localizer::with_locales_dir(&["ja-JP"], Some(dir.path()));
+        assert_eq!(localizer.text("button-download"), "保存");
+        assert_eq!(localizer.text("button-cancel"), "キャンセル");
+    }
+
+    #[test]
+    fn broken_external_locale_falls_back_to_embedded() {
+        let dir = tempfile::tempdir().unwrap();
+        write_locale(dir.path(), "ja-JP", "button-download = 保存\nbroken = {");
+
+        let localizer = Localizer::with_locales_dir(&["ja-JP"], Some(dir.path()));
+        assert_eq!(localizer.text("button-download"), "ダウンロード");
+        assert_eq!(localizer.language_label(&lang!("ja-JP")), "ja-JP");
+    }
 }
@@ -2,28 +2,36 @@
 
 mod localization;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
+use fluent_bundle::FluentArgs;
+use futures::SinkExt;
 use iced::alignment::{Horizontal, Vertical};
+use iced::clipboard;
 use iced::executor;
 use iced::time;
-use iced::widget::{button, Column, Container, ProgressBar, Row, Scrollable, Text, TextInput};
-use iced::{Element, Length, Subscription, Task, Theme};
-use localization::Localizer;
+use iced::widget::{
+    button, checkbox, container, image, pick_list, progress_bar, Column, Container, Image,
+    ProgressBar, Row, Scrollable, Slider, Text, TextInput,
+};
+use iced::{font, Color, Element, Font, Length, Subscription, Task, Theme};
+use localization::{format_number, Localizer};
 use parking_lot::Mutex;
-use space_downloader_core::config::{Config, ThemePreference};
+use space_downloader_core::config::{
+    AudioFormat, Config, GeoBypassMode, ThemePreference, MAX_CONCURRENCY,
+};
 use space_downloader_core::download::{
-    DownloadEvent, DownloadRequest, DownloadSummary, DownloaderService, JobHandle, JobStatus,
-    ProgressSnapshot,
+    DownloadEvent, DownloadRequest, DownloadSummary, DownloaderService, JobHandle, JobProgress,
+    JobStatus, ProgressSnapshot,
 };
-use space_downloader_core::error::SpaceDownloaderError;
-use space_downloader_core::history::HistoryRepository;
+use space_downloader_core::error::{ErrorHelp, SpaceDownloaderError};
+use space_downloader_core::history::{DownloadHistoryEntry, HistoryQuery, HistoryRepository};
 use space_downloader_core::logging::{LogManager, LogManagerBuilder};
-use tokio::sync::{mpsc, watch};
+use tokio::sync::{broadcast, mpsc, watch};
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
@@ -42,35 +50,180 @@ fn main() -> iced::Result {
 enum SpaceDownloaderApp {
     Ready(Box<AppState>),
     Failed(String),
-    DownloadingYtDlp {
-        downloaded: u64,
-        total: u64,
+    Initializing {
+        steps: Vec<String>,
         localizer: Localizer,
+        /// Invalid config values that were reset, shown once the app is ready.
+        config_warnings: Vec<String>,
     },
 }
 
+const HISTORY_PAGE_SIZE: usize = 20;
+/// How many recent failures "Retry All Failed" queues again.
+const RETRY_FAILED_LIMIT: usize = 10;
+const COPIED_NOTICE_DURATION: Duration = Duration::from_secs(2);
+const DESCRIPTION_PREVIEW_CHARS: usize = 120;
+/// Weight of the newest ETA in [`JobTracker::smoothed_eta`]; lower values
+/// react more slowly but jitter less.
+const ETA_SMOOTHING: f64 = 0.3;
+/// How long the URL input must stay unchanged before its thumbnail is fetched.
+const THUMBNAIL_PREVIEW_DELAY: Duration = Duration::from_secs(1);
+/// Most recent application log lines kept for the Logs tab.
+const LOG_PANEL_CAPACITY: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Downloads,
+    History,
+    Logs,
+}
+
 struct AppState {
     downloader: Arc<DownloaderService>,
+    history: HistoryRepository,
     config: Config,
     localizer: Localizer,
     _log_manager: Option<LogManager>,
+    log_rx: Option<broadcast::Receiver<String>>,
+    log_lines: VecDeque<String>,
     url_input: String,
     url_error: Option<String>,
+    /// Suggested remedy shown below `url_error`.
+    url_error_help: Option<ErrorHelp>,
+    /// Whether the URL in the input was downloaded successfully before.
+    url_previously_downloaded: bool,
+    /// Thumbnail of the URL in the input, once it has been fetched.
+    url_thumbnail: Option<image::Handle>,
+    /// Dismissable notices, e.g. about downloads interrupted last session.
+    notices: Vec<String>,
     jobs: HashMap<Uuid, JobTracker>,
     job_order: Vec<Uuid>,
+    copied_at: HashMap<Uuid, Instant>,
+    active_tab: Tab,
+    history_page: usize,
+    history_entries: Vec<DownloadHistoryEntry>,
+    history_total: usize,
+    history_error: Option<String>,
+    history_count: usize,
+    top_uploaders: Vec<(String, usize)>,
+    history_show_archived: bool,
+    history_formats: Vec<AudioFormat>,
+    history_format_filter: Option<AudioFormat>,
+    /// When set, the History tab lists URLs downloaded more than once instead
+    /// of the paged history.
+    history_duplicates: Option<Vec<(String, usize)>>,
+    /// Successful downloads of the duplicate URL picked in that list.
+    history_duplicate_entries: Option<(String, Vec<DownloadHistoryEntry>)>,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
     UrlChanged(String),
+    UrlHistoryChecked(String, bool),
+    /// The URL input has not changed for [`THUMBNAIL_PREVIEW_DELAY`].
+    UrlSettled(String),
+    UrlThumbnailLoaded(String, image::Handle),
     StartDownload,
+    ConcurrencyChanged(usize),
+    WindowsNoConsoleToggled(bool),
+    PreferFreeFormatsToggled(bool),
+    ForceOverwritesToggled(bool),
+    LiveFromStartToggled(bool),
+    GeoBypassSelected(GeoBypassOption),
+    LanguageSelected(LanguageOption),
+    GeoBypassCountryChanged(String),
+    AgeLimitChanged(String),
+    AudioBitrateChanged(String),
+    NoticesDismissed,
+    EditConfigRequested,
+    /// The edited config and the errors of values that were reset.
+    ConfigReloaded(Result<(Box<Config>, Vec<String>), String>),
     DownloadQueued(SharedJobResult),
     CancelDownload(Uuid),
+    CancelAndDeleteDownload(Uuid),
+    CopyLogsToClipboard(Uuid),
+    ToggleDescription(Uuid),
     OpenFolder(PathBuf),
     Tick,
+    InitStep(InitStep),
     InitializationComplete(Result<Arc<AppInit>, String>),
+    TabSelected(Tab),
+    HistoryPageRequested(usize),
+    HistoryPageLoaded(usize, HistoryPageResult),
+    HistoryCountLoaded(Result<usize, String>),
+    TopUploadersLoaded(Result<Vec<(String, usize)>, String>),
+    HistoryShowArchivedToggled(bool),
+    HistoryFormatsLoaded(Result<Vec<AudioFormat>, String>),
+    HistoryFormatFilterSelected(FormatFilterOption),
+    HistoryArchiveRequested(Uuid, bool),
+    HistoryArchived(Result<(), String>),
+    HistoryForceRetryRequested(Uuid, String),
+    HistoryRetryFailedRequested,
+    HistoryRetryFailedQueued(Result<Vec<SharedJobResult>, String>),
+    HistoryDuplicatesRequested,
+    HistoryDuplicatesLoaded(Result<Vec<(String, usize)>, String>),
+    HistoryDuplicatesClosed,
+    HistoryDuplicateSelected(String),
+    HistoryDuplicateEntriesLoaded(String, Result<Vec<DownloadHistoryEntry>, String>),
+    HistoryDuplicateCleared,
+}
+
+/// Entry in the history format filter; `None` shows every format.
+#[derive(Debug, Clone)]
+struct FormatFilterOption {
+    format: Option<AudioFormat>,
+    label: String,
+}
+
+impl PartialEq for FormatFilterOption {
+    fn eq(&self, other: &Self) -> bool {
+        self.format == other.format
+    }
+}
+
+impl fmt::Display for FormatFilterOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.label)
+    }
 }
 
+/// Entry in the geo bypass picker; the country itself is edited separately.
+#[derive(Debug, Clone)]
+struct GeoBypassOption {
+    mode: GeoBypassMode,
+    label: String,
+}
+
+impl PartialEq for GeoBypassOption {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(&self.mode) == std::mem::discriminant(&other.mode)
+    }
+}
+
+impl fmt::Display for GeoBypassOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.label)
+    }
+}
+
+/// Entry in the language picker.
+#[derive(Debug, Clone, PartialEq)]
+struct LanguageOption {
+    language: String,
+    label: String,
+}
+
+impl fmt::Display for LanguageOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.label)
+    }
+}
+
+/// Country preselected when switching the geo bypass picker to a country.
+const DEFAULT_GEO_BYPASS_COUNTRY: &str = "US";
+
+type HistoryPageResult = Result<(Vec<DownloadHistoryEntry>, usize), String>;
+
 type SharedJobResult = Result<SharedJobHandle, Arc<SpaceDownloaderError>>;
 
 #[derive(Clone)]
@@ -116,11 +269,22 @@ struct JobTracker {
     progress_rx: watch::Receiver<Option<ProgressSnapshot>>,
     events_rx: Option<mpsc::Receiver<DownloadEvent>>,
     cancel_token: CancellationToken,
+    scheduled_at: Option<chrono::DateTime<chrono::Utc>>,
     last_status: JobStatus,
     last_progress: Option<ProgressSnapshot>,
+    /// Moving average of the reported ETA, used for the completion time.
+    smoothed_eta: Option<Duration>,
+    /// Bytes received according to the native network monitor, which is
+    /// preferred over the byte count parsed from yt-dlp output.
+    network_received_bytes: Option<u64>,
     logs: Vec<String>,
+    /// Suggested remedy for the failure, once the job has failed.
+    failure_help: Option<ErrorHelp>,
+    /// `(start_secs, end_secs, category)` of the SponsorBlock segments found.
+    sponsorblock_marks: Vec<(u64, u64, String)>,
     summary: Option<DownloadSummary>,
     folder_opened: bool,
+    description_expanded: bool,
 }
 
 impl JobTracker {
@@ -129,6 +293,7 @@ impl JobTracker {
         let progress_rx = handle.progress_receiver();
         let events_rx = handle.take_events();
         let cancel_token = handle.cancellation_token();
+        let scheduled_at = handle.scheduled_at();
         let last_status = *status_rx.borrow();
 
         Self {
@@ -138,11 +303,17 @@ impl JobTracker {
             progress_rx,
             events_rx,
             cancel_token,
+            scheduled_at,
             last_status,
             last_progress: None,
+            smoothed_eta: None,
+            network_received_bytes: None,
             logs: Vec::new(),
+            failure_help: None,
+            sponsorblock_marks: Vec::new(),
             summary: None,
             folder_opened: false,
+            description_expanded: false,
         }
     }
 
@@ -164,6 +335,27 @@ impl JobTracker {
                             self.logs.remove(0);
                         }
                     }
+                    // The retry loop logs the cooldown when it starts.
+                    DownloadEvent::RateLimited(_) => {}
+                    DownloadEvent::NetworkProgress { received_bytes, .. } => {
+                        self.network_received_bytes = Some(received_bytes);
+                    }
+                    DownloadEvent::SponsorBlockMark {
+                        start_secs,
+                        end_secs,
+                        category,
+                    } => {
+                        self.logs.push(format!(
+                            "[SponsorBlock] {category}: {} - {}",
+                            format_timestamp(start_secs),
+                            format_timestamp(end_secs)
+                        ));
+                        if self.logs.len() > 100 {
+                            self.logs.remove(0);
+                        }
+                        self.sponsorblock_marks
+                            .push((start_secs, end_secs, category));
+                    }
                     DownloadEvent::Completed(summary) => {
                         self.summary = Some(summary.clone());
                         self.last_status = summary.status;
@@ -178,8 +370,9 @@ impl JobTracker {
                             }
                         }
                     }
-                    DownloadEvent::Failed(message) => {
+                    DownloadEvent::Failed { message, help } => {
                         self.last_status = JobStatus::Failed;
+                        self.failure_help = help;
                         self.logs.push(message);
                         if self.logs.len() > 100 {
                             self.logs.remove(0);
@@ -197,10 +390,38 @@ impl JobTracker {
         if let Some(progress) = self.progress_rx.borrow().clone() {
             self.last_progress = Some(progress);
         }
+        self.update_smoothed_eta();
 
         folder_to_open
     }
 
+    fn update_smoothed_eta(&mut self) {
+        let eta = self
+            .last_progress
+            .as_ref()
+            .and_then(|progress| progress.eta);
+        self.smoothed_eta = match (self.smoothed_eta, eta) {
+            (Some(previous), Some(eta)) => {
+                Some(previous.mul_f64(1.0 - ETA_SMOOTHING) + eta.mul_f64(ETA_SMOOTHING))
+            }
+            (_, eta) => eta,
+        };
+    }
+
+    /// When the download should finish, based on the smoothed ETA.
+    fn estimated_completion_at(&self) -> Option<SystemTime> {
+        self.last_progress.as_ref()?;
+        Some(SystemTime::now() + self.smoothed_eta?)
+    }
+
+    fn display_progress(&self) -> Option<ProgressSnapshot> {
+        let mut progress = self.last_progress.clone()?;
+        if self.network_received_bytes.is_some() {
+            progress.downloaded_bytes = self.network_received_bytes;
+        }
+        Some(progress)
+    }
+
     fn is_finished(&self) -> bool {
         matches!(
             self.last_status,
@@ -212,21 +433,53 @@ impl JobTracker {
         self.cancel_token.cancel();
     }
 
-    fn view(&self, localizer: &Localizer) -> Element<'_, Message> {
+    fn copy_log_to_clipboard(&self) -> Task<Message> {
+        clipboard::write(self.logs.join("\n"))
+    }
+
+    fn view(&self, localizer: &Localizer, logs_copied: bool) -> Element<'_, Message> {
+        let mut status_text = format_status(self.last_status, localizer);
+        if let (JobStatus::Scheduled, Some(at)) = (self.last_status, self.scheduled_at) {
+            let remaining = (at - chrono::Utc::now()).to_std().unwrap_or_default();
+            status_text.push_str(" • ");
+            status_text.push_str(&localizer.text_with_args(
+                "progress-starts-in",
+                &[("eta", &format_eta(remaining, localizer))],
+            ));
+        }
         let mut column = Column::new()
             .spacing(6)
             .push(Text::new(self.url.clone()).size(14))
-            .push(Text::new(format_status(self.last_status, localizer)).size(12));
-
-        if let Some(progress) = &self.last_progress {
-            if let Some(percent) = progress.percent {
-                column = column.push(ProgressBar::new(
-                    0.0..=1.0,
-                    (percent / 100.0).clamp(0.0, 1.0),
-                ));
+            .push(Text::new(status_text).size(12));
+
+        if let Some(progress) = &self.display_progress() {
+            let ratio = progress
+                .percent
+                .map(|percent| (percent / 100.0).clamp(0.0, 1.0))
+                .or_else(|| progress.transfer_ratio())
+                .or_else(|| {
+                    progress
+                        .synthetic_percent()
+                        .map(|percent| (percent / 100.0).clamp(0.0, 1.0))
+                });
+            if let Some(ratio) = ratio {
+                let tone = progress_tone(self.last_status, progress.speed_bytes_per_sec);
+                column = column.push(
+                    ProgressBar::new(0.0..=1.0, ratio)
+                        .style(move |theme| progress_bar_style(theme, tone)),
+                );
             }
 
-            if let Some(progress_text) = format_progress(progress) {
+            if let Some(mut progress_text) = format_progress(progress, localizer) {
+                if let Some(completes_at) = self.estimated_completion_at() {
+                    let time = chrono::DateTime::<chrono::Local>::from(completes_at)
+                        .format("%H:%M")
+                        .to_string();
+                    progress_text.push_str(" • ");
+                    progress_text.push_str(
+                        &localizer.text_with_args("progress-completes-at", &[("time", &time)]),
+                    );
+                }
                 column = column.push(Text::new(progress_text).size(12));
             }
         }
@@ -235,20 +488,80 @@ impl JobTracker {
             if let Some(path) = &summary.file_path {
                 column = column.push(Text::new(path.to_string_lossy().to_string()).size(12));
             }
+            if !summary.extra_files.is_empty() {
+                let count = summary.extra_files.len().to_string();
+                column = column.push(
+                    Text::new(
+                        localizer.text_with_args("job-tracks-extracted", &[("count", &count)]),
+                    )
+                    .size(12),
+                );
+            }
+            if let Some(description) = &summary.description {
+                let (shown, truncated) = if self.description_expanded {
+                    (description.clone(), false)
+                } else {
+                    preview_description(description)
+                };
+                let mut description_row = Row::new()
+                    .spacing(8)
+                    .push(Text::new(shown).size(12).width(Length::Fill));
+                if truncated || self.description_expanded {
+                    let label = if self.description_expanded {
+                        "job-description-less"
+                    } else {
+                        "job-description-more"
+                    };
+                    description_row = description_row.push(
+                        button(Text::new(localizer.text(label)).size(12))
+                            .style(button::text)
+                            .on_press(Message::ToggleDescription(self.id)),
+                    );
+                }
+                column = column.push(description_row);
+            }
+            if !self.sponsorblock_marks.is_empty() {
+                let chapters = self.sponsorblock_marks.iter().fold(
+                    Column::new()
+                        .spacing(2)
+                        .push(Text::new(localizer.text("job-chapters")).size(12)),
+                    |chapters, (start_secs, end_secs, category)| {
+                        let start = format_timestamp(*start_secs);
+                        let end = format_timestamp(*end_secs);
+                        chapters.push(
+                            Text::new(localizer.text_with_args(
+                                "job-sponsorblock-mark",
+                                &[("category", category), ("start", &start), ("end", &end)],
+                            ))
+                            .size(12),
+                        )
+                    },
+                );
+                column = column.push(chapters);
+            }
         }
 
         if let Some(last) = self.logs.last() {
             column = column.push(Text::new(last.clone()).size(12));
         }
+        if let Some(help) = self.failure_help {
+            column = column.push(help_text(help, localizer));
+        }
 
         // Button row for actions
         let mut button_row = Row::new().spacing(8);
 
         if !self.is_finished() {
-            button_row = button_row.push(
-                button(Text::new(localizer.text("button-cancel")))
-                    .on_press(Message::CancelDownload(self.id)),
-            );
+            button_row = button_row
+                .push(
+                    button(Text::new(localizer.text("button-cancel")))
+                        .on_press(Message::CancelDownload(self.id)),
+                )
+                .push(
+                    button(Text::new(localizer.text("button-cancel-delete")))
+                        .style(button::danger)
+                        .on_press(Message::CancelAndDeleteDownload(self.id)),
+                );
         } else if let Some(summary) = &self.summary {
             // Show "Open Folder" button if download completed successfully
             if matches!(self.last_status, JobStatus::Succeeded) {
@@ -263,7 +576,17 @@ impl JobTracker {
             }
         }
 
-        column = column.push(button_row);
+        button_row = button_row.push(
+            button(Text::new(localizer.text("job-copy-logs")))
+                .style(button::secondary)
+                .on_press(Message::CopyLogsToClipboard(self.id)),
+        );
+
+        if logs_copied {
+            button_row = button_row.push(Text::new(localizer.text("job-logs-copied")).size(12));
+        }
+
+        column = column.push(button_row.align_y(Vertical::Center));
 
         Container::new(column)
             .padding(12)
@@ -272,18 +595,60 @@ impl JobTracker {
     }
 }
 
+fn help_text<'a>(help: ErrorHelp, localizer: &Localizer) -> Text<'a> {
+    let key = match help {
+        ErrorHelp::CheckUrl => "help-check-url",
+        ErrorHelp::InstallDependency => "help-install-dependency",
+        ErrorHelp::AddCookieFile => "help-add-cookie-file",
+        ErrorHelp::UseProxy => "help-use-proxy",
+        ErrorHelp::WaitForRateLimit => "help-wait-rate-limit",
+        ErrorHelp::SupportedSites => "help-supported-sites",
+        ErrorHelp::WaitForQuota => "help-wait-quota",
+        ErrorHelp::MakeExecutable => "help-make-executable",
+    };
+    Text::new(localizer.text(key)).size(12).font(Font {
+        style: font::Style::Italic,
+        ..Font::DEFAULT
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InitStep {
+    CheckingDependencies,
+    OpeningHistory,
+    InitializingLogger,
+}
+
+impl InitStep {
+    fn label_key(self) -> &'static str {
+        match self {
+            InitStep::CheckingDependencies => "init-checking-dependencies",
+            InitStep::OpeningHistory => "init-opening-history",
+            InitStep::InitializingLogger => "init-initializing-logger",
+        }
+    }
+}
+
 struct AppInit {
     downloader: Arc<DownloaderService>,
+    history: HistoryRepository,
     config: Config,
     log_manager: Option<LogManager>,
+    /// Jobs that were still running when the app last exited.
+    interrupted: Vec<JobProgress>,
+    /// History rows left unfinished by the last run, now marked failed.
+    interrupted_count: usize,
 }
 
 impl Clone for AppInit {
     fn clone(&self) -> Self {
         Self {
             downloader: self.downloader.clone(),
+            history: self.history.clone(),
             config: self.config.clone(),
             log_manager: None, // LogManager is not cloneable, so we set it to None
+            interrupted: self.interrupted.clone(),
+            interrupted_count: self.interrupted_count,
         }
     }
 }
@@ -298,8 +663,8 @@ impl std::fmt::Debug for AppInit {
 
 impl SpaceDownloaderApp {
     fn initialize() -> (Self, Task<Message>) {
-        let (config, _) = match Config::load_or_default(None) {
-            Ok(cfg) => cfg,
+        let (config, _, warnings) = match Config::load_with_warnings(None) {
+            Ok(loaded) => loaded,
             Err(err) => {
                 return (
                     SpaceDownloaderApp::Failed(format!("Failed to load config: {}", err)),
@@ -309,16 +674,31 @@ impl SpaceDownloaderApp {
         };
 
         let localizer = Localizer::new(&config.general.language);
+        let config_warnings = warnings
+            .iter()
+            .map(|warning| {
+                localizer.text_with_args("notice-config-reset", &[("error", &warning.to_string())])
+            })
+            .collect();
+
+        let initialization = iced::stream::channel(4, move |mut output| async move {
+            let result = async_initialize(config, None, |step| {
+                output.try_send(Message::InitStep(step)).ok();
+            })
+            .await;
+            output
+                .send(Message::InitializationComplete(result.map(Arc::new)))
+                .await
+                .ok();
+        });
 
         (
-            SpaceDownloaderApp::DownloadingYtDlp {
-                downloaded: 0,
-                total: 0,
+            SpaceDownloaderApp::Initializing {
+                steps: Vec::new(),
                 localizer,
+                config_warnings,
             },
-            Task::perform(async_initialize(config), |result| {
-                Message::InitializationComplete(result.map(Arc::new))
-            }),
+            Task::stream(initialization),
         )
     }
 
@@ -326,9 +706,7 @@ impl SpaceDownloaderApp {
         match self {
             SpaceDownloaderApp::Failed(_) => "Space Downloader".into(),
             SpaceDownloaderApp::Ready(state) => state.localizer.text("app-title"),
-            SpaceDownloaderApp::DownloadingYtDlp { localizer, .. } => {
-                localizer.text("app-title")
-            }
+            SpaceDownloaderApp::Initializing { localizer, .. } => localizer.text("app-title"),
         }
     }
 
@@ -336,22 +714,29 @@ impl SpaceDownloaderApp {
         match self {
             SpaceDownloaderApp::Failed(_) => Task::none(),
             SpaceDownloaderApp::Ready(state) => state.update(message),
-            SpaceDownloaderApp::DownloadingYtDlp {
-                downloaded: _,
-                total: _,
-                localizer: _,
+            SpaceDownloaderApp::Initializing {
+                steps,
+                localizer,
+                config_warnings,
             } => match message {
+                Message::InitStep(step) => {
+                    steps.push(localizer.text(step.label_key()));
+                    Task::none()
+                }
                 Message::InitializationComplete(result) => match result {
                     Ok(init) => {
                         let init = Arc::try_unwrap(init).unwrap_or_else(|arc| (*arc).clone());
-                        *self = SpaceDownloaderApp::Ready(Box::new(AppState::from(init)));
-                        Task::none()
+                        let mut state = AppState::from(init);
+                        state.notices.append(config_warnings);
+                        let task = state.refresh_history_count();
+                        *self = SpaceDownloaderApp::Ready(Box::new(state));
+                        task
                     }
                     Err(error) => {
                         *self = SpaceDownloaderApp::Failed(error);
                         Task::none()
                     }
-                }
+                },
                 _ => Task::none(),
             },
         }
@@ -366,41 +751,25 @@ impl SpaceDownloaderApp {
                 .align_y(Vertical::Center)
                 .into(),
             SpaceDownloaderApp::Ready(state) => state.view(),
-            SpaceDownloaderApp::DownloadingYtDlp {
-                downloaded,
-                total,
-                localizer: _,
+            SpaceDownloaderApp::Initializing {
+                steps, localizer, ..
             } => {
-                let progress = if *total > 0 {
-                    *downloaded as f32 / *total as f32
-                } else {
-                    0.0
-                };
+                let mut column = Column::new()
+                    .spacing(8)
+                    .align_x(Horizontal::Center)
+                    .push(Text::new(localizer.text("init-title")).size(24));
 
-                let size_text = if *total > 0 {
-                    format!(
-                        "{:.1} MB / {:.1} MB",
-                        *downloaded as f64 / 1024.0 / 1024.0,
-                        *total as f64 / 1024.0 / 1024.0
-                    )
-                } else {
-                    "Initializing...".to_string()
-                };
+                for step in steps {
+                    column = column.push(Text::new(step.clone()));
+                }
 
-                Container::new(
-                    Column::new()
-                        .spacing(16)
-                        .align_x(Horizontal::Center)
-                        .push(Text::new("Downloading yt-dlp...").size(24))
-                        .push(ProgressBar::new(0.0..=1.0, progress))
-                        .push(Text::new(size_text)),
-                )
-                .width(Length::Fill)
-                .height(Length::Fill)
-                .align_x(Horizontal::Center)
-                .align_y(Vertical::Center)
-                .padding(48)
-                .into()
+                Container::new(column)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .align_x(Horizontal::Center)
+                    .align_y(Vertical::Center)
+                    .padding(48)
+                    .into()
             }
         }
     }
@@ -411,7 +780,7 @@ impl SpaceDownloaderApp {
                 time::every(Duration::from_millis(500)).map(|_| Message::Tick)
             }
             SpaceDownloaderApp::Failed(_) => Subscription::none(),
-            SpaceDownloaderApp::DownloadingYtDlp { .. } => Subscription::none(),
+            SpaceDownloaderApp::Initializing { .. } => Subscription::none(),
         }
     }
 
@@ -423,7 +792,7 @@ impl SpaceDownloaderApp {
                 ThemePreference::System => Theme::default(),
             },
             SpaceDownloaderApp::Failed(_) => Theme::default(),
-            SpaceDownloaderApp::DownloadingYtDlp { .. } => Theme::default(),
+            SpaceDownloaderApp::Initializing { .. } => Theme::default(),
         }
     }
 }
@@ -431,15 +800,53 @@ impl SpaceDownloaderApp {
 impl AppState {
     fn from(init: AppInit) -> Self {
         let localizer = Localizer::new(&init.config.general.language);
+        // Both describe the same downloads, so the count is only shown when no
+        // progress was saved for them.
+        let notices = if init.interrupted.is_empty() {
+            (init.interrupted_count > 0)
+                .then(|| {
+                    localizer.text_with_args(
+                        "notice-interrupted-count",
+                        &[("count", &init.interrupted_count.to_string())],
+                    )
+                })
+                .into_iter()
+                .collect()
+        } else {
+            init.interrupted
+                .iter()
+                .map(|progress| interrupted_notice(progress, &localizer))
+                .collect()
+        };
         Self {
             downloader: init.downloader,
+            history: init.history,
             config: init.config,
             localizer,
+            log_rx: init.log_manager.as_ref().map(LogManager::subscribe),
+            log_lines: VecDeque::with_capacity(LOG_PANEL_CAPACITY),
             _log_manager: init.log_manager,
             url_input: String::new(),
             url_error: None,
+            url_error_help: None,
+            url_previously_downloaded: false,
+            url_thumbnail: None,
+            notices,
             jobs: HashMap::new(),
             job_order: Vec::new(),
+            copied_at: HashMap::new(),
+            active_tab: Tab::Downloads,
+            history_page: 0,
+            history_entries: Vec::new(),
+            history_total: 0,
+            history_error: None,
+            history_count: 0,
+            top_uploaders: Vec::new(),
+            history_show_archived: false,
+            history_formats: Vec::new(),
+            history_format_filter: None,
+            history_duplicates: None,
+            history_duplicate_entries: None,
         }
     }
 
@@ -448,6 +855,64 @@ impl AppState {
             Message::UrlChanged(value) => {
                 self.url_input = value;
                 self.url_error = None;
+                self.url_error_help = None;
+                self.url_previously_downloaded = false;
+                self.url_thumbnail = None;
+                let url = self.url_input.trim().to_string();
+                if url.is_empty() {
+                    return Task::none();
+                }
+                let settled = Task::perform(
+                    {
+                        let url = url.clone();
+                        async move {
+                            tokio::time::sleep(THUMBNAIL_PREVIEW_DELAY).await;
+                            url
+                        }
+                    },
+                    Message::UrlSettled,
+                );
+                let history = self.history.clone();
+                let checked = Task::perform(
+                    async move {
+                        let downloaded = tokio::task::spawn_blocking({
+                            let url = url.clone();
+                            move || history.url_was_downloaded(&url)
+                        })
+                        .await;
+                        let downloaded = matches!(downloaded, Ok(Ok(true)));
+                        (url, downloaded)
+                    },
+                    |(url, downloaded)| Message::UrlHistoryChecked(url, downloaded),
+                );
+                Task::batch([checked, settled])
+            }
+            Message::UrlHistoryChecked(url, downloaded) => {
+                // Ignore answers for text that has since been edited.
+                if url == self.url_input.trim() {
+                    self.url_previously_downloaded = downloaded;
+                }
+                Task::none()
+            }
+            Message::UrlSettled(url) => {
+                if url != self.url_input.trim() {
+                    return Task::none();
+                }
+                let advanced = self.config.advanced.clone();
+                let request = build_download_request(&self.config, url.clone());
+                // The preview is optional, so failures are not shown.
+                Task::future(async move { request.preview_thumbnail(&advanced).await.ok() })
+                    .and_then(move |bytes| {
+                        Task::done(Message::UrlThumbnailLoaded(
+                            url.clone(),
+                            image::Handle::from_bytes(bytes),
+                        ))
+                    })
+            }
+            Message::UrlThumbnailLoaded(url, thumbnail) => {
+                if url == self.url_input.trim() {
+                    self.url_thumbnail = Some(thumbnail);
+                }
                 Task::none()
             }
             Message::StartDownload => self.start_download(),
@@ -461,20 +926,154 @@ impl AppState {
                             self.jobs.insert(id, tracker);
                             self.url_input.clear();
                             self.url_error = None;
+                            self.url_error_help = None;
+                            self.url_thumbnail = None;
                         }
                     }
                     Err(error) => {
                         self.url_error = Some(error.to_string());
+                        self.url_error_help = match error.as_ref() {
+                            SpaceDownloaderError::Download(error) => error.help(),
+                            _ => None,
+                        };
                     }
                 }
                 Task::none()
             }
+            Message::ConcurrencyChanged(concurrency) => {
+                // Applied immediately but only kept in memory; the config file
+                // is left untouched.
+                self.config.download.concurrency = concurrency;
+                let downloader = self.downloader.clone();
+                Task::future(async move {
+                    downloader.set_max_concurrency(concurrency).await;
+                })
+                .discard()
+            }
+            // Like the concurrency slider, these settings are only kept in memory.
+            Message::WindowsNoConsoleToggled(hide) => {
+                self.apply_settings(|config| config.advanced.windows_no_console = hide)
+            }
+            Message::PreferFreeFormatsToggled(prefer) => {
+                self.apply_settings(|config| config.download.prefer_free_formats = prefer)
+            }
+            Message::ForceOverwritesToggled(force) => {
+                self.apply_settings(|config| config.advanced.force_overwrites = force)
+            }
+            Message::LiveFromStartToggled(live) => {
+                self.apply_settings(|config| config.advanced.live_from_start = live)
+            }
+            Message::GeoBypassSelected(option) => {
+                // Keep the country typed so far when toggling back and forth.
+                if std::mem::discriminant(&option.mode)
+                    == std::mem::discriminant(&self.config.download.geo_bypass)
+                {
+                    return Task::none();
+                }
+                self.apply_settings(|config| config.download.geo_bypass = option.mode)
+            }
+            Message::LanguageSelected(option) => {
+                // Like the other settings in this row, only kept in memory.
+                self.localizer.set_language(&option.language);
+                self.config.general.language = option.language;
+                Task::none()
+            }
+            Message::GeoBypassCountryChanged(country) => {
+                let country: String = country
+                    .chars()
+                    .filter(char::is_ascii_alphabetic)
+                    .take(2)
+                    .collect::<String>()
+                    .to_ascii_uppercase();
+                self.apply_settings(|config| {
+                    config.download.geo_bypass = GeoBypassMode::ForceCountry(country);
+                })
+            }
+            Message::AgeLimitChanged(age) => {
+                let digits: String = age.chars().filter(char::is_ascii_digit).take(2).collect();
+                self.apply_settings(|config| config.download.age_limit = digits.parse().ok())
+            }
+            Message::AudioBitrateChanged(kbps) => {
+                // Empty means the best available quality.
+                let digits: String = kbps.chars().filter(char::is_ascii_digit).take(3).collect();
+                self.apply_settings(|config| {
+                    config.download.audio_bitrate_kbps = digits.parse().ok();
+                })
+            }
+            Message::NoticesDismissed => {
+                self.notices.clear();
+                Task::none()
+            }
+            Message::EditConfigRequested => Task::perform(
+                async {
+                    tokio::task::spawn_blocking(|| {
+                        let path = Config::default_path();
+                        Config::open_in_editor(&path).map_err(|err| err.to_string())?;
+                        Config::load_with_warnings(Some(&path))
+                            .map(|(config, _, warnings)| {
+                                let warnings = warnings.iter().map(ToString::to_string).collect();
+                                (Box::new(config), warnings)
+                            })
+                            .map_err(|err| err.to_string())
+                    })
+                    .await
+                    .map_err(|err| err.to_string())?
+                },
+                Message::ConfigReloaded,
+            ),
+            Message::ConfigReloaded(result) => match result {
+                Ok((config, warnings)) => {
+                    self.localizer = Localizer::new(&config.general.language);
+                    for warning in warnings {
+                        self.notices.push(
+                            self.localizer
+                                .text_with_args("notice-config-reset", &[("error", &warning)]),
+                        );
+                    }
+                    self.config = (*config).clone();
+                    let downloader = self.downloader.clone();
+                    Task::future(async move {
+                        downloader.update_config(*config).await;
+                    })
+                    .discard()
+                }
+                Err(error) => {
+                    tracing::error!("Failed to edit config: {error}");
+                    self.notices.push(
+                        self.localizer
+                            .text_with_args("notice-config-reload-failed", &[("error", &error)]),
+                    );
+                    Task::none()
+                }
+            },
             Message::CancelDownload(id) => {
                 if let Some(job) = self.jobs.get(&id) {
                     job.cancel();
                 }
                 Task::none()
             }
+            Message::CancelAndDeleteDownload(id) => {
+                let downloader = self.downloader.clone();
+                Task::future(async move {
+                    if let Err(error) = downloader.abort_and_delete(id).await {
+                        tracing::error!("Failed to delete partial files: {error}");
+                    }
+                })
+                .discard()
+            }
+            Message::CopyLogsToClipboard(id) => match self.jobs.get(&id) {
+                Some(job) => {
+                    self.copied_at.insert(id, Instant::now());
+                    job.copy_log_to_clipboard()
+                }
+                None => Task::none(),
+            },
+            Message::ToggleDescription(id) => {
+                if let Some(job) = self.jobs.get_mut(&id) {
+                    job.description_expanded = !job.description_expanded;
+                }
+                Task::none()
+            }
             Message::OpenFolder(path) => {
                 if let Err(e) = open_folder_in_explorer(&path) {
                     tracing::error!("Failed to open folder: {}", e);
@@ -482,26 +1081,315 @@ impl AppState {
                 Task::none()
             }
             Message::Tick => {
+                self.copied_at
+                    .retain(|_, copied_at| copied_at.elapsed() < COPIED_NOTICE_DURATION);
+                self.drain_log_lines();
+                let mut job_finished = false;
                 for id in &self.job_order {
                     if let Some(job) = self.jobs.get_mut(id) {
+                        let was_finished = job.is_finished();
                         if let Some(folder_path) = job.poll() {
                             // Auto-open folder on completion
                             if let Err(e) = open_folder_in_explorer(&folder_path) {
                                 tracing::error!("Failed to auto-open folder: {}", e);
                             }
                         }
+                        job_finished |= !was_finished && job.is_finished();
+                    }
+                }
+                if job_finished {
+                    self.refresh_history_count()
+                } else {
+                    Task::none()
+                }
+            }
+            Message::InitStep(_) | Message::InitializationComplete(_) => {
+                // These messages are handled in the top-level update
+                Task::none()
+            }
+            Message::TabSelected(tab) => {
+                self.active_tab = tab;
+                if tab == Tab::History {
+                    Task::batch([
+                        self.load_history_page(self.history_page),
+                        self.load_history_formats(),
+                        self.load_top_uploaders(),
+                    ])
+                } else {
+                    Task::none()
+                }
+            }
+            Message::HistoryPageRequested(page) => self.load_history_page(page),
+            Message::HistoryPageLoaded(page, result) => {
+                match result {
+                    Ok((entries, total)) => {
+                        self.history_page = page;
+                        self.history_entries = entries;
+                        self.history_total = total;
+                        if !self.history_show_archived && self.history_format_filter.is_none() {
+                            self.history_count = total;
+                        }
+                        self.history_error = None;
+                    }
+                    Err(error) => {
+                        self.history_error = Some(error);
+                    }
+                }
+                Task::none()
+            }
+            Message::HistoryCountLoaded(result) => {
+                match result {
+                    Ok(count) => self.history_count = count,
+                    Err(error) => tracing::warn!("Failed to count history entries: {}", error),
+                }
+                Task::none()
+            }
+            Message::TopUploadersLoaded(result) => {
+                match result {
+                    Ok(uploaders) => self.top_uploaders = uploaders,
+                    Err(error) => tracing::warn!("Failed to load uploader stats: {}", error),
+                }
+                Task::none()
+            }
+            Message::HistoryShowArchivedToggled(show) => {
+                self.history_show_archived = show;
+                self.load_history_page(0)
+            }
+            Message::HistoryFormatsLoaded(result) => {
+                match result {
+                    Ok(formats) => self.history_formats = formats,
+                    Err(error) => tracing::warn!("Failed to load history formats: {}", error),
+                }
+                Task::none()
+            }
+            Message::HistoryFormatFilterSelected(option) => {
+                self.history_format_filter = option.format;
+                self.load_history_page(0)
+            }
+            Message::HistoryArchiveRequested(job_id, archived) => {
+                let history = self.history.clone();
+                Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || history.mark_archived(job_id, archived))
+                            .await
+                            .map_err(|err| err.to_string())?
+                            .map_err(|err| err.to_string())
+                    },
+                    Message::HistoryArchived,
+                )
+            }
+            Message::HistoryForceRetryRequested(job_id, url) => {
+                let history = self.history.clone();
+                let downloader = self.downloader.clone();
+                let mut request = build_download_request(&self.config, url);
+                request.force_redownload = true;
+                self.active_tab = Tab::Downloads;
+                Task::perform(
+                    async move {
+                        let removed = tokio::task::spawn_blocking(move || {
+                            history.remove_from_archive(job_id)
+                        })
+                        .await;
+                        if let Ok(Err(error)) = removed {
+                            tracing::warn!("Failed to update download archive: {}", error);
+                        }
+                        queue_download(downloader, request).await
+                    },
+                    Message::DownloadQueued,
+                )
+            }
+            Message::HistoryRetryFailedRequested => {
+                let history = self.history.clone();
+                let downloader = self.downloader.clone();
+                self.active_tab = Tab::Downloads;
+                Task::perform(
+                    async move {
+                        let results = downloader
+                            .requeue_failed(&history, RETRY_FAILED_LIMIT)
+                            .await
+                            .map_err(|err| err.to_string())?;
+                        Ok(results
+                            .into_iter()
+                            .map(|result| {
+                                result
+                                    .map(SharedJobHandle::new)
+                                    .map_err(|err| Arc::new(SpaceDownloaderError::from(err)))
+                            })
+                            .collect())
+                    },
+                    Message::HistoryRetryFailedQueued,
+                )
+            }
+            Message::HistoryRetryFailedQueued(result) => {
+                match result {
+                    Ok(results) => {
+                        for result in results {
+                            match result {
+                                Ok(shared) => {
+                                    if let Some(handle) = shared.take() {
+                                        let id = shared.id();
+                                        self.job_order.push(id);
+                                        self.jobs.insert(id, JobTracker::new(handle));
+                                    }
+                                }
+                                Err(error) => {
+                                    tracing::warn!("Failed to requeue download: {}", error);
+                                }
+                            }
+                        }
+                        self.history_error = None;
                     }
+                    Err(error) => self.history_error = Some(error),
                 }
                 Task::none()
             }
-            Message::InitializationComplete(_) => {
-                // This message is handled in the top-level update
+            Message::HistoryDuplicatesRequested => {
+                let history = self.history.clone();
+                Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || history.duplicate_downloads())
+                            .await
+                            .map_err(|err| err.to_string())?
+                            .map_err(|err| err.to_string())
+                    },
+                    Message::HistoryDuplicatesLoaded,
+                )
+            }
+            Message::HistoryDuplicatesLoaded(result) => {
+                match result {
+                    Ok(duplicates) => {
+                        self.history_duplicates = Some(duplicates);
+                        self.history_duplicate_entries = None;
+                        self.history_error = None;
+                    }
+                    Err(error) => self.history_error = Some(error),
+                }
+                Task::none()
+            }
+            Message::HistoryDuplicatesClosed => {
+                self.history_duplicates = None;
+                self.history_duplicate_entries = None;
+                self.load_history_page(self.history_page)
+            }
+            Message::HistoryDuplicateSelected(url) => {
+                let history = self.history.clone();
+                Task::perform(
+                    {
+                        let url = url.clone();
+                        async move {
+                            tokio::task::spawn_blocking(move || {
+                                history.succeeded_downloads_of(&url)
+                            })
+                            .await
+                            .map_err(|err| err.to_string())?
+                            .map_err(|err| err.to_string())
+                        }
+                    },
+                    move |result| Message::HistoryDuplicateEntriesLoaded(url.clone(), result),
+                )
+            }
+            Message::HistoryDuplicateEntriesLoaded(url, result) => {
+                match result {
+                    Ok(entries) => self.history_duplicate_entries = Some((url, entries)),
+                    Err(error) => self.history_error = Some(error),
+                }
                 Task::none()
             }
+            Message::HistoryDuplicateCleared => {
+                self.history_duplicate_entries = None;
+                Task::none()
+            }
+            Message::HistoryArchived(result) => {
+                if let Err(error) = result {
+                    self.history_error = Some(error);
+                }
+                Task::batch([
+                    self.load_history_page(self.history_page),
+                    self.refresh_history_count(),
+                ])
+            }
         }
     }
 
     fn view(&self) -> Element<'_, Message> {
+        let tab_button = |tab: Tab, label: String| {
+            let style = if self.active_tab == tab {
+                button::primary
+            } else {
+                button::secondary
+            };
+            button(Text::new(label))
+                .style(style)
+                .on_press(Message::TabSelected(tab))
+        };
+
+        let tabs = Row::new()
+            .spacing(8)
+            .push(tab_button(
+                Tab::Downloads,
+                self.localizer.text("tab-download"),
+            ))
+            .push(tab_button(
+                Tab::History,
+                history_tab_label(&self.localizer.text("tab-history"), self.history_count),
+            ))
+            .push(tab_button(
+                Tab::Logs,
+                self.localizer.text("logs-panel-title"),
+            ));
+
+        let content = match self.active_tab {
+            Tab::Downloads => self.downloads_view(),
+            Tab::History => self.history_view(),
+            Tab::Logs => self.logs_view(),
+        };
+
+        Container::new(
+            Column::new()
+                .spacing(16)
+                .push(tabs)
+                .push(content)
+                .padding(16),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// Move newly recorded log lines into the Logs tab buffer.
+    fn drain_log_lines(&mut self) {
+        let Some(log_rx) = self.log_rx.as_mut() else {
+            return;
+        };
+        loop {
+            match log_rx.try_recv() {
+                Ok(line) => {
+                    if self.log_lines.len() == LOG_PANEL_CAPACITY {
+                        self.log_lines.pop_front();
+                    }
+                    self.log_lines.push_back(line);
+                }
+                // Skipped lines are gone; keep reading from the oldest buffered one.
+                Err(broadcast::error::TryRecvError::Lagged(_)) => {}
+                Err(_) => break,
+            }
+        }
+    }
+
+    fn logs_view(&self) -> Element<'_, Message> {
+        let lines = self
+            .log_lines
+            .iter()
+            .fold(Column::new().spacing(2), |column, line| {
+                column.push(Text::new(line.as_str()).size(12))
+            });
+        Scrollable::new(lines)
+            .height(Length::Fill)
+            .width(Length::Fill)
+            .into()
+    }
+
+    fn downloads_view(&self) -> Element<'_, Message> {
         let input_row = Row::new()
             .spacing(8)
             .push(
@@ -515,18 +1403,162 @@ impl AppState {
                     .on_press(Message::StartDownload),
             );
 
-        let mut column = Column::new().spacing(16).push(input_row);
+        let concurrency = self.config.download.effective_concurrency();
+        let mut concurrency_row = Row::new()
+            .spacing(8)
+            .align_y(Vertical::Center)
+            .push(Text::new(self.localizer.text("settings-concurrency")).size(12))
+            .push(
+                Slider::new(1..=MAX_CONCURRENCY as u8, concurrency as u8, |value| {
+                    Message::ConcurrencyChanged(value as usize)
+                })
+                .width(Length::Fixed(160.0)),
+            )
+            .push(Text::new(concurrency.to_string()).size(12))
+            .push(
+                button(Text::new(self.localizer.text("button-edit-config")).size(12))
+                    .on_press(Message::EditConfigRequested),
+            );
+        concurrency_row = concurrency_row
+            .push(
+                checkbox(
+                    self.localizer.text("settings-prefer-free-formats"),
+                    self.config.download.prefer_free_formats,
+                )
+                .text_size(12)
+                .on_toggle(Message::PreferFreeFormatsToggled),
+            )
+            .push(
+                checkbox(
+                    self.localizer.text("settings-force-overwrites"),
+                    self.config.advanced.force_overwrites,
+                )
+                .text_size(12)
+                .on_toggle(Message::ForceOverwritesToggled),
+            )
+            .push(
+                checkbox(
+                    self.localizer.text("settings-live-from-start"),
+                    self.config.advanced.live_from_start,
+                )
+                .text_size(12)
+                .on_toggle(Message::LiveFromStartToggled),
+            )
+            .push(Text::new(self.localizer.text("settings-language")).size(12))
+            .push(self.language_picker())
+            .push(Text::new(self.localizer.text("settings-geo-bypass")).size(12))
+            .push(self.geo_bypass_picker());
+        if let GeoBypassMode::ForceCountry(country) = &self.config.download.geo_bypass {
+            concurrency_row = concurrency_row.push(
+                TextInput::new(DEFAULT_GEO_BYPASS_COUNTRY, country)
+                    .size(12)
+                    .width(Length::Fixed(48.0))
+                    .on_input(Message::GeoBypassCountryChanged),
+            );
+        }
+        let age_limit = self
+            .config
+            .download
+            .age_limit
+            .map(|age| age.to_string())
+            .unwrap_or_default();
+        concurrency_row = concurrency_row
+            .push(Text::new(self.localizer.text("settings-age-limit")).size(12))
+            .push(
+                TextInput::new("", &age_limit)
+                    .size(12)
+                    .width(Length::Fixed(48.0))
+                    .on_input(Message::AgeLimitChanged),
+            );
+        let audio_bitrate = self
+            .config
+            .download
+            .audio_bitrate_kbps
+            .map(|kbps| kbps.to_string())
+            .unwrap_or_default();
+        concurrency_row = concurrency_row
+            .push(Text::new(self.localizer.text("settings-audio-bitrate")).size(12))
+            .push(
+                TextInput::new(
+                    &self.localizer.text("settings-audio-bitrate-best"),
+                    &audio_bitrate,
+                )
+                .size(12)
+                .width(Length::Fixed(56.0))
+                .on_input(Message::AudioBitrateChanged),
+            );
+        if cfg!(target_os = "windows") {
+            concurrency_row = concurrency_row.push(
+                checkbox(
+                    self.localizer.text("settings-windows-no-console"),
+                    self.config.advanced.windows_no_console,
+                )
+                .text_size(12)
+                .on_toggle(Message::WindowsNoConsoleToggled),
+            );
+        }
+
+        let mut column = Column::new().spacing(16);
+        if !self.notices.is_empty() {
+            let notices = self
+                .notices
+                .iter()
+                .fold(Column::new().spacing(4), |notices, notice| {
+                    notices.push(Text::new(notice.as_str()).size(12))
+                });
+            column = column.push(
+                Row::new()
+                    .spacing(8)
+                    .align_y(Vertical::Center)
+                    .push(notices.width(Length::Fill))
+                    .push(
+                        button(Text::new(self.localizer.text("button-dismiss")).size(12))
+                            .on_press(Message::NoticesDismissed),
+                    ),
+            );
+        }
+        column = column.push(input_row);
+        if let Some(thumbnail) = &self.url_thumbnail {
+            column = column.push(Image::new(thumbnail.clone()).height(Length::Fixed(90.0)));
+        }
+        if self.url_previously_downloaded {
+            column =
+                column.push(Text::new(self.localizer.text("url-previously-downloaded")).size(12));
+        }
+        column = column.push(concurrency_row);
 
         if let Some(error) = &self.url_error {
             column = column.push(Text::new(error.clone()));
+            if let Some(help) = self.url_error_help {
+                column = column.push(help_text(help, &self.localizer));
+            }
         }
 
-        column = column.push(Text::new(self.localizer.text("download-active")).size(16));
+        let mut active_header = Row::new()
+            .spacing(8)
+            .align_y(Vertical::Center)
+            .push(Text::new(self.localizer.text("download-active")).size(16));
+        let bytes_remaining = self.downloader.bytes_remaining_in_queue();
+        if bytes_remaining > 0 {
+            let size = format_bytes(bytes_remaining, &self.localizer);
+            active_header = active_header.push(
+                Text::new(
+                    self.localizer
+                        .text_with_args("download-bytes-remaining", &[("size", &size)]),
+                )
+                .size(12),
+            );
+        }
+        column = column.push(active_header);
 
         let mut jobs_list = Column::new().spacing(8);
         for id in &self.job_order {
             if let Some(job) = self.jobs.get(id) {
-                jobs_list = jobs_list.push(job.view(&self.localizer));
+                let logs_copied = self
+                    .copied_at
+                    .get(id)
+                    .is_some_and(|copied_at| copied_at.elapsed() < COPIED_NOTICE_DURATION);
+                jobs_list = jobs_list.push(job.view(&self.localizer, logs_copied));
             }
         }
 
@@ -536,16 +1568,317 @@ impl AppState {
             column = column.push(Scrollable::new(jobs_list).height(Length::Fill));
         }
 
-        Container::new(column.padding(16))
-            .width(Length::Fill)
-            .height(Length::Fill)
+        column.into()
+    }
+
+    fn history_view(&self) -> Element<'_, Message> {
+        if let Some(duplicates) = &self.history_duplicates {
+            return self.history_duplicates_view(duplicates);
+        }
+
+        let page_count = history_page_count(self.history_total, HISTORY_PAGE_SIZE);
+        let mut page_args = FluentArgs::new();
+        page_args.set("page", self.history_page + 1);
+        page_args.set("total", page_count);
+
+        let pager = Row::new()
+            .spacing(8)
+            .align_y(Vertical::Center)
+            .push(
+                button(Text::new(self.localizer.text("button-previous"))).on_press_maybe(
+                    (self.history_page > 0)
+                        .then(|| Message::HistoryPageRequested(self.history_page - 1)),
+                ),
+            )
+            .push(Text::new(self.localizer.format("history-page", Some(&page_args))).size(12))
+            .push(
+                button(Text::new(self.localizer.text("button-next"))).on_press_maybe(
+                    (self.history_page + 1 < page_count)
+                        .then(|| Message::HistoryPageRequested(self.history_page + 1)),
+                ),
+            )
+            .push(
+                button(Text::new(self.localizer.text("button-history-refresh")))
+                    .style(button::secondary)
+                    .on_press(Message::HistoryPageRequested(self.history_page)),
+            )
+            .push(
+                checkbox(
+                    self.localizer.text("history-show-archived"),
+                    self.history_show_archived,
+                )
+                .on_toggle(Message::HistoryShowArchivedToggled),
+            )
+            .push(self.history_format_picker())
+            .push(
+                button(Text::new(self.localizer.text("history-retry-failed")))
+                    .style(button::secondary)
+                    .on_press(Message::HistoryRetryFailedRequested),
+            )
+            .push(
+                button(Text::new(self.localizer.text("history-duplicates")))
+                    .style(button::secondary)
+                    .on_press(Message::HistoryDuplicatesRequested),
+            );
+
+        let mut column = Column::new().spacing(16).push(pager);
+
+        if let Some(error) = &self.history_error {
+            column = column.push(Text::new(error.clone()));
+        }
+
+        if !self.top_uploaders.is_empty() {
+            let card = self.top_uploaders.iter().fold(
+                Column::new()
+                    .spacing(4)
+                    .push(Text::new(self.localizer.text("history-top-uploaders")).size(14)),
+                |card, (uploader, count)| {
+                    let mut args = FluentArgs::new();
+                    args.set("uploader", uploader.as_str());
+                    args.set("count", format_number(*count as f64, 0));
+                    card.push(
+                        Text::new(self.localizer.format("history-uploader-entry", Some(&args)))
+                            .size(12),
+                    )
+                },
+            );
+            column = column.push(
+                Container::new(card)
+                    .padding(12)
+                    .width(Length::Fill)
+                    .style(container::rounded_box),
+            );
+        }
+
+        if self.history_entries.is_empty() {
+            column = column.push(Text::new(self.localizer.text("history-empty")));
+        } else {
+            let mut entries = Column::new().spacing(8);
+            for entry in &self.history_entries {
+                entries = entries.push(history_entry_view(
+                    entry,
+                    &self.localizer,
+                    self.config.advanced.download_archive.is_some(),
+                ));
+            }
+            column = column.push(Scrollable::new(entries).height(Length::Fill));
+        }
+
+        column.into()
+    }
+
+    fn history_format_picker(&self) -> Element<'_, Message> {
+        let all = FormatFilterOption {
+            format: None,
+            label: self.localizer.text("history-format-all"),
+        };
+        let mut options = vec![all.clone()];
+        options.extend(
+            self.history_formats
+                .iter()
+                .map(|format| FormatFilterOption {
+                    format: Some(*format),
+                    label: format.to_string(),
+                }),
+        );
+        let selected = options
+            .iter()
+            .find(|option| option.format == self.history_format_filter)
+            .cloned()
+            .unwrap_or(all);
+
+        pick_list(
+            options,
+            Some(selected),
+            Message::HistoryFormatFilterSelected,
+        )
+        .into()
+    }
+
+    fn language_picker(&self) -> Element<'_, Message> {
+        let options: Vec<_> = self
+            .localizer
+            .languages()
+            .iter()
+            .map(|lang| LanguageOption {
+                language: lang.to_string(),
+                label: self.localizer.language_label(lang),
+            })
+            .collect();
+        let current = self.localizer.current_language().to_string();
+        let selected = options
+            .iter()
+            .find(|option| option.language == current)
+            .cloned();
+
+        pick_list(options, selected, Message::LanguageSelected)
+            .text_size(12)
             .into()
     }
 
+    fn geo_bypass_picker(&self) -> Element<'_, Message> {
+        let country = match &self.config.download.geo_bypass {
+            GeoBypassMode::ForceCountry(country) => country.clone(),
+            _ => DEFAULT_GEO_BYPASS_COUNTRY.to_string(),
+        };
+        let options = vec![
+            GeoBypassOption {
+                mode: GeoBypassMode::None,
+                label: self.localizer.text("settings-geo-bypass-off"),
+            },
+            GeoBypassOption {
+                mode: GeoBypassMode::Auto,
+                label: self.localizer.text("settings-geo-bypass-auto"),
+            },
+            GeoBypassOption {
+                mode: GeoBypassMode::ForceCountry(country),
+                label: self.localizer.text("settings-geo-bypass-country"),
+            },
+        ];
+        let selected = options
+            .iter()
+            .find(|option| {
+                std::mem::discriminant(&option.mode)
+                    == std::mem::discriminant(&self.config.download.geo_bypass)
+            })
+            .cloned();
+
+        pick_list(options, selected, Message::GeoBypassSelected)
+            .text_size(12)
+            .into()
+    }
+
+    /// Changes the in-memory settings with `change` and hands the download
+    /// and advanced settings to the downloader. Invalid values, such as
+    /// incomplete country codes, are kept for editing but not applied.
+    fn apply_settings(&mut self, change: impl FnOnce(&mut Config)) -> Task<Message> {
+        change(&mut self.config);
+        if self.config.validate().is_err() {
+            return Task::none();
+        }
+        let downloader = self.downloader.clone();
+        let download = self.config.download.clone();
+        let advanced = self.config.advanced.clone();
+        Task::future(async move {
+            downloader.update_settings(download, advanced).await;
+        })
+        .discard()
+    }
+
+    fn history_duplicates_view<'a>(
+        &'a self,
+        duplicates: &'a [(String, usize)],
+    ) -> Element<'a, Message> {
+        let (back_message, back_label) = if self.history_duplicate_entries.is_some() {
+            (Message::HistoryDuplicateCleared, "history-duplicates")
+        } else {
+            (Message::HistoryDuplicatesClosed, "history-all")
+        };
+        let mut column = Column::new().spacing(16).push(
+            button(Text::new(self.localizer.text(back_label)))
+                .style(button::secondary)
+                .on_press(back_message),
+        );
+
+        if let Some(error) = &self.history_error {
+            column = column.push(Text::new(error.clone()));
+        }
+
+        if let Some((url, entries)) = &self.history_duplicate_entries {
+            let mut list = Column::new()
+                .spacing(8)
+                .push(Text::new(url.clone()).size(14));
+            for entry in entries {
+                list = list.push(history_entry_view(
+                    entry,
+                    &self.localizer,
+                    self.config.advanced.download_archive.is_some(),
+                ));
+            }
+            column = column.push(Scrollable::new(list).height(Length::Fill));
+        } else if duplicates.is_empty() {
+            column = column.push(Text::new(self.localizer.text("history-duplicates-empty")));
+        } else {
+            let mut list = Column::new().spacing(4);
+            for (url, count) in duplicates {
+                let label = self.localizer.text_with_args(
+                    "history-duplicate-entry",
+                    &[("url", url), ("count", &count.to_string())],
+                );
+                list = list.push(
+                    button(Text::new(label).size(12))
+                        .style(button::text)
+                        .on_press(Message::HistoryDuplicateSelected(url.clone())),
+                );
+            }
+            column = column.push(Scrollable::new(list).height(Length::Fill));
+        }
+
+        column.into()
+    }
+
+    fn load_top_uploaders(&self) -> Task<Message> {
+        let history = self.history.clone();
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || history.get_uploader_stats())
+                    .await
+                    .map_err(|err| err.to_string())?
+                    .map_err(|err| err.to_string())
+            },
+            Message::TopUploadersLoaded,
+        )
+    }
+
+    fn refresh_history_count(&self) -> Task<Message> {
+        let history = self.history.clone();
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || history.count())
+                    .await
+                    .map_err(|err| err.to_string())?
+                    .map_err(|err| err.to_string())
+            },
+            Message::HistoryCountLoaded,
+        )
+    }
+
+    fn load_history_formats(&self) -> Task<Message> {
+        let history = self.history.clone();
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || history.distinct_formats())
+                    .await
+                    .map_err(|err| err.to_string())?
+                    .map_err(|err| err.to_string())
+            },
+            Message::HistoryFormatsLoaded,
+        )
+    }
+
+    fn load_history_page(&self, page: usize) -> Task<Message> {
+        let history = self.history.clone();
+        let query = HistoryQuery::new()
+            .include_archived(self.history_show_archived)
+            .format(self.history_format_filter);
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    history.page_with(page, HISTORY_PAGE_SIZE, &query)
+                })
+                .await
+                .map_err(|err| err.to_string())?
+                .map_err(|err| err.to_string())
+            },
+            move |result| Message::HistoryPageLoaded(page, result),
+        )
+    }
+
     fn start_download(&mut self) -> Task<Message> {
         let url = self.url_input.trim();
         if url.is_empty() {
             self.url_error = Some(self.localizer.text("error-invalid-url"));
+            self.url_error_help = None;
             return Task::none();
         }
 
@@ -555,11 +1888,16 @@ impl AppState {
     }
 }
 
-async fn async_initialize(config: Config) -> Result<AppInit, String> {
+async fn async_initialize(
+    config: Config,
+    history_path: Option<PathBuf>,
+    mut report: impl FnMut(InitStep),
+) -> Result<AppInit, String> {
     // Check if yt-dlp is available (Homebrew installation expected)
     use space_downloader_core::dependency::check_dependencies;
 
-    let deps = check_dependencies(&config.advanced)
+    report(InitStep::CheckingDependencies);
+    let deps = check_dependencies(&config.advanced, false)
         .await
         .map_err(|err| format!("Failed to check dependencies: {}", err))?;
 
@@ -570,17 +1908,52 @@ async fn async_initialize(config: Config) -> Result<AppInit, String> {
     }
 
     // Continue with normal initialization
-    let history = HistoryRepository::open(None).map_err(|err| err.to_string())?;
-    let downloader = Arc::new(DownloaderService::new(config.clone(), history));
+    report(InitStep::OpeningHistory);
+    let history_path = history_path.unwrap_or_else(HistoryRepository::default_path);
+    // Progress files live next to the history database.
+    let in_progress_dir = history_path.with_file_name("in_progress");
+    let interrupted = JobProgress::take_interrupted(&in_progress_dir);
+    let history =
+        HistoryRepository::open_or_recover(history_path).map_err(|err| err.to_string())?;
+    // Unfinished rows of instances that have exited are from a run that crashed.
+    let interrupted_count = history.cleanup_running().unwrap_or_else(|error| {
+        tracing::warn!("Failed to mark interrupted downloads as failed: {}", error);
+        0
+    });
+    let downloader = DownloaderService::builder()
+        .config(config.clone())
+        .history(history.clone())
+        .in_progress_dir(in_progress_dir)
+        .build()
+        .map(Arc::new)
+        .map_err(|err| err.to_string())?;
+
+    report(InitStep::InitializingLogger);
     let log_manager = initialize_logger(&config.logging).map_err(|err| err.to_string())?;
+    for note in deps.setting_notes(&config.download) {
+        tracing::warn!("{note}");
+    }
 
     Ok(AppInit {
         downloader,
+        history,
         config,
         log_manager,
+        interrupted,
+        interrupted_count,
     })
 }
 
+fn interrupted_notice(progress: &JobProgress, localizer: &Localizer) -> String {
+    match progress.percent {
+        Some(percent) => localizer.text_with_args(
+            "notice-interrupted",
+            &[("percent", &format!("{percent:.0}"))],
+        ),
+        None => localizer.text("notice-interrupted-unknown"),
+    }
+}
+
 fn initialize_logger(
     settings: &space_downloader_core::config::LogSettings,
 ) -> std::io::Result<Option<LogManager>> {
@@ -605,6 +1978,7 @@ fn build_download_request(config: &Config, url: String) -> DownloadRequest {
 
     request.extra_args = config.advanced.extra_args.clone();
     request.cookie_file = config.advanced.cookie_file.clone();
+    request.archive_file = config.advanced.download_archive.clone();
     request
 }
 
@@ -621,6 +1995,7 @@ async fn queue_download(
 
 fn format_status(status: JobStatus, localizer: &Localizer) -> String {
     let key = match status {
+        JobStatus::Scheduled => "status-scheduled",
         JobStatus::Queued => "status-queued",
         JobStatus::Running => "status-running",
         JobStatus::Succeeded => "status-succeeded",
@@ -630,31 +2005,151 @@ fn format_status(status: JobStatus, localizer: &Localizer) -> String {
     localizer.text(key)
 }
 
-fn format_progress(progress: &ProgressSnapshot) -> Option<String> {
+/// `force_retry` shows the button that downloads an entry again despite the
+/// download archive, which only makes sense while one is in use.
+fn history_entry_view<'a>(
+    entry: &DownloadHistoryEntry,
+    localizer: &Localizer,
+    force_retry: bool,
+) -> Element<'a, Message> {
+    let heading = entry.title.clone().unwrap_or_else(|| entry.url.clone());
+    let mut details = format!(
+        "{} • {} • {}",
+        format_status(entry.status, localizer),
+        entry.format,
+        entry.started_at.format("%Y-%m-%d %H:%M UTC")
+    );
+    for (key, count) in [
+        ("history-views", entry.view_count),
+        ("history-likes", entry.like_count),
+    ] {
+        if let Some(count) = count {
+            let mut args = FluentArgs::new();
+            args.set("count", format_number(count as f64, 0));
+            details.push_str(" • ");
+            details.push_str(&localizer.format(key, Some(&args)));
+        }
+    }
+
+    let mut column = Column::new()
+        .spacing(4)
+        .push(Text::new(heading).size(14))
+        .push(Text::new(details).size(12));
+
+    if let Some(source_url) = entry
+        .source_url
+        .as_deref()
+        .filter(|source_url| *source_url != entry.url)
+    {
+        column = column.push(
+            Text::new(localizer.text_with_args("history-source-url", &[("url", source_url)]))
+                .size(12),
+        );
+    }
+
+    if let Some(path) = &entry.file_path {
+        column = column.push(Text::new(path.to_string_lossy().to_string()).size(12));
+    } else if let Some(message) = &entry.error_message {
+        column = column.push(Text::new(message.clone()).size(12));
+    }
+
+    let archive_label = if entry.archived {
+        "history-unarchive"
+    } else {
+        "history-archive"
+    };
+    let mut actions = Row::new().spacing(8).push(
+        button(Text::new(localizer.text(archive_label)).size(12))
+            .style(button::secondary)
+            .on_press(Message::HistoryArchiveRequested(
+                entry.job_id,
+                !entry.archived,
+            )),
+    );
+    if force_retry {
+        actions = actions.push(
+            button(Text::new(localizer.text("history-force-retry")).size(12))
+                .style(button::secondary)
+                .on_press(Message::HistoryForceRetryRequested(
+                    entry.job_id,
+                    entry.url.clone(),
+                )),
+        );
+    }
+    column = column.push(actions);
+
+    Container::new(column)
+        .padding(12)
+        .width(Length::Fill)
+        .into()
+}
+
+fn history_tab_label(label: &str, count: usize) -> String {
+    match count {
+        0 => label.to_string(),
+        1..=99 => format!("{label} ({count})"),
+        _ => format!("{label} (99+)"),
+    }
+}
+
+/// Shorten a description for the collapsed job row. The flag reports whether
+/// anything was cut off.
+fn preview_description(description: &str) -> (String, bool) {
+    match description.char_indices().nth(DESCRIPTION_PREVIEW_CHARS) {
+        Some((index, _)) => (format!("{}…", &description[..index]), true),
+        None => (description.to_string(), false),
+    }
+}
+
+fn history_page_count(total: usize, per_page: usize) -> usize {
+    total.div_ceil(per_page.max(1)).max(1)
+}
+
+fn format_progress(progress: &ProgressSnapshot, localizer: &Localizer) -> Option<String> {
     let mut parts = Vec::new();
 
     if let Some(percent) = progress.percent {
-        parts.push(format!("{percent:.1}%"));
+        let mut args = FluentArgs::new();
+        args.set("percent", format_number(percent as f64, 1));
+        parts.push(localizer.format("progress-percent", Some(&args)));
+    } else if let Some((current, total)) = progress.fragments {
+        parts.push(localizer.text_with_args(
+            "progress-fragment",
+            &[
+                ("current", &current.to_string()),
+                ("total", &total.to_string()),
+            ],
+        ));
     }
 
     if let Some(downloaded) = progress.downloaded_bytes {
+        let downloaded = format_bytes(downloaded, localizer);
         if let Some(total) = progress.total_bytes {
-            parts.push(format!(
-                "{}/{}",
-                format_bytes(downloaded),
-                format_bytes(total)
+            parts.push(localizer.text_with_args(
+                "progress-size",
+                &[
+                    ("downloaded", &downloaded),
+                    ("total", &format_bytes(total, localizer)),
+                ],
             ));
         } else {
-            parts.push(format!("{} downloaded", format_bytes(downloaded)));
+            parts.push(
+                localizer.text_with_args("progress-downloaded", &[("downloaded", &downloaded)]),
+            );
         }
     }
 
     if let Some(speed) = progress.speed_bytes_per_sec {
-        parts.push(format!("{} /s", format_bytes(speed)));
+        parts.push(localizer.text_with_args(
+            "progress-speed",
+            &[("speed", &format_bytes(speed, localizer))],
+        ));
     }
 
     if let Some(eta) = progress.eta {
-        parts.push(format!("ETA {}", format_eta(eta)));
+        parts.push(
+            localizer.text_with_args("progress-eta", &[("eta", &format_eta(eta, localizer))]),
+        );
     }
 
     if parts.is_empty() {
@@ -664,32 +2159,98 @@ fn format_progress(progress: &ProgressSnapshot) -> Option<String> {
     }
 }
 
-fn format_bytes(bytes: u64) -> String {
+/// Below this speed the progress bar is drawn red.
+const SLOW_SPEED_BYTES_PER_SEC: u64 = 100 * 1024;
+/// Above this speed the progress bar is drawn green.
+const FAST_SPEED_BYTES_PER_SEC: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProgressTone {
+    Default,
+    Slow,
+    Moderate,
+    Fast,
+    Inactive,
+}
+
+fn progress_tone(status: JobStatus, speed_bytes_per_sec: Option<u64>) -> ProgressTone {
+    if matches!(status, JobStatus::Failed | JobStatus::Canceled) {
+        return ProgressTone::Inactive;
+    }
+
+    match speed_bytes_per_sec {
+        None => ProgressTone::Default,
+        Some(speed) if speed < SLOW_SPEED_BYTES_PER_SEC => ProgressTone::Slow,
+        Some(speed) if speed <= FAST_SPEED_BYTES_PER_SEC => ProgressTone::Moderate,
+        Some(_) => ProgressTone::Fast,
+    }
+}
+
+fn progress_bar_style(theme: &Theme, tone: ProgressTone) -> progress_bar::Style {
+    let bar = match tone {
+        ProgressTone::Default => return progress_bar::primary(theme),
+        ProgressTone::Slow => Color::from_rgb8(0xD9, 0x3B, 0x3B),
+        ProgressTone::Moderate => Color::from_rgb8(0xF0, 0x8C, 0x1A),
+        ProgressTone::Fast => Color::from_rgb8(0x2E, 0xA0, 0x4A),
+        ProgressTone::Inactive => Color::from_rgb8(0x9E, 0x9E, 0x9E),
+    };
+
+    progress_bar::Style {
+        bar: bar.into(),
+        ..progress_bar::primary(theme)
+    }
+}
+
+/// `m:ss`, or `h:mm:ss` from one hour on.
+fn format_timestamp(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+fn format_bytes(bytes: u64, localizer: &Localizer) -> String {
     const KB: f64 = 1024.0;
     const MB: f64 = KB * 1024.0;
     const GB: f64 = MB * 1024.0;
 
     let value = bytes as f64;
+    let mut args = FluentArgs::new();
 
-    if value >= GB {
-        format!("{:.2} GB", value / GB)
+    let key = if value >= GB {
+        args.set("value", format_number(value / GB, 2));
+        "size-gb"
     } else if value >= MB {
-        format!("{:.2} MB", value / MB)
+        args.set("value", format_number(value / MB, 2));
+        "size-mb"
     } else if value >= KB {
-        format!("{:.2} KB", value / KB)
+        args.set("value", format_number(value / KB, 2));
+        "size-kb"
     } else {
-        format!("{bytes} B")
-    }
+        args.set("value", bytes);
+        "size-bytes"
+    };
+    localizer.format(key, Some(&args))
 }
 
-fn format_eta(duration: Duration) -> String {
+fn format_eta(duration: Duration, localizer: &Localizer) -> String {
     let secs = duration.as_secs();
     let minutes = secs / 60;
     let seconds = secs % 60;
     if minutes > 0 {
-        format!("{}m {:02}s", minutes, seconds)
+        localizer.text_with_args(
+            "eta-minutes",
+            &[
+                ("minutes", &minutes.to_string()),
+                ("seconds", &format!("{seconds:02}")),
+            ],
+        )
     } else {
-        format!("{}s", seconds)
+        let mut args = FluentArgs::new();
+        args.set("seconds", seconds);
+        localizer.format("eta-seconds", Some(&args))
     }
 }
 
@@ -711,3 +2272,215 @@ fn open_folder_in_explorer(path: &std::path::Path) -> std::io::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn initialization_reports_steps_in_order() {
+        use space_downloader_core::testing::MockYtDlp;
+
+        let dir = tempfile::tempdir().unwrap();
+        let yt_dlp = MockYtDlp::builder().stdout("2025.01.01").build().unwrap();
+
+        let mut config = Config::default();
+        config.advanced.yt_dlp_path = yt_dlp.path();
+        config.logging.enabled = false;
+
+        let mut steps = Vec::new();
+        let result = async_initialize(config, Some(dir.path().join("history.db")), |step| {
+            steps.push(step)
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(
+            steps,
+            vec![
+                InitStep::CheckingDependencies,
+                InitStep::OpeningHistory,
+                InitStep::InitializingLogger,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn completion_time_uses_smoothed_eta() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.general.output_dir = dir.path().to_path_buf();
+        config.advanced.yt_dlp_path = dir.path().join("missing-yt-dlp");
+        let service = DownloaderService::new_without_history(config);
+        let handle = service
+            .queue(DownloadRequest::new(
+                "https://x.com/i/spaces/1".to_string(),
+                PathBuf::new(),
+                AudioFormat::M4a,
+            ))
+            .await
+            .unwrap();
+        let mut tracker = JobTracker::new(handle);
+        assert!(tracker.estimated_completion_at().is_none());
+
+        tracker.last_progress = Some(ProgressSnapshot {
+            eta: Some(Duration::from_secs(100)),
+            ..ProgressSnapshot::default()
+        });
+        tracker.update_smoothed_eta();
+        assert!(tracker.estimated_completion_at().unwrap() > SystemTime::now());
+
+        tracker.last_progress = Some(ProgressSnapshot {
+            eta: Some(Duration::from_secs(200)),
+            ..ProgressSnapshot::default()
+        });
+        tracker.update_smoothed_eta();
+        assert_eq!(tracker.smoothed_eta, Some(Duration::from_secs(130)));
+    }
+
+    #[test]
+    fn interrupted_download_notice_shows_percent() {
+        let mut progress = JobProgress {
+            id: Uuid::new_v4(),
+            url: "https://x.com/i/spaces/1".to_string(),
+            percent: Some(66.7),
+            downloaded_bytes: None,
+            eta: None,
+        };
+        let localizer = Localizer::new("en-US");
+        assert_eq!(
+            interrupted_notice(&progress, &localizer),
+            "A previous download was interrupted at 67%"
+        );
+        progress.percent = None;
+        assert_eq!(
+            interrupted_notice(&progress, &localizer),
+            "A previous download was interrupted"
+        );
+    }
+
+    #[test]
+    fn download_requests_use_the_configured_archive() {
+        let mut config = Config::default();
+        let url = "https://x.com/i/spaces/1".to_string();
+        assert_eq!(
+            build_download_request(&config, url.clone()).archive_file,
+            None
+        );
+
+        config.advanced.download_archive = Some(PathBuf::from("archive.txt"));
+        assert_eq!(
+            build_download_request(&config, url).archive_file,
+            Some(PathBuf::from("archive.txt"))
+        );
+    }
+
+    #[test]
+    fn history_tab_badge_caps_large_counts() {
+        assert_eq!(history_tab_label("History", 0), "History");
+        assert_eq!(history_tab_label("History", 42), "History (42)");
+        assert_eq!(history_tab_label("History", 99), "History (99)");
+        assert_eq!(history_tab_label("History", 142), "History (99+)");
+    }
+
+    #[test]
+    fn description_preview_truncates_long_text() {
+        assert_eq!(preview_description("short"), ("short".to_string(), false));
+
+        let long = "x".repeat(DESCRIPTION_PREVIEW_CHARS + 1);
+        let (preview, truncated) = preview_description(&long);
+        assert!(truncated);
+        assert_eq!(preview.chars().count(), DESCRIPTION_PREVIEW_CHARS + 1);
+        assert!(preview.ends_with('…'));
+    }
+
+    #[test]
+    fn timestamps_show_hours_only_when_needed() {
+        assert_eq!(format_timestamp(5), "0:05");
+        assert_eq!(format_timestamp(65), "1:05");
+        assert_eq!(format_timestamp(3_725), "1:02:05");
+    }
+
+    #[test]
+    fn fragment_progress_is_shown_without_percent() {
+        let progress = ProgressSnapshot {
+            fragments: Some((5, 20)),
+            ..ProgressSnapshot::default()
+        };
+
+        assert_eq!(
+            format_progress(&progress, &Localizer::new("en-US")).unwrap(),
+            "Fragment 5/20"
+        );
+        assert_eq!(
+            format_progress(&progress, &Localizer::new("ja-JP")).unwrap(),
+            "フラグメント 5/20"
+        );
+    }
+
+    #[test]
+    fn progress_text_is_localized() {
+        let progress = ProgressSnapshot {
+            percent: Some(42.0),
+            downloaded_bytes: Some(3 * 1024 * 1024 / 2),
+            total_bytes: Some(3 * 1024 * 1024),
+            speed_bytes_per_sec: Some(512),
+            eta: Some(Duration::from_secs(65)),
+            fragments: None,
+        };
+
+        assert_eq!(
+            format_progress(&progress, &Localizer::new("en-US")).unwrap(),
+            "42.0% • 1.50 MB / 3.00 MB • 512 B/s • ETA 1m 05s"
+        );
+        assert_eq!(
+            format_progress(&progress, &Localizer::new("ja-JP")).unwrap(),
+            "42.0% • 1.50 MB / 3.00 MB • 512 B/秒 • 残り 1分05秒"
+        );
+    }
+
+    #[test]
+    fn history_page_count_rounds_up() {
+        assert_eq!(history_page_count(0, 20), 1);
+        assert_eq!(history_page_count(20, 20), 1);
+        assert_eq!(history_page_count(21, 20), 2);
+        assert_eq!(history_page_count(25, 10), 3);
+    }
+
+    #[test]
+    fn progress_tone_follows_speed_boundaries() {
+        let running = JobStatus::Running;
+        assert_eq!(progress_tone(running, None), ProgressTone::Default);
+        assert_eq!(progress_tone(running, Some(0)), ProgressTone::Slow);
+        assert_eq!(
+            progress_tone(running, Some(SLOW_SPEED_BYTES_PER_SEC - 1)),
+            ProgressTone::Slow
+        );
+        assert_eq!(
+            progress_tone(running, Some(SLOW_SPEED_BYTES_PER_SEC)),
+            ProgressTone::Moderate
+        );
+        assert_eq!(
+            progress_tone(running, Some(FAST_SPEED_BYTES_PER_SEC)),
+            ProgressTone::Moderate
+        );
+        assert_eq!(
+            progress_tone(running, Some(FAST_SPEED_BYTES_PER_SEC + 1)),
+            ProgressTone::Fast
+        );
+    }
+
+    #[test]
+    fn progress_tone_is_grey_for_finished_failures() {
+        let fast = Some(FAST_SPEED_BYTES_PER_SEC * 10);
+        assert_eq!(
+            progress_tone(JobStatus::Failed, fast),
+            ProgressTone::Inactive
+        );
+        assert_eq!(
+            progress_tone(JobStatus::Canceled, fast),
+            ProgressTone::Inactive
+        );
+    }
+}